@@ -0,0 +1,112 @@
+#![feature(test)]
+extern crate test;
+
+use std::rc::Rc;
+
+use im::Vector;
+use reactive_graph::prelude::*;
+use test::Bencher;
+
+const CHILD_COUNT: Uid = 5_000;
+
+// A single parent node (1) with CHILD_COUNT children, each rendered through a "child" edge
+// Every other child also references node 2, so node 2 has CHILD_COUNT incoming edges
+fn set_up_wide_node() -> Rc<UseRoutableReturn<String, String, String>> {
+    use_routable_store::<String, String, String>(
+        None::<Vector<AllowedRenderEdgeSpecifier<String>>>,
+    );
+    let routable = use_routable::<String, String, String>();
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .set_data("node1".to_string());
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+
+    let blueprint = BuildBlueprint::new();
+    for id in 2..CHILD_COUNT + 2 {
+        blueprint
+            .start_with_new_node()
+            .set_id(id)
+            .set_temp_id(id)
+            .set_data(format!("node{}", id))
+            .add_edge_existing(EdgeDir::Recv, "child".to_string(), 1, |blue_existing| {
+                blue_existing
+            });
+    }
+    for id in 3..CHILD_COUNT + 2 {
+        blueprint.start_with_update_node(id).add_edge_existing(
+            EdgeDir::Emit,
+            "reference".to_string(),
+            2,
+            |blue_existing| blue_existing,
+        );
+    }
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    routable
+}
+
+fn child_by_target() -> EdgeFinder<String, String, String> {
+    EdgeFinder::new()
+        .edge_type("child".to_string())
+        .target(CHILD_COUNT / 2)
+}
+
+fn render_edge() -> EdgeFinder<String, String, String> {
+    EdgeFinder::new().render_info(Some(EdgeDir::Recv))
+}
+
+// The index only looks at the finder's own fields, so a finder wrapped in a combinator has to visit every edge.
+// Runs the same query as the indexed lookup, for comparison.
+fn unindexed(
+    edge_finder: EdgeFinder<String, String, String>,
+) -> EdgeFinder<String, String, String> {
+    EdgeFinder::new().and(edge_finder)
+}
+
+#[bench]
+fn edge_by_target(b: &mut Bencher) {
+    let routable = set_up_wide_node();
+    let parent = routable.get_node(&1).unwrap();
+    let edge_finder = child_by_target();
+    b.iter(|| parent.search_for_edge(&edge_finder).unwrap());
+}
+
+#[bench]
+fn edge_by_target_unindexed(b: &mut Bencher) {
+    let routable = set_up_wide_node();
+    let parent = routable.get_node(&1).unwrap();
+    let edge_finder = unindexed(child_by_target());
+    b.iter(|| parent.search_for_edge(&edge_finder).unwrap());
+}
+
+#[bench]
+fn edge_by_render_info(b: &mut Bencher) {
+    let routable = set_up_wide_node();
+    let referenced_child = routable.get_node(&2).unwrap();
+    let edge_finder = render_edge();
+    b.iter(|| referenced_child.search_for_edge(&edge_finder).unwrap());
+}
+
+#[bench]
+fn edge_by_render_info_unindexed(b: &mut Bencher) {
+    let routable = set_up_wide_node();
+    let referenced_child = routable.get_node(&2).unwrap();
+    let edge_finder = unindexed(render_edge());
+    b.iter(|| referenced_child.search_for_edge(&edge_finder).unwrap());
+}
+
+// Has to visit every child edge however the finder is written
+#[bench]
+fn all_children(b: &mut Bencher) {
+    let routable = set_up_wide_node();
+    let parent = routable.get_node(&1).unwrap();
+    let edge_finder = EdgeFinder::new().edge_type("child".to_string()).match_all();
+    b.iter(|| parent.search_for_edge(&edge_finder).unwrap());
+}
@@ -0,0 +1,178 @@
+#![feature(test)]
+extern crate test;
+
+use std::rc::Rc;
+
+use im::Vector;
+use reactive_graph::prelude::*;
+use test::Bencher;
+
+const NODE_COUNT: Uid = 10_000;
+const BRANCHING_FACTOR: Uid = 4;
+
+// A tree of NODE_COUNT nodes where every node emits a "child" edge to each of its children
+// Node 1 is the root, and the children of node n are (n - 1) * BRANCHING_FACTOR + 2 onwards
+fn set_up_tree() -> Rc<UseRoutableReturn<String, String, String>> {
+    use_routable_store::<String, String, String>(
+        None::<Vector<AllowedRenderEdgeSpecifier<String>>>,
+    );
+    let routable = use_routable::<String, String, String>();
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .set_data("node1".to_string());
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+
+    let mut layer_start = 2;
+    let mut layer_size = BRANCHING_FACTOR;
+    while layer_start <= NODE_COUNT {
+        let layer_end = (layer_start + layer_size).min(NODE_COUNT + 1);
+        let blueprint = BuildBlueprint::new();
+        for id in layer_start..layer_end {
+            blueprint
+                .start_with_new_node()
+                .set_id(id)
+                .set_temp_id(id)
+                .set_data(format!("node{}", id))
+                .add_edge_existing(
+                    EdgeDir::Recv,
+                    "child".to_string(),
+                    (id - 2) / BRANCHING_FACTOR + 1,
+                    |blue_existing| blue_existing,
+                );
+        }
+        routable
+            .process_blueprint(blueprint, "action_data".to_string())
+            .unwrap();
+        layer_start = layer_end;
+        layer_size *= BRANCHING_FACTOR;
+    }
+    routable
+}
+
+const LAYER_WIDTH: Uid = 100;
+
+// Node 1 emits a "child" edge to each node of the first of several layers of LAYER_WIDTH nodes, NODE_COUNT in total.
+// Node p of a layer emits a "child" edge to nodes p and p + 1 of the next layer, so the number of routes to a node
+// doubles with every layer. Node 1 also emits a "marker" edge to node 2, the only one any node receives.
+fn set_up_layered_dag() -> Rc<UseRoutableReturn<String, String, String>> {
+    use_routable_store::<String, String, String>(
+        None::<Vector<AllowedRenderEdgeSpecifier<String>>>,
+    );
+    let routable = use_routable::<String, String, String>();
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .set_data("node1".to_string());
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+
+    for layer_start in (2..=NODE_COUNT).step_by(LAYER_WIDTH as usize) {
+        let blueprint = BuildBlueprint::new();
+        for id in layer_start..(layer_start + LAYER_WIDTH).min(NODE_COUNT + 1) {
+            let position = id - layer_start;
+            let parents = match layer_start {
+                2 => vec![1],
+                _ => vec![
+                    layer_start - LAYER_WIDTH + position,
+                    layer_start - LAYER_WIDTH + (position + LAYER_WIDTH - 1) % LAYER_WIDTH,
+                ],
+            };
+            let mut new_node = blueprint
+                .start_with_new_node()
+                .set_id(id)
+                .set_temp_id(id)
+                .set_data(format!("node{}", id));
+            for parent in parents {
+                new_node = new_node.add_edge_existing(
+                    EdgeDir::Recv,
+                    "child".to_string(),
+                    parent,
+                    |blue_existing| blue_existing,
+                );
+            }
+            if id == 2 {
+                new_node.add_edge_existing(
+                    EdgeDir::Recv,
+                    "marker".to_string(),
+                    1,
+                    |blue_existing| blue_existing,
+                );
+            }
+        }
+        routable
+            .process_blueprint(blueprint, "action_data".to_string())
+            .unwrap();
+    }
+    routable
+}
+
+fn children() -> EdgeFinder<String, String, String> {
+    EdgeFinder::new()
+        .dir(EdgeDir::Emit)
+        .edge_type("child".to_string())
+        .match_all()
+}
+
+fn parents() -> EdgeFinder<String, String, String> {
+    EdgeFinder::new()
+        .dir(EdgeDir::Recv)
+        .edge_type("child".to_string())
+        .match_all()
+}
+
+#[bench]
+fn all_descendants(b: &mut Bencher) {
+    let routable = set_up_tree();
+    let traversal = routable
+        .traverse_search(1)
+        .add_step(children(), TraversalCount::AtLeastInclusive(1));
+    b.iter(|| traversal.execute().unwrap());
+}
+
+#[bench]
+fn fixed_depth_descendants(b: &mut Bencher) {
+    let routable = set_up_tree();
+    let traversal = routable
+        .traverse_search(1)
+        .add_step(children(), TraversalCount::Exactly(6));
+    b.iter(|| traversal.execute().unwrap());
+}
+
+// Every descendant shares its parent with its siblings, so the second step is repeated for the same nodes
+#[bench]
+fn descendants_then_parents(b: &mut Bencher) {
+    let routable = set_up_tree();
+    let traversal = routable
+        .traverse_search(1)
+        .add_step(children(), TraversalCount::AtLeastInclusive(1))
+        .add_step(parents(), TraversalCount::Exactly(1));
+    b.iter(|| traversal.execute().unwrap());
+}
+
+// Every descendant is searched for a received marker edge, which only node 2 has.
+// The routes to each node merge, so the failed routes can only be searched once per node if they are shared.
+#[bench]
+fn layered_descendants_then_marker(b: &mut Bencher) {
+    let routable = set_up_layered_dag();
+    let traversal = routable
+        .traverse_search(1)
+        .add_step(children(), TraversalCount::AtLeastInclusive(1))
+        .add_step(
+            EdgeFinder::new()
+                .dir(EdgeDir::Recv)
+                .edge_type("marker".to_string())
+                .match_all(),
+            TraversalCount::Exactly(1),
+        );
+    b.iter(|| traversal.execute().unwrap());
+}
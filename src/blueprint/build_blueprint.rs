@@ -3,68 +3,235 @@ use std::cell::RefCell;
 use im::{hashset, vector, HashMap, HashSet, Vector};
 use leptos::{logging::log, *};
 
-use crate::graph::view_graph::ViewGraph;
+use crate::graph::{graph_schema::OnDelete, view_graph::ViewGraph};
 use crate::prelude::*;
 
 use super::{
     delete_node::DeleteNode,
-    finalized_update_node::{FinalizedUpdateNode, UpdateNodeReplacementData},
+    finalized_update_node::{EdgePayloadUpdate, FinalizedUpdateNode, UpdateNodeReplacementData},
     new_node::{NewNode, TempId},
     update_node::UpdateNode,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 
-pub struct AllowedRenderEdgeSpecifier<E: GraphTraits> {
+pub struct AllowedRenderEdgeSpecifier<E: EdgeTraits, L: GraphTraits = String> {
     pub edge_type: E,
     pub dir: EdgeDir,
+    // Further narrows which edges of the type and direction are allowed, including finders combined with or/and/not
+    // Bound at finalize against the graph and its PredicateRegistry
+    pub finder: Option<Box<EdgeFinderSpec<E, L>>>,
 }
 
+impl<E: EdgeTraits, L: GraphTraits> AllowedRenderEdgeSpecifier<E, L> {
+    pub fn new(edge_type: E, dir: EdgeDir) -> Self {
+        Self {
+            edge_type,
+            dir,
+            finder: None,
+        }
+    }
+
+    pub fn finder(&self, finder: EdgeFinderSpec<E, L>) -> Self {
+        Self {
+            finder: Some(Box::new(finder)),
+            ..self.clone()
+        }
+    }
+
+    pub fn to_edge_finder<T: GraphTraits, A: GraphTraits>(
+        &self,
+        registry: &PredicateRegistry<T, E, A, L>,
+        get_node_closure: GetNodeClosure<T, E, A, L>,
+    ) -> Result<EdgeFinder<T, E, A, L>, GraphError> {
+        let edge_finder = EdgeFinder::new()
+            .edge_type(self.edge_type.clone())
+            .dir(self.dir.clone());
+        Ok(match &self.finder {
+            Some(finder) => edge_finder.and(finder.bind(registry, get_node_closure)?),
+            None => edge_finder,
+        })
+    }
+}
+
+// Where to place an edge among the edges of the same type and direction on its host
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+enum EdgePosition<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> {
+    Before(EdgeFinder<T, E, A, L>),
+    After(EdgeFinder<T, E, A, L>),
+    Index(usize),
+}
+
+// An edge finder along with where to place the edge it matches
+type EdgePositionRequest<T, E, A, L> = (EdgeFinder<T, E, A, L>, EdgePosition<T, E, A, L>);
+
+// An edge finder along with the new payload for the edges it matches
+type PayloadUpdateFinder<T, E, A, L> = (EdgeFinder<T, E, A, L>, Option<<E as EdgeTraits>::Payload>);
+
+// The edge as it is in the graph along with its new payload, by edge id
+type PayloadUpdates<E> = HashMap<Uid, (EdgeDescriptor<E>, Option<<E as EdgeTraits>::Payload>)>;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub struct BuildBlueprint<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    new_nodes: RefCell<HashMap<Uid, NewNode<T, E>>>,
-    update_nodes: RefCell<HashMap<Uid, UpdateNode<T, E>>>,
+pub struct BuildBlueprint<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    new_nodes: RefCell<HashMap<Uid, NewNode<T, E, L>>>,
+    update_nodes: RefCell<HashMap<Uid, UpdateNode<T, E, L>>>,
     delete_nodes: RefCell<HashSet<Uid>>,
+    // Deleted nodes whose on-delete policies were already applied, as nodes can be added to delete_nodes after the first pass
+    policy_checked_nodes: RefCell<HashSet<Uid>>,
     // Should be from the perspective of the renderer -- the new node should be the target
     entry_edges: RefCell<HashSet<EdgeDescriptor<E>>>,
     // Bool represents whether the node is_new
     temp_edges: RefCell<HashSet<(EdgeDescriptor<E>, bool)>>,
-    remove_edge_finders: RefCell<HashSet<EdgeFinder<T, E, A>>>,
+    remove_edge_finders: RefCell<HashSet<EdgeFinder<T, E, A, L>>>,
+    // The new payload for every edge the finder matches
+    update_payload_finders: RefCell<HashSet<PayloadUpdateFinder<T, E, A, L>>>,
+    // Applied in the order they were requested, since each one can depend on the last
+    edge_positions: RefCell<Vector<EdgePositionRequest<T, E, A, L>>>,
+    // New edges without a position are appended in the order they were added
+    added_edge_ids: RefCell<Vector<Uid>>,
     removed_render_edges: RefCell<HashSet<EdgeDescriptor<E>>>,
     // Stored as NewNodes to facilitate the render path finding algorithm, but these nodes are actually existent.
     // The new edges represent all of the existing edges except for any that have been removed
-    displaced_nodes: RefCell<HashMap<Uid, NewNode<T, E>>>,
+    displaced_nodes: RefCell<HashMap<Uid, NewNode<T, E, L>>>,
     // Possible entry edges to the displaced nodes. Should be from the perspective of the renderer
     displaced_entry_edges: RefCell<HashSet<EdgeDescriptor<E>>>,
     pub temp_id_map: RefCell<HashMap<TempId, Uid>>,
     errors: RefCell<Vec<GraphError>>,
+    // Ids for the new nodes and edges. Without one, the graph's generator assigns them when the blueprint is finalized.
+    id_generator: Option<IdGenerator>,
+    // Stand-in ids handed out without a generator, in the order they were handed out
+    provisional_ids: RefCell<Vector<Uid>>,
 }
 
-impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> Default
-    for BuildBlueprint<T, E, A>
+impl<'a, 'b: 'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Default
+    for BuildBlueprint<T, E, A, L>
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<T, E, A> {
+impl<'a, 'b: 'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>
+    BuildBlueprint<T, E, A, L>
+{
     pub fn new() -> Self {
         Self {
             new_nodes: RefCell::new(HashMap::new()),
             update_nodes: RefCell::new(HashMap::new()),
             delete_nodes: RefCell::new(HashSet::new()),
+            policy_checked_nodes: RefCell::new(HashSet::new()),
             entry_edges: RefCell::new(HashSet::new()),
             temp_edges: RefCell::new(HashSet::new()),
             temp_id_map: RefCell::new(HashMap::new()),
             remove_edge_finders: RefCell::new(HashSet::new()),
+            update_payload_finders: RefCell::new(HashSet::new()),
+            edge_positions: RefCell::new(Vector::new()),
+            added_edge_ids: RefCell::new(Vector::new()),
             removed_render_edges: RefCell::new(HashSet::new()),
             displaced_nodes: RefCell::new(HashMap::new()),
             displaced_entry_edges: RefCell::new(HashSet::new()),
             errors: RefCell::new(Vec::new()),
+            id_generator: None,
+            provisional_ids: RefCell::new(Vector::new()),
+        }
+    }
+
+    // Takes ids from the given generator rather than the graph's
+    pub fn with_id_generator(id_generator: IdGenerator) -> Self {
+        Self {
+            id_generator: Some(id_generator),
+            ..Self::new()
         }
     }
 
+    fn next_id(&self) -> Uid {
+        match &self.id_generator {
+            Some(id_generator) => id_generator.next_id(),
+            None => {
+                let id = IdGenerator::random().next_id();
+                self.provisional_ids.borrow_mut().push_back(id);
+                id
+            }
+        }
+    }
+
+    // Replaces the provisional ids with ones from the graph's generator, in the order they were handed out.
+    // Ids which were replaced by set_id are skipped, so they don't use up the graph's ids.
+    pub(crate) fn assign_ids(&self, id_generator: &IdGenerator) {
+        let provisional_ids = self.provisional_ids.take();
+        if provisional_ids.is_empty() {
+            return;
+        }
+        let ids = {
+            let new_nodes = self.new_nodes.borrow();
+            let added_edge_ids = self.added_edge_ids.borrow();
+            provisional_ids
+                .into_iter()
+                .filter(|id| new_nodes.contains_key(id) || added_edge_ids.contains(id))
+                .map(|id| (id, id_generator.next_id()))
+                .collect::<HashMap<Uid, Uid>>()
+        };
+        let map = |id: Uid| ids.get(&id).copied().unwrap_or(id);
+        let map_edge = |edge: &EdgeDescriptor<E>| EdgeDescriptor {
+            id: map(edge.id),
+            host: map(edge.host),
+            target: map(edge.target),
+            ..edge.clone()
+        };
+        let map_edges = |edges: &HashSet<EdgeDescriptor<E>>| edges.iter().map(map_edge).collect();
+
+        self.new_nodes.replace_with(|new_nodes| {
+            new_nodes
+                .values()
+                .map(|node| {
+                    let id = map(node.id);
+                    let node = NewNode {
+                        id,
+                        add_edges: map_edges(&node.add_edges),
+                        ..node.clone()
+                    };
+                    (id, node)
+                })
+                .collect()
+        });
+        self.update_nodes.replace_with(|update_nodes| {
+            update_nodes
+                .values()
+                .map(|node| {
+                    let id = map(node.id);
+                    let node = UpdateNode {
+                        id,
+                        add_edges: node.add_edges.as_ref().map(map_edges),
+                        remove_edges: node.remove_edges.as_ref().map(map_edges),
+                        ..node.clone()
+                    };
+                    (id, node)
+                })
+                .collect()
+        });
+        self.delete_nodes
+            .replace_with(|delete_nodes| delete_nodes.iter().map(|id| map(*id)).collect());
+        self.entry_edges
+            .replace_with(|entry_edges| map_edges(entry_edges));
+        self.removed_render_edges
+            .replace_with(|removed_render_edges| map_edges(removed_render_edges));
+        self.temp_edges.replace_with(|temp_edges| {
+            temp_edges
+                .iter()
+                .map(|(edge, is_new)| (map_edge(edge), *is_new))
+                .collect()
+        });
+        self.temp_id_map.replace_with(|temp_id_map| {
+            temp_id_map
+                .iter()
+                .map(|(temp_id, id)| (*temp_id, map(*id)))
+                .collect()
+        });
+        self.added_edge_ids
+            .replace_with(|added_edge_ids| added_edge_ids.iter().map(|id| map(*id)).collect());
+        self.map_edge_finders(|edge_finder| edge_finder.map_ids(&map));
+    }
+
     fn add_entry_edge(&self, edge: EdgeDescriptor<E>) {
         self.entry_edges.borrow_mut().insert(edge);
     }
@@ -73,7 +240,7 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         let _result = self.new_nodes.borrow_mut().remove(&id);
     }
 
-    fn add_node(&self, node: NewNode<T, E>) {
+    fn add_node(&self, node: NewNode<T, E, L>) {
         let mut new_nodes_mut = self.new_nodes.borrow_mut();
         if let Some(existing_entry) = new_nodes_mut.get_mut(&node.id) {
             let result = existing_entry.merge_additive(node);
@@ -88,8 +255,8 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         }
     }
 
-    pub fn start_with_new_node(&'b self) -> BlueNew<'a, T, E, A> {
-        let new_node = NewNode::new();
+    pub fn start_with_new_node(&'b self) -> BlueNew<'a, T, E, A, L> {
+        let new_node = NewNode::new().set_id(self.next_id());
         self.add_node(new_node.clone());
         BlueNew {
             node: new_node,
@@ -97,7 +264,7 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         }
     }
 
-    fn update_node(&self, node: UpdateNode<T, E>) {
+    fn update_node(&self, node: UpdateNode<T, E, L>) {
         let mut update_nodes_mut = self.update_nodes.borrow_mut();
         if let Some(existing_entry) = update_nodes_mut.get_mut(&node.id) {
             let result = existing_entry.merge(node);
@@ -112,7 +279,7 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         }
     }
 
-    pub fn start_with_update_node(&'b self, id: Uid) -> BlueUpdate<'b, T, E, A> {
+    pub fn start_with_update_node(&'b self, id: Uid) -> BlueUpdate<'b, T, E, A, L> {
         let update_node = UpdateNode::new(id);
         self.update_node(update_node.clone());
         BlueUpdate {
@@ -128,7 +295,7 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
     // Edge should be given from the perspective of the potentially displaced node
     fn find_and_catalog_displaced_nodes(
         &self,
-        graph: &ViewGraph<T, E, A>,
+        graph: &ViewGraph<T, E, A, L>,
         edge_to_check: EdgeDescriptor<E>,
     ) {
         if edge_to_check
@@ -164,7 +331,7 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
             let converted_node = NewNode {
                 add_edges: all_edges_except_current_one.clone(),
                 id: found_node.id,
-                ..NewNode::<T, E>::new()
+                ..NewNode::<T, E, L>::new()
             };
             // log!("~~~ Converted Node: {:?}", converted_node.clone());
             self.displaced_nodes
@@ -178,25 +345,85 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         }
     }
 
-    fn finalize_delete_nodes(&self, graph: &ViewGraph<T, E, A>) {
-        for node_id in self.delete_nodes.borrow().iter() {
+    // Adds the nodes cascaded to by the schema's on-delete policies, and refuses restricted deletions.
+    // Only nodes added to delete_nodes since the last call are checked, so each restriction is reported once.
+    fn apply_delete_policies(&self, graph: &ViewGraph<T, E, A, L>) {
+        let Some(schema) = &graph.schema else {
+            return;
+        };
+        let mut pending = self
+            .delete_nodes
+            .borrow()
+            .clone()
+            .relative_complement(self.policy_checked_nodes.borrow().clone())
+            .into_iter()
+            .collect::<Vec<Uid>>();
+        while let Some(node_id) = pending.pop() {
+            let Some(graph_node) = graph.nodes.get(&node_id) else {
+                continue;
+            };
+            for edge in graph_node.0.convert_all_edges_to_hashset() {
+                if schema.0.on_delete(&edge) == OnDelete::Cascade
+                    && self.delete_nodes.borrow_mut().insert(edge.target).is_none()
+                {
+                    pending.push(edge.target);
+                }
+            }
+        }
+
+        // Checked once every cascade is known, as a restricting edge between two deleted nodes is fine
+        let delete_nodes = self.delete_nodes.borrow();
+        let mut policy_checked_nodes = self.policy_checked_nodes.borrow_mut();
+        let mut restricted = Vec::new();
+        for node_id in delete_nodes.iter() {
+            if policy_checked_nodes.insert(*node_id).is_some() {
+                continue;
+            }
+            let Some(graph_node) = graph.nodes.get(node_id) else {
+                continue;
+            };
+            for edge in graph_node.0.convert_all_edges_to_hashset() {
+                if schema.0.on_delete(&edge) == OnDelete::Restrict
+                    && !delete_nodes.contains(&edge.target)
+                {
+                    restricted.push(SchemaError::DeleteRestricted {
+                        node: *node_id,
+                        edge_type: SchemaValue::new(edge.edge_type.clone()),
+                        target: edge.target,
+                    });
+                }
+            }
+        }
+        if !restricted.is_empty() {
+            self.errors
+                .borrow_mut()
+                .push(GraphError::Schema(restricted));
+        }
+    }
+
+    fn finalize_delete_nodes(&self, graph: &ViewGraph<T, E, A, L>) {
+        self.apply_delete_policies(graph);
+        // Grouped once up front rather than scanning every entry edge for each deleted node
+        let mut entry_edges_by_host = self.entry_edges.borrow().iter().fold(
+            HashMap::<Uid, HashSet<EdgeDescriptor<E>>>::new(),
+            |mut acc, edge| {
+                acc.entry(edge.host).or_default().insert(edge.clone());
+                acc
+            },
+        );
+        let delete_nodes = self.delete_nodes.borrow();
+        for node_id in delete_nodes.iter() {
             // Since entry edges should represent everywhere a new node is being connected to an existing node,
             // we should be able to check the entry edges for anywhere the a new node was referencing a deleted node, and remove those edges
             // (This instead of looping through all new nodes searching for edges to the deleted node)
-            let found_entry_edges = self
-                .entry_edges
-                .borrow()
-                .iter()
-                .filter(|edge| edge.host == *node_id)
-                .cloned()
-                .collect::<HashSet<EdgeDescriptor<E>>>();
+            let found_entry_edges = entry_edges_by_host.remove(node_id).unwrap_or_default();
 
             for found_edge in found_entry_edges {
                 self.entry_edges.borrow_mut().remove(&found_edge);
                 let mut new_nodes_mut = self.new_nodes.borrow_mut();
                 let new_node = new_nodes_mut.get_mut(&found_edge.target).unwrap();
-                let found_actual_edges =
-                    new_node.find_edges(&EdgeFinder::<T, E, A>::new().target(*node_id).match_all());
+                let found_actual_edges = new_node
+                    .find_edges(&EdgeFinder::<T, E, A, L>::new().target(*node_id).match_all());
                 for found_actual_edge in found_actual_edges {
                     new_node.add_edges.remove(&found_actual_edge);
                 }
@@ -207,26 +434,32 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
 
             let graph_node = graph.nodes.get(node_id);
             // If any of the edges on the deleted node were rendering another node, add the edge to the deleted_render_edges
-            // Remove all existing edges on the deleted node
+            // Remove all existing edges on the deleted node, other than from nodes which are being deleted too
             if let Some(graph_node) = graph_node {
                 graph_node.0.incoming_edges.get_untracked().iter().for_each(
                     |(_edge_type, edges)| {
-                        edges.iter().for_each(|edge| {
-                            let inverted_edge = edge.invert();
-
-                            self.remove_edge(inverted_edge.clone());
-                            self.find_and_catalog_displaced_nodes(graph, inverted_edge);
-                        })
+                        edges
+                            .iter()
+                            .filter(|edge| !delete_nodes.contains(&edge.target))
+                            .for_each(|edge| {
+                                let inverted_edge = edge.invert();
+
+                                self.remove_edge(inverted_edge.clone());
+                                self.find_and_catalog_displaced_nodes(graph, inverted_edge);
+                            })
                     },
                 );
                 graph_node.0.outgoing_edges.get_untracked().iter().for_each(
                     |(_edge_type, edges)| {
-                        edges.iter().for_each(|edge| {
-                            let inverted_edge = edge.clone().invert();
-
-                            self.remove_edge(inverted_edge.clone());
-                            self.find_and_catalog_displaced_nodes(graph, inverted_edge)
-                        })
+                        edges
+                            .iter()
+                            .filter(|edge| !delete_nodes.contains(&edge.target))
+                            .for_each(|edge| {
+                                let inverted_edge = edge.clone().invert();
+
+                                self.remove_edge(inverted_edge.clone());
+                                self.find_and_catalog_displaced_nodes(graph, inverted_edge)
+                            })
                     },
                 );
             } else {
@@ -238,7 +471,42 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         }
     }
 
-    fn finalize_removed_edges(&self, graph: &ViewGraph<T, E, A>) {
+    // Lets the finders given to the blueprint check the labels and data of the nodes on either end of an edge
+    fn bind_edge_finders(&self, graph: &ViewGraph<T, E, A, L>) {
+        let get_node_closure = graph.get_node_closure();
+        self.map_edge_finders(|edge_finder| edge_finder.bind_graph(&get_node_closure));
+    }
+
+    // Replaces every edge finder the blueprint holds, including those positioning edges
+    fn map_edge_finders(&self, map: impl Fn(&EdgeFinder<T, E, A, L>) -> EdgeFinder<T, E, A, L>) {
+        self.remove_edge_finders
+            .replace_with(|edge_finders| edge_finders.iter().map(&map).collect());
+        self.update_payload_finders.replace_with(|payload_finders| {
+            payload_finders
+                .iter()
+                .map(|(edge_finder, payload)| (map(edge_finder), payload.clone()))
+                .collect()
+        });
+        self.edge_positions.replace_with(|edge_positions| {
+            edge_positions
+                .iter()
+                .map(|(edge_finder, position)| {
+                    let position = match position {
+                        EdgePosition::Before(sibling_finder) => {
+                            EdgePosition::Before(map(sibling_finder))
+                        }
+                        EdgePosition::After(sibling_finder) => {
+                            EdgePosition::After(map(sibling_finder))
+                        }
+                        EdgePosition::Index(index) => EdgePosition::Index(*index),
+                    };
+                    (map(edge_finder), position)
+                })
+                .collect()
+        });
+    }
+
+    fn finalize_removed_edges(&self, graph: &ViewGraph<T, E, A, L>) {
         for edge_finder in self.remove_edge_finders.borrow().iter() {
             // Find the edge(s) in the graph
             // We are manually setting the host in the BlueUpdate method.
@@ -278,6 +546,293 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         }
     }
 
+    // Payload updates are collected before the rest of the blueprint is finalized, but only added at the end,
+    // since the edges they update may still be removed or replaced
+    fn collect_updated_edge_payloads(&self, graph: &ViewGraph<T, E, A, L>) -> PayloadUpdates<E> {
+        let mut payload_updates = PayloadUpdates::new();
+        for (edge_finder, payload) in self.update_payload_finders.borrow().iter() {
+            // We are manually setting the host in the BlueUpdate method.
+            let graph_node = graph
+                .nodes
+                .get(edge_finder.host.as_ref().unwrap().iter().next().unwrap())
+                .unwrap()
+                .0
+                .clone();
+
+            let Some(found_edges) = graph_node.search_for_edge(edge_finder) else {
+                self.errors.borrow_mut().push(GraphError::Blueprint(format!(
+                    "update_edge_payload: edge not found\nEdge Finder: {:?}",
+                    edge_finder
+                )));
+                continue;
+            };
+
+            // Several finders can match the same edge, from either side
+            for found_edge in found_edges {
+                match payload_updates.get(&found_edge.id) {
+                    Some((_, other_payload)) if other_payload != payload => {
+                        self.errors.borrow_mut().push(GraphError::Blueprint(format!(
+                            "update_edge_payload: conflicting payloads for edge, ID: {:?}",
+                            found_edge.id
+                        )));
+                    }
+                    Some(_) => {}
+                    None => {
+                        payload_updates.insert(found_edge.id, (found_edge, payload.clone()));
+                    }
+                }
+            }
+        }
+        payload_updates
+    }
+
+    fn finalize_updated_edge_payloads(&self, payload_updates: PayloadUpdates<E>) {
+        for (_id, (edge, payload)) in payload_updates {
+            // Edges of deleted nodes are removed rather than updated
+            let delete_nodes = self.delete_nodes.borrow();
+            if delete_nodes.contains(&edge.host) || delete_nodes.contains(&edge.target) {
+                continue;
+            }
+            for side in [edge.clone(), edge.invert()] {
+                let mut update_nodes_mut = self.update_nodes.borrow_mut();
+                let update_node = update_nodes_mut
+                    .entry(side.host)
+                    .or_insert_with(|| UpdateNode::new(side.host));
+                let is_same_edge =
+                    |other: &EdgeDescriptor<E>| other.id == side.id && other.dir == side.dir;
+
+                // Edges replaced by the blueprint, e.g. to change their render info or order key, take the payload with them
+                if let Some(add_edges) = update_node.add_edges.as_mut() {
+                    if let Some(replacement) = add_edges.iter().find(|e| is_same_edge(e)).cloned() {
+                        add_edges.remove(&replacement);
+                        add_edges.insert(replacement.payload(payload.clone()));
+                        continue;
+                    }
+                }
+                // Edges removed by the blueprint stay removed
+                if update_node
+                    .remove_edges
+                    .as_ref()
+                    .is_some_and(|remove_edges| remove_edges.iter().any(is_same_edge))
+                {
+                    continue;
+                }
+                update_node
+                    .update_edge_payloads
+                    .get_or_insert_with(HashSet::new)
+                    .insert(EdgePayloadUpdate::new(&side, payload.clone()));
+            }
+        }
+    }
+
+    fn position_edge(
+        &self,
+        host: Uid,
+        edge_finder: EdgeFinder<T, E, A, L>,
+        position: EdgePosition<T, E, A, L>,
+    ) {
+        let mut edge_finder = edge_finder;
+        edge_finder.host = Some(hashset!(host));
+        self.edge_positions
+            .borrow_mut()
+            .push_back((edge_finder, position));
+    }
+
+    // Every edge the node will have once the blueprint is applied
+    fn effective_edges(
+        &self,
+        graph: &ViewGraph<T, E, A, L>,
+        host: Uid,
+    ) -> HashSet<EdgeDescriptor<E>> {
+        if let Some(new_node) = self.new_nodes.borrow().get(&host) {
+            return new_node.add_edges.clone();
+        }
+        let mut edges = graph
+            .nodes
+            .get(&host)
+            .map(|node| node.0.convert_all_edges_to_hashset())
+            .unwrap_or_default();
+        if let Some(update_node) = self.update_nodes.borrow().get(&host) {
+            if let Some(remove_edges) = update_node.remove_edges.clone() {
+                edges = edges.relative_complement(remove_edges);
+            }
+            if let Some(add_edges) = update_node.add_edges.clone() {
+                edges = edges.union(add_edges);
+            }
+        }
+        edges
+    }
+
+    // The other edges of the same type and direction on the edge's host, in order
+    fn sorted_siblings(
+        &self,
+        graph: &ViewGraph<T, E, A, L>,
+        edge: &EdgeDescriptor<E>,
+    ) -> Vec<EdgeDescriptor<E>> {
+        let mut siblings: Vec<EdgeDescriptor<E>> = self
+            .effective_edges(graph, edge.host)
+            .into_iter()
+            .filter(|sibling| {
+                sibling.dir == edge.dir
+                    && sibling.edge_type == edge.edge_type
+                    && sibling.id != edge.id
+            })
+            .collect();
+        siblings.sort_by(|a, b| a.cmp_order(b));
+        // Edges without a key sort first, so they are given keys before the first keyed sibling.
+        // Otherwise an edge positioned among them would get a key which sorts after all of them.
+        let mut next_key = siblings
+            .iter()
+            .find_map(|sibling| sibling.order_key.clone());
+        for sibling in siblings.iter_mut().rev() {
+            if sibling.order_key.is_some() {
+                continue;
+            }
+            let order_key = OrderKey::between(None, next_key.as_ref());
+            self.set_order_key(graph, sibling, order_key.clone());
+            *sibling = sibling.order_key(Some(order_key.clone()));
+            next_key = Some(order_key);
+        }
+        siblings
+    }
+
+    // Replaces both sides of the edge pair with copies carrying the order key
+    fn set_order_key(
+        &self,
+        graph: &ViewGraph<T, E, A, L>,
+        edge: &EdgeDescriptor<E>,
+        order_key: OrderKey,
+    ) {
+        for side in [edge.clone(), edge.invert()] {
+            // Looked up by id, since the render info of the other side may have been changed by this blueprint
+            let Some(current) = self
+                .effective_edges(graph, side.host)
+                .into_iter()
+                .find(|current| current.id == side.id && current.dir == side.dir)
+            else {
+                continue;
+            };
+            let keyed = current.order_key(Some(order_key.clone()));
+
+            if let Some(new_node) = self.new_nodes.borrow_mut().get_mut(&side.host) {
+                new_node.add_edges.remove(&current);
+                new_node.add_edges.insert(keyed);
+                continue;
+            }
+            let mut update_nodes_mut = self.update_nodes.borrow_mut();
+            let update_node = update_nodes_mut
+                .entry(side.host)
+                .or_insert_with(|| UpdateNode::new(side.host));
+            // Existing edges are replaced, the same as when their render info changes
+            let add_edges = update_node.add_edges.get_or_insert_with(HashSet::new);
+            if add_edges.remove(&current).is_none() {
+                update_node
+                    .remove_edges
+                    .get_or_insert_with(HashSet::new)
+                    .insert(current);
+            }
+            update_node
+                .add_edges
+                .get_or_insert_with(HashSet::new)
+                .insert(keyed);
+        }
+    }
+
+    fn finalize_order_keys(&self, graph: &ViewGraph<T, E, A, L>) {
+        let added_edge_order = self
+            .added_edge_ids
+            .borrow()
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index))
+            .collect::<HashMap<Uid, usize>>();
+        // Keys are chosen from the emitting side, which covers every new edge pair once
+        let mut unordered_edges = HashMap::<(Uid, E), Vec<EdgeDescriptor<E>>>::new();
+        let new_edges = self
+            .new_nodes
+            .borrow()
+            .values()
+            .flat_map(|node| node.add_edges.clone())
+            .chain(
+                self.update_nodes
+                    .borrow()
+                    .values()
+                    .flat_map(|node| node.add_edges.clone().unwrap_or_default()),
+            )
+            .collect::<Vec<_>>();
+        for edge in new_edges {
+            if edge.dir == EdgeDir::Emit && edge.order_key.is_none() {
+                unordered_edges
+                    .entry((edge.host, edge.edge_type.clone()))
+                    .or_default()
+                    .push(edge);
+            }
+        }
+        for (_, mut edges) in unordered_edges {
+            edges.sort_by_key(|edge| (added_edge_order.get(&edge.id).copied(), edge.id));
+            let mut last_key = self
+                .sorted_siblings(graph, &edges[0])
+                .into_iter()
+                .filter_map(|sibling| sibling.order_key)
+                .max();
+            for edge in edges {
+                let order_key = OrderKey::between(last_key.as_ref(), None);
+                self.set_order_key(graph, &edge, order_key.clone());
+                last_key = Some(order_key);
+            }
+        }
+
+        for (edge_finder, position) in self.edge_positions.borrow().iter() {
+            // We are manually setting the host in position_edge.
+            let host = *edge_finder.host.as_ref().unwrap().iter().next().unwrap();
+            let Some(edge) = self
+                .effective_edges(graph, host)
+                .into_iter()
+                .find(|edge| edge_finder.matches(edge))
+            else {
+                self.errors.borrow_mut().push(GraphError::Blueprint(format!(
+                    "position_edge: edge not found\nEdge Finder: {:?}",
+                    edge_finder
+                )));
+                continue;
+            };
+            // The key would be chosen among the receiving side's siblings but kept on the emitting side too
+            if edge.dir != EdgeDir::Emit {
+                self.errors.borrow_mut().push(GraphError::Blueprint(format!(
+                    "position_edge: only emitted edges can be positioned, position it from node {:?} instead\nEdge Finder: {:?}",
+                    edge.target, edge_finder
+                )));
+                continue;
+            }
+            let siblings = self.sorted_siblings(graph, &edge);
+            let index = match position {
+                EdgePosition::Index(index) => Some((*index).min(siblings.len())),
+                EdgePosition::Before(sibling_finder) => siblings
+                    .iter()
+                    .position(|sibling| sibling_finder.matches(sibling)),
+                EdgePosition::After(sibling_finder) => siblings
+                    .iter()
+                    .position(|sibling| sibling_finder.matches(sibling))
+                    .map(|index| index + 1),
+            };
+            let Some(index) = index else {
+                self.errors.borrow_mut().push(GraphError::Blueprint(format!(
+                    "position_edge: sibling not found\nPosition: {:?}",
+                    position
+                )));
+                continue;
+            };
+            let before = index
+                .checked_sub(1)
+                .and_then(|index| siblings.get(index))
+                .and_then(|sibling| sibling.order_key.as_ref());
+            let after = siblings
+                .get(index)
+                .and_then(|sibling| sibling.order_key.as_ref());
+            self.set_order_key(graph, &edge, OrderKey::between(before, after));
+        }
+    }
+
     fn add_edge(&self, edge: EdgeDescriptor<E>, is_new: bool) {
         if is_new {
             self.new_nodes
@@ -347,7 +902,7 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         }
     }
 
-    fn find_potential_entries_for_displaced_nodes(&self, graph: &ViewGraph<T, E, A>) {
+    fn find_potential_entries_for_displaced_nodes(&self, graph: &ViewGraph<T, E, A, L>) {
         for (id, displaced_node) in self.displaced_nodes.borrow().iter() {
             displaced_node.add_edges.iter().for_each(|edge| {
                 if graph.nodes.get(&edge.target).is_some()
@@ -364,9 +919,9 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
 
     fn set_render_edges(
         &self,
-        valid_render_edge_finders: Vector<EdgeFinder<T, E, A>>,
+        valid_render_edge_finders: Vector<EdgeFinder<T, E, A, L>>,
         entry_point_temp_id: Option<TempId>,
-        graph: &ViewGraph<T, E, A>,
+        graph: &ViewGraph<T, E, A, L>,
     ) {
         let combined_entry_edges: HashSet<EdgeDescriptor<E>> = self
             .entry_edges
@@ -401,7 +956,7 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         combined_uncertain_render_nodes.extend(self.displaced_nodes.borrow().clone());
 
         let mut all_connected_nodes: HashSet<Uid> = HashSet::new();
-        let mut newly_connected_nodes: HashSet<NewNode<T, E>> = HashSet::new();
+        let mut newly_connected_nodes: HashSet<NewNode<T, E, L>> = HashSet::new();
         let mut remaining_nodes = combined_uncertain_render_nodes
             .iter()
             .map(|(id, _node)| *id)
@@ -544,6 +1099,7 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
                             .edge_type(connection_clone.edge_type.clone())
                             .dir(connection_clone.dir)
                             .target(connection_clone.target)
+                            .edge_id(connection_clone.id)
                             .render_info(Some(EdgeDir::Emit)),
                     )
                     .is_some();
@@ -609,47 +1165,57 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
 
     pub fn finalize(
         self,
-        graph: &ViewGraph<T, E, A>,
+        graph: &ViewGraph<T, E, A, L>,
         // Will be chosen with preference to the order they are specified
         // None defaults to all edge types in the Emit direction
-        render_edge_types: Option<Vector<AllowedRenderEdgeSpecifier<E>>>,
+        render_edge_types: Option<Vector<AllowedRenderEdgeSpecifier<E, L>>>,
         entry_point_temp_id: Option<TempId>,
-    ) -> Result<FinalizedBlueprint<T, E>, Vec<GraphError>> {
-        let valid_render_edge_finders: Vector<EdgeFinder<T, E, A>> =
+    ) -> Result<FinalizedBlueprint<T, E, L>, Vec<GraphError>> {
+        let valid_render_edge_finders: Vector<EdgeFinder<T, E, A, L>> =
             if let Some(render_edge_types) = &render_edge_types {
                 render_edge_types
                     .iter()
-                    .map(|edge_type| {
-                        EdgeFinder::new()
-                            .edge_type(edge_type.edge_type.clone())
-                            .dir(edge_type.dir.clone())
-                        // .render_info(None)
+                    .map(|render_edge_type| {
+                        render_edge_type
+                            .to_edge_finder(&graph.predicate_registry, graph.get_node_closure())
                     })
-                    .collect()
+                    .collect::<Result<_, _>>()
+                    .map_err(|error| vec![error])?
             } else {
                 vector![EdgeFinder::new().dir(EdgeDir::Emit)
                 // .render_info(None)
                 ]
             };
 
+        self.assign_ids(&graph.id_generator);
+
         self.update_temp_ids();
 
+        self.bind_edge_finders(graph);
+
         self.finalize_delete_nodes(graph);
 
         self.finalize_removed_edges(graph);
 
+        let payload_updates = self.collect_updated_edge_payloads(graph);
+
         self.find_potential_entries_for_displaced_nodes(graph);
 
         self.set_render_edges(valid_render_edge_finders, entry_point_temp_id, graph);
 
-        let mut finalized_delete_nodes = HashMap::<Uid, DeleteNode<T, E>>::new();
+        // Done last, as edges may still be replaced while finding render edges
+        self.finalize_order_keys(graph);
+
+        self.finalize_updated_edge_payloads(payload_updates);
+
+        let mut finalized_delete_nodes = HashMap::<Uid, DeleteNode<T, E, L>>::new();
         for delete_id in self.delete_nodes.take().iter() {
             finalized_delete_nodes.insert(
                 *delete_id,
                 DeleteNode::from_read_reactive_node(&graph.nodes.get(delete_id).unwrap().0),
             );
         }
-        let mut finalized_update_nodes = HashMap::<Uid, FinalizedUpdateNode<T, E>>::new();
+        let mut finalized_update_nodes = HashMap::<Uid, FinalizedUpdateNode<T, E, L>>::new();
         for (id, update_node) in self.update_nodes.take() {
             let finalized_replacement_data =
                 update_node
@@ -667,6 +1233,7 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
                     id: update_node.id,
                     remove_edges: update_node.remove_edges,
                     remove_labels: update_node.remove_labels,
+                    update_edge_payloads: update_node.update_edge_payloads,
                 },
             );
         }
@@ -675,21 +1242,30 @@ impl<'a, 'b: 'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildBlueprint<
         if !errors.is_empty() {
             return Err(errors);
         }
-        Ok(FinalizedBlueprint {
+        let finalized_blueprint = FinalizedBlueprint {
             delete_nodes: finalized_delete_nodes,
             new_nodes: self.new_nodes.take(),
             update_nodes: finalized_update_nodes,
-        })
+        };
+        if let Some(schema) = &graph.schema {
+            schema
+                .0
+                .validate(&finalized_blueprint, graph)
+                .map_err(|errors| vec![GraphError::Schema(errors)])?;
+        }
+        Ok(finalized_blueprint)
     }
 }
 
+// Unless the blueprint was made with_id_generator or set_id was called, node.id is a provisional id until the blueprint
+// is processed. Use set_temp_id and look the node up in the temp id map returned by process_blueprint for its real id.
 #[derive(Clone, Debug)]
-pub struct BlueNew<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub node: NewNode<T, E>,
-    pub blueprint: &'a BuildBlueprint<T, E, A>,
+pub struct BlueNew<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub node: NewNode<T, E, L>,
+    pub blueprint: &'a BuildBlueprint<T, E, A, L>,
 }
 
-impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BlueNew<'a, T, E, A> {
+impl<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> BlueNew<'a, T, E, A, L> {
     pub fn set_data(&self, data: T) -> Self {
         let new_node = NewNode {
             data,
@@ -721,7 +1297,7 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BlueNew<'a, T, E, A> {
             ..self.clone()
         }
     }
-    pub fn add_label(&self, label: String) -> Self {
+    pub fn add_label(&self, label: L) -> Self {
         let mut new_labels = self.node.add_labels.clone();
         new_labels.insert(label);
         let new_node = NewNode {
@@ -752,12 +1328,12 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BlueNew<'a, T, E, A> {
 }
 
 #[derive(Clone)]
-pub struct BlueUpdate<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub node: UpdateNode<T, E>,
-    pub blueprint: &'a BuildBlueprint<T, E, A>,
+pub struct BlueUpdate<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub node: UpdateNode<T, E, L>,
+    pub blueprint: &'a BuildBlueprint<T, E, A, L>,
 }
 
-impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BlueUpdate<'a, T, E, A> {
+impl<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> BlueUpdate<'a, T, E, A, L> {
     pub fn update_data(&self, data: T) -> Self {
         let new_node = UpdateNode {
             replacement_data: Some(data),
@@ -770,7 +1346,7 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BlueUpdate<'a, T, E, A>
         }
     }
 
-    pub fn add_label(&self, label: String) -> Self {
+    pub fn add_label(&self, label: L) -> Self {
         let mut new_labels = self.node.add_labels.clone().unwrap_or_default();
         new_labels.insert(label);
         let new_node = UpdateNode {
@@ -784,7 +1360,7 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BlueUpdate<'a, T, E, A>
         }
     }
 
-    pub fn remove_label(&self, label: String) -> Self {
+    pub fn remove_label(&self, label: L) -> Self {
         let mut new_labels = self.node.remove_labels.clone().unwrap_or_default();
         new_labels.insert(label);
         let new_node = UpdateNode {
@@ -798,7 +1374,7 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BlueUpdate<'a, T, E, A>
         }
     }
 
-    pub fn remove_edge(&self, edge_finder: EdgeFinder<T, E, A>) -> Self {
+    pub fn remove_edge(&self, edge_finder: EdgeFinder<T, E, A, L>) -> Self {
         let host_hashset = hashset!(self.node.id);
         let mut edge_finder = edge_finder;
 
@@ -811,42 +1387,127 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> BlueUpdate<'a, T, E, A>
 
         self.clone()
     }
+
+    // Sets the payload on both sides of every edge the finder matches
+    pub fn update_edge_payload(
+        &self,
+        edge_finder: EdgeFinder<T, E, A, L>,
+        payload: Option<E::Payload>,
+    ) -> Self {
+        let host_hashset = hashset!(self.node.id);
+        let mut edge_finder = edge_finder;
+
+        edge_finder.host = Some(host_hashset);
+
+        self.blueprint
+            .update_payload_finders
+            .borrow_mut()
+            .insert((edge_finder, payload));
+
+        self.clone()
+    }
 }
 
 pub trait GetIsNew {
     fn get_is_new(&self) -> bool;
 }
-impl<'b, T: GraphTraits, E: GraphTraits, A: GraphTraits> GetIsNew for BlueNew<'b, T, E, A> {
+impl<'b, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> GetIsNew
+    for BlueNew<'b, T, E, A, L>
+{
     fn get_is_new(&self) -> bool {
         true
     }
 }
-impl<'b, T: GraphTraits, E: GraphTraits, A: GraphTraits> GetIsNew for BlueUpdate<'b, T, E, A> {
+impl<'b, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> GetIsNew
+    for BlueUpdate<'b, T, E, A, L>
+{
     fn get_is_new(&self) -> bool {
         false
     }
 }
-pub trait AddBlueprintEdges<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> {
+pub trait AddBlueprintEdges<
+    'a,
+    T: GraphTraits,
+    E: EdgeTraits,
+    A: GraphTraits,
+    L: GraphTraits = String,
+>: Sized
+{
     fn add_edge_existing<F>(&self, direction: EdgeDir, edge_type: E, id: Uid, f: F) -> Self
     where
-        F: FnOnce(BlueUpdate<'a, T, E, A>) -> BlueUpdate<'a, T, E, A>;
+        F: FnOnce(BlueUpdate<'a, T, E, A, L>) -> BlueUpdate<'a, T, E, A, L>,
+    {
+        self.add_edge_existing_with_payload(direction, edge_type, None, id, f)
+    }
     fn add_edge_new<F>(&self, direction: EdgeDir, edge_type: E, f: F) -> Self
     where
-        F: FnOnce(BlueNew<'a, T, E, A>) -> BlueNew<'a, T, E, A>;
-    fn add_edge_temp(&self, direction: EdgeDir, edge_type: E, temp_id: Uid) -> Self;
+        F: FnOnce(BlueNew<'a, T, E, A, L>) -> BlueNew<'a, T, E, A, L>,
+    {
+        self.add_edge_new_with_payload(direction, edge_type, None, f)
+    }
+    fn add_edge_temp(&self, direction: EdgeDir, edge_type: E, temp_id: Uid) -> Self {
+        self.add_edge_temp_with_payload(direction, edge_type, None, temp_id)
+    }
+    // The payload is stored on both sides of the edge pair
+    fn add_edge_existing_with_payload<F>(
+        &self,
+        direction: EdgeDir,
+        edge_type: E,
+        payload: Option<E::Payload>,
+        id: Uid,
+        f: F,
+    ) -> Self
+    where
+        F: FnOnce(BlueUpdate<'a, T, E, A, L>) -> BlueUpdate<'a, T, E, A, L>;
+    fn add_edge_new_with_payload<F>(
+        &self,
+        direction: EdgeDir,
+        edge_type: E,
+        payload: Option<E::Payload>,
+        f: F,
+    ) -> Self
+    where
+        F: FnOnce(BlueNew<'a, T, E, A, L>) -> BlueNew<'a, T, E, A, L>;
+    fn add_edge_temp_with_payload(
+        &self,
+        direction: EdgeDir,
+        edge_type: E,
+        payload: Option<E::Payload>,
+        temp_id: Uid,
+    ) -> Self;
+    // Positions the edge of this node which the finder matches among the other edges of its type and direction
+    // Works for edges which already exist as well as edges added by the blueprint
+    // Only emitted edges can be positioned, as the order is kept by the emitting node
+    fn insert_before(
+        &self,
+        edge_finder: EdgeFinder<T, E, A, L>,
+        sibling_finder: EdgeFinder<T, E, A, L>,
+    ) -> Self;
+    fn insert_after(
+        &self,
+        edge_finder: EdgeFinder<T, E, A, L>,
+        sibling_finder: EdgeFinder<T, E, A, L>,
+    ) -> Self;
+    fn move_to(&self, edge_finder: EdgeFinder<T, E, A, L>, index: usize) -> Self;
 }
 
 macro_rules! implement_add_blueprint_edges {
     ($type:ty) => {
-        impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> AddBlueprintEdges<'a, T, E, A>
-            for $type
+        impl<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>
+            AddBlueprintEdges<'a, T, E, A, L> for $type
         {
-            fn add_edge_new<F>(&self, dir: EdgeDir, edge_type: E, f: F) -> Self
+            fn add_edge_new_with_payload<F>(
+                &self,
+                dir: EdgeDir,
+                edge_type: E,
+                payload: Option<E::Payload>,
+                f: F,
+            ) -> Self
             where
-                F: FnOnce(BlueNew<'a, T, E, A>) -> BlueNew<'a, T, E, A>,
+                F: FnOnce(BlueNew<'a, T, E, A, L>) -> BlueNew<'a, T, E, A, L>,
             {
                 let edge_node = f(BlueNew {
-                    node: NewNode::new(),
+                    node: NewNode::new().set_id(self.blueprint.next_id()),
                     blueprint: self.blueprint,
                 });
                 if let Some(temp_id) = edge_node.node.temp_id {
@@ -856,13 +1517,19 @@ macro_rules! implement_add_blueprint_edges {
                         .insert(temp_id, edge_node.node.id);
                 }
 
-                let this_edge = EdgeDescriptor {
-                    dir,
+                let this_edge = EdgeDescriptor::new(
+                    self.blueprint.next_id(),
+                    self.node.id,
                     edge_type,
-                    host: self.node.id,
-                    target: edge_node.node.id.clone(),
-                    render_info: None,
-                };
+                    edge_node.node.id.clone(),
+                    None,
+                    dir,
+                )
+                .payload(payload);
+                self.blueprint
+                    .added_edge_ids
+                    .borrow_mut()
+                    .push_back(this_edge.id);
                 self.blueprint.add_node(edge_node.node);
                 self.blueprint.add_edge(this_edge.invert(), true);
                 self.blueprint
@@ -874,9 +1541,16 @@ macro_rules! implement_add_blueprint_edges {
                 self.clone()
             }
 
-            fn add_edge_existing<F>(&self, dir: EdgeDir, edge_type: E, id: Uid, f: F) -> Self
+            fn add_edge_existing_with_payload<F>(
+                &self,
+                dir: EdgeDir,
+                edge_type: E,
+                payload: Option<E::Payload>,
+                id: Uid,
+                f: F,
+            ) -> Self
             where
-                F: FnOnce(BlueUpdate<'a, T, E, A>) -> BlueUpdate<'a, T, E, A>,
+                F: FnOnce(BlueUpdate<'a, T, E, A, L>) -> BlueUpdate<'a, T, E, A, L>,
             {
                 let edge_node = f(BlueUpdate {
                     node: UpdateNode::new(id),
@@ -884,12 +1558,18 @@ macro_rules! implement_add_blueprint_edges {
                 });
 
                 let this_edge = EdgeDescriptor::new(
+                    self.blueprint.next_id(),
                     self.node.id,
                     edge_type,
                     edge_node.node.id.clone(),
                     None,
                     dir,
-                );
+                )
+                .payload(payload);
+                self.blueprint
+                    .added_edge_ids
+                    .borrow_mut()
+                    .push_back(this_edge.id);
                 self.blueprint.update_node(edge_node.node);
                 self.blueprint.add_edge(this_edge.invert(), false);
                 self.blueprint
@@ -901,14 +1581,26 @@ macro_rules! implement_add_blueprint_edges {
                 self.clone()
             }
 
-            fn add_edge_temp(&self, dir: EdgeDir, edge_type: E, temp_id: Uid) -> Self {
-                let this_edge = EdgeDescriptor {
-                    dir,
+            fn add_edge_temp_with_payload(
+                &self,
+                dir: EdgeDir,
+                edge_type: E,
+                payload: Option<E::Payload>,
+                temp_id: Uid,
+            ) -> Self {
+                let this_edge = EdgeDescriptor::new(
+                    self.blueprint.next_id(),
+                    self.node.id,
                     edge_type,
-                    host: self.node.id,
-                    target: temp_id,
-                    render_info: None,
-                };
+                    temp_id,
+                    None,
+                    dir,
+                )
+                .payload(payload);
+                self.blueprint
+                    .added_edge_ids
+                    .borrow_mut()
+                    .push_back(this_edge.id);
                 // Not adding inverted edge to reduce the number of edges that need to be checked
                 // When this edge is found in the temp_edge finalization step, the inverted edge needs to be added
                 self.blueprint
@@ -917,18 +1609,51 @@ macro_rules! implement_add_blueprint_edges {
                     .insert((this_edge, self.get_is_new()));
                 self.clone()
             }
+
+            fn insert_before(
+                &self,
+                edge_finder: EdgeFinder<T, E, A, L>,
+                sibling_finder: EdgeFinder<T, E, A, L>,
+            ) -> Self {
+                self.blueprint.position_edge(
+                    self.node.id,
+                    edge_finder,
+                    EdgePosition::Before(sibling_finder),
+                );
+                self.clone()
+            }
+
+            fn insert_after(
+                &self,
+                edge_finder: EdgeFinder<T, E, A, L>,
+                sibling_finder: EdgeFinder<T, E, A, L>,
+            ) -> Self {
+                self.blueprint.position_edge(
+                    self.node.id,
+                    edge_finder,
+                    EdgePosition::After(sibling_finder),
+                );
+                self.clone()
+            }
+
+            fn move_to(&self, edge_finder: EdgeFinder<T, E, A, L>, index: usize) -> Self {
+                self.blueprint
+                    .position_edge(self.node.id, edge_finder, EdgePosition::Index(index));
+                self.clone()
+            }
         }
     };
 }
 
-implement_add_blueprint_edges!(BlueNew<'a, T, E, A>);
-implement_add_blueprint_edges!(BlueUpdate<'a, T, E, A>);
+implement_add_blueprint_edges!(BlueNew<'a, T, E, A, L>);
+implement_add_blueprint_edges!(BlueUpdate<'a, T, E, A, L>);
 
 #[cfg(test)]
 mod tests {
     use std::{cell::RefCell, rc::Rc};
 
-    use im::HashSet;
+    use im::{vector, HashSet};
+    use leptos::SignalGetUntracked;
 
     use crate::prelude::reactive_node::build_reactive_node::BuildReactiveNode;
     use crate::prelude::reactive_node::last_action::ActionData;
@@ -943,11 +1668,14 @@ mod tests {
         let mut graph = ViewGraph::new();
         let mut edges1 = HashSet::new();
         edges1.insert(EdgeDescriptor {
+            id: 1,
             dir: EdgeDir::Emit,
             edge_type: "existing_edge_type".into(),
             host: 999,
             target: 998,
             render_info: Some(EdgeDir::Emit),
+            payload: None,
+            order_key: None,
         });
         let (read_reactive_node_1, write_reative_node_1) =
             BuildReactiveNode::<String, String, String>::new()
@@ -961,18 +1689,24 @@ mod tests {
                 .build();
         let mut edges2 = HashSet::new();
         edges2.insert(EdgeDescriptor {
+            id: 1,
             dir: EdgeDir::Recv,
             edge_type: "existing_edge_type".into(),
             host: 998,
             target: 999,
             render_info: Some(EdgeDir::Recv),
+            payload: None,
+            order_key: None,
         });
         edges2.insert(EdgeDescriptor {
+            id: 2,
             dir: EdgeDir::Emit,
             edge_type: "existing_edge_type".into(),
             host: 998,
             target: 997,
             render_info: Some(EdgeDir::Emit),
+            payload: None,
+            order_key: None,
         });
         let (read_reactive_node_2, write_reative_node_2) =
             BuildReactiveNode::<String, String, String>::new()
@@ -986,11 +1720,14 @@ mod tests {
                 .build();
         let mut edges3 = HashSet::new();
         edges3.insert(EdgeDescriptor {
+            id: 2,
             dir: EdgeDir::Recv,
             edge_type: "existing_edge_type".into(),
             host: 997,
             target: 998,
             render_info: Some(EdgeDir::Recv),
+            payload: None,
+            order_key: None,
         });
         let (read_reactive_node_3, write_reative_node_3) =
             BuildReactiveNode::<String, String, String>::new()
@@ -1131,6 +1868,98 @@ mod tests {
         assert!(build_blueprint.is_err());
     }
 
+    #[test]
+    fn should_render_through_combined_edge_finders() {
+        let graph = ViewGraph::new();
+        // (1)<-ref-(2), so (2) can only be rendered through a received reference edge
+        let build_blueprint = || {
+            let build_blueprint = BuildBlueprint::<String, String, String>::new();
+            build_blueprint
+                .start_with_new_node()
+                .set_id(1)
+                .set_temp_id(1)
+                .add_edge_new(EdgeDir::Recv, "ref".into(), |blue_new| blue_new.set_id(2));
+            build_blueprint
+        };
+        let child = AllowedRenderEdgeSpecifier::new("child".to_string(), EdgeDir::Emit);
+        let reference = AllowedRenderEdgeSpecifier::new("ref".to_string(), EdgeDir::Recv);
+
+        assert!(build_blueprint().finalize(&graph, None, Some(1)).is_err());
+        assert!(build_blueprint()
+            .finalize(
+                &graph,
+                Some(vector![child.clone(), reference.clone()]),
+                Some(1)
+            )
+            .is_ok());
+        assert!(build_blueprint()
+            .finalize(
+                &graph,
+                Some(vector![
+                    child.clone(),
+                    reference.finder(
+                        EdgeFinder::<String, String, String>::new()
+                            .target(2)
+                            .or(EdgeFinder::new().target(3))
+                            .not()
+                            .to_spec()
+                            .unwrap()
+                    )
+                ]),
+                Some(1)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn should_bind_render_edge_finders_against_the_graph() {
+        let graph = manual_setup_graph();
+        // (999)-edge_type->(1), where (1) can only be rendered from the existing node
+        let build_blueprint = || {
+            let build_blueprint = BuildBlueprint::<String, String, String>::new();
+            build_blueprint
+                .start_with_new_node()
+                .set_id(1)
+                .add_edge_existing(EdgeDir::Recv, "edge_type".into(), 999, |blue_update| {
+                    blue_update
+                });
+            build_blueprint
+        };
+        let from_existent = AllowedRenderEdgeSpecifier::new("edge_type".to_string(), EdgeDir::Emit)
+            .finder(EdgeFinderSpec {
+                host_data: Some("is_existent".to_string()),
+                ..EdgeFinderSpec::default()
+            });
+
+        assert_eq!(
+            build_blueprint()
+                .finalize(&graph, Some(vector![from_existent.clone()]), None)
+                .err(),
+            Some(vec![GraphError::Traversal(
+                TraversalError::UnknownPredicate("is_existent".to_string())
+            )])
+        );
+        let graph_with_registry = |predicate: fn(&String) -> bool| ViewGraph {
+            predicate_registry: PredicateRegistry::new()
+                .register_data_predicate("is_existent", predicate),
+            ..graph.clone()
+        };
+        assert!(build_blueprint()
+            .finalize(
+                &graph_with_registry(|data| data.starts_with("Existent")),
+                Some(vector![from_existent.clone()]),
+                None
+            )
+            .is_ok());
+        assert!(build_blueprint()
+            .finalize(
+                &graph_with_registry(|_| false),
+                Some(vector![from_existent]),
+                None
+            )
+            .is_err());
+    }
+
     #[test]
     fn should_correctly_attach_new_node_subgraph_to_existing_graph() {
         let mut graph = manual_setup_graph();
@@ -1173,6 +2002,64 @@ mod tests {
             .is_some());
     }
 
+    #[test]
+    fn should_position_edges_among_siblings_without_order_keys() {
+        let mut graph = manual_setup_graph();
+        let build_blueprint = BuildBlueprint::<String, String, String>::new();
+
+        // The edge to 998 was built without an order key
+        build_blueprint
+            .start_with_update_node(999)
+            .add_edge_new(EdgeDir::Emit, "existing_edge_type".into(), |blue_new| {
+                blue_new.set_id(1)
+            })
+            .insert_before(EdgeFinder::new().target(1), EdgeFinder::new().target(998));
+
+        let build_blueprint = build_blueprint.finalize(&graph, None, None).unwrap();
+        let action_data = ActionData::new("update".to_string());
+        graph.add_nodes(build_blueprint.new_nodes, action_data.clone());
+        graph
+            .update_nodes(build_blueprint.update_nodes, action_data)
+            .unwrap();
+
+        let targets = graph
+            .nodes
+            .get(&999)
+            .unwrap()
+            .0
+            .outgoing_edges
+            .get_untracked()
+            .get("existing_edge_type")
+            .unwrap()
+            .iter()
+            .map(|edge| edge.target)
+            .collect::<Vec<_>>();
+        assert_eq!(targets, vec![1, 998]);
+        // The backfilled key is kept on both sides
+        let backfilled = graph
+            .nodes
+            .get(&998)
+            .unwrap()
+            .0
+            .search_for_edge(&EdgeFinder::new().target(999))
+            .unwrap();
+        assert!(backfilled.iter().next().unwrap().order_key.is_some());
+    }
+
+    #[test]
+    fn should_refuse_to_position_received_edges() {
+        let graph = manual_setup_graph();
+        let build_blueprint = BuildBlueprint::<String, String, String>::new();
+
+        // 998 receives its edge from 999, so the edge is ordered among 999's edges
+        build_blueprint
+            .start_with_update_node(998)
+            .move_to(EdgeFinder::new().target(999), 0);
+
+        let errors = build_blueprint.finalize(&graph, None, None).unwrap_err();
+        assert!(matches!(errors.as_slice(), [GraphError::Blueprint(_)]));
+    }
+
     #[test]
     fn should_delete_existing_nodes_which_are_no_longer_renderable() {
         let graph = manual_setup_graph();
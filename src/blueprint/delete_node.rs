@@ -2,26 +2,22 @@ use im::HashSet;
 use leptos::{logging::log, SignalGetUntracked};
 
 use crate::prelude::{
-    reactive_node::read_reactive_node::ReadReactiveNode, EdgeDescriptor, GraphTraits, Uid,
+    reactive_node::read_reactive_node::ReadReactiveNode, EdgeDescriptor, EdgeTraits, GraphTraits,
+    Uid,
 };
 
 use super::new_node::NewNode;
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct DeleteNode<T: GraphTraits, E: GraphTraits> {
+pub struct DeleteNode<T: GraphTraits, E: EdgeTraits, L: GraphTraits = String> {
     pub id: Uid,
     pub remove_edges: HashSet<EdgeDescriptor<E>>,
-    pub remove_labels: HashSet<String>,
+    pub remove_labels: HashSet<L>,
     pub data: T,
 }
 
-impl<T: GraphTraits, E: GraphTraits> DeleteNode<T, E> {
-    pub fn new(
-        id: Uid,
-        edges: HashSet<EdgeDescriptor<E>>,
-        labels: HashSet<String>,
-        data: T,
-    ) -> Self {
+impl<T: GraphTraits, E: EdgeTraits, L: GraphTraits> DeleteNode<T, E, L> {
+    pub fn new(id: Uid, edges: HashSet<EdgeDescriptor<E>>, labels: HashSet<L>, data: T) -> Self {
         Self {
             id,
             remove_edges: edges,
@@ -31,7 +27,7 @@ impl<T: GraphTraits, E: GraphTraits> DeleteNode<T, E> {
     }
 
     pub fn from_read_reactive_node<A: GraphTraits>(
-        reactive_node: &ReadReactiveNode<T, E, A>,
+        reactive_node: &ReadReactiveNode<T, E, A, L>,
     ) -> Self {
         Self {
             id: reactive_node.id,
@@ -46,7 +42,7 @@ impl<T: GraphTraits, E: GraphTraits> DeleteNode<T, E> {
         }
     }
 
-    pub fn history_invert(&self) -> NewNode<T, E> {
+    pub fn history_invert(&self) -> NewNode<T, E, L> {
         NewNode {
             id: self.id,
             temp_id: None,
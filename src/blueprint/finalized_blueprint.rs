@@ -1,19 +1,19 @@
 use im::HashMap;
 
-use crate::prelude::{GraphTraits, Uid};
+use crate::prelude::{EdgeTraits, GraphTraits, Uid};
 
 use super::{
     delete_node::DeleteNode, finalized_update_node::FinalizedUpdateNode, new_node::NewNode,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
-pub struct FinalizedBlueprint<T: GraphTraits, E: GraphTraits> {
-    pub new_nodes: HashMap<Uid, NewNode<T, E>>,
-    pub update_nodes: HashMap<Uid, FinalizedUpdateNode<T, E>>,
-    pub delete_nodes: HashMap<Uid, DeleteNode<T, E>>,
+pub struct FinalizedBlueprint<T: GraphTraits, E: EdgeTraits, L: GraphTraits = String> {
+    pub new_nodes: HashMap<Uid, NewNode<T, E, L>>,
+    pub update_nodes: HashMap<Uid, FinalizedUpdateNode<T, E, L>>,
+    pub delete_nodes: HashMap<Uid, DeleteNode<T, E, L>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits> FinalizedBlueprint<T, E> {
+impl<T: GraphTraits, E: EdgeTraits, L: GraphTraits> FinalizedBlueprint<T, E, L> {
     pub fn invert_blueprint(&self) -> Self {
         let inverted_update_nodes = self
             .update_nodes
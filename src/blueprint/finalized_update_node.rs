@@ -1,6 +1,6 @@
 use im::HashSet;
 
-use crate::prelude::{EdgeDescriptor, GraphTraits, Uid};
+use crate::prelude::{EdgeDescriptor, EdgeTraits, GraphTraits, Uid};
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
 pub struct UpdateNodeReplacementData<T: GraphTraits> {
@@ -8,17 +8,60 @@ pub struct UpdateNodeReplacementData<T: GraphTraits> {
     pub prev_data: T,
 }
 
+// The edge is kept without its payload, so the same update can be applied and undone
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct FinalizedUpdateNode<T: GraphTraits, E: GraphTraits> {
+pub struct EdgePayloadUpdate<E: EdgeTraits> {
+    pub edge: EdgeDescriptor<E>,
+    pub new_payload: Option<E::Payload>,
+    pub prev_payload: Option<E::Payload>,
+}
+
+impl<E: EdgeTraits> EdgePayloadUpdate<E> {
+    pub fn new(edge: &EdgeDescriptor<E>, new_payload: Option<E::Payload>) -> Self {
+        Self {
+            edge: edge.payload(None),
+            new_payload,
+            prev_payload: edge.payload.clone(),
+        }
+    }
+
+    pub fn prev_edge(&self) -> EdgeDescriptor<E> {
+        self.edge.payload(self.prev_payload.clone())
+    }
+
+    pub fn new_edge(&self) -> EdgeDescriptor<E> {
+        self.edge.payload(self.new_payload.clone())
+    }
+
+    // The same update from the perspective of the node on the other end of the edge
+    pub fn invert(&self) -> Self {
+        Self {
+            edge: self.edge.invert(),
+            ..self.clone()
+        }
+    }
+
+    pub fn history_invert(&self) -> Self {
+        Self {
+            edge: self.edge.clone(),
+            new_payload: self.prev_payload.clone(),
+            prev_payload: self.new_payload.clone(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Eq, Hash)]
+pub struct FinalizedUpdateNode<T: GraphTraits, E: EdgeTraits, L: GraphTraits = String> {
     pub id: Uid,
     pub replacement_data: Option<UpdateNodeReplacementData<T>>,
-    pub add_labels: Option<HashSet<String>>,
-    pub remove_labels: Option<HashSet<String>>,
+    pub add_labels: Option<HashSet<L>>,
+    pub remove_labels: Option<HashSet<L>>,
     pub add_edges: Option<HashSet<EdgeDescriptor<E>>>,
     pub remove_edges: Option<HashSet<EdgeDescriptor<E>>>,
+    pub update_edge_payloads: Option<HashSet<EdgePayloadUpdate<E>>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits> FinalizedUpdateNode<T, E> {
+impl<T: GraphTraits, E: EdgeTraits, L: GraphTraits> FinalizedUpdateNode<T, E, L> {
     pub fn history_invert(&self) -> Self {
         Self {
             id: self.id,
@@ -33,6 +76,12 @@ impl<T: GraphTraits, E: GraphTraits> FinalizedUpdateNode<T, E> {
             remove_labels: self.add_labels.clone(),
             add_edges: self.remove_edges.clone(),
             remove_edges: self.add_edges.clone(),
+            update_edge_payloads: self.update_edge_payloads.as_ref().map(|updates| {
+                updates
+                    .iter()
+                    .map(|update| update.history_invert())
+                    .collect()
+            }),
         }
     }
 }
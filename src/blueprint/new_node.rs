@@ -8,21 +8,23 @@ use super::delete_node::DeleteNode;
 pub type TempId = Uid;
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct NewNode<T: GraphTraits, E: GraphTraits> {
+pub struct NewNode<T: GraphTraits, E: EdgeTraits, L: GraphTraits = String> {
     pub id: Uid,
     pub temp_id: Option<TempId>,
     pub data: T,
-    pub add_labels: HashSet<String>,
+    pub add_labels: HashSet<L>,
     pub add_edges: HashSet<EdgeDescriptor<E>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits> Default for NewNode<T, E> {
+impl<T: GraphTraits, E: EdgeTraits, L: GraphTraits> Default for NewNode<T, E, L> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T: GraphTraits, E: GraphTraits> NewNode<T, E> {
+impl<T: GraphTraits, E: EdgeTraits, L: GraphTraits> NewNode<T, E, L> {
+    // The random id is only a placeholder. Nodes started by a blueprint are given a provisional id, which is replaced
+    // when the blueprint is processed, so their real id comes from the blueprint's temp id map.
     pub fn new() -> Self {
         Self {
             id: Uuid::new_v4().to_u128_le(),
@@ -63,7 +65,7 @@ impl<T: GraphTraits, E: GraphTraits> NewNode<T, E> {
 
     pub fn find_edges<A: GraphTraits>(
         &self,
-        edge_finder: &EdgeFinder<T, E, A>,
+        edge_finder: &EdgeFinder<T, E, A, L>,
     ) -> HashSet<EdgeDescriptor<E>> {
         let mut edges = HashSet::new();
         for edge in self.add_edges.iter() {
@@ -101,7 +103,7 @@ impl<T: GraphTraits, E: GraphTraits> NewNode<T, E> {
 
     pub fn get_render_edge<A: GraphTraits>(&self) -> Option<EdgeDescriptor<E>> {
         let render_edge =
-            self.find_edges(&EdgeFinder::<T, E, A>::new().render_info(Some(EdgeDir::Recv)));
+            self.find_edges(&EdgeFinder::<T, E, A, L>::new().render_info(Some(EdgeDir::Recv)));
         if render_edge.is_empty() {
             None
         } else {
@@ -109,7 +111,7 @@ impl<T: GraphTraits, E: GraphTraits> NewNode<T, E> {
         }
     }
 
-    pub fn history_invert(&self) -> DeleteNode<T, E> {
+    pub fn history_invert(&self) -> DeleteNode<T, E, L> {
         DeleteNode {
             id: self.id,
             data: self.data.clone(),
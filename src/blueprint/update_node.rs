@@ -2,17 +2,20 @@ use im::HashSet;
 
 use crate::prelude::*;
 
+use super::finalized_update_node::EdgePayloadUpdate;
+
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct UpdateNode<T: GraphTraits, E: GraphTraits> {
+pub struct UpdateNode<T: GraphTraits, E: EdgeTraits, L: GraphTraits = String> {
     pub id: Uid,
     pub replacement_data: Option<T>,
-    pub add_labels: Option<HashSet<String>>,
-    pub remove_labels: Option<HashSet<String>>,
+    pub add_labels: Option<HashSet<L>>,
+    pub remove_labels: Option<HashSet<L>>,
     pub add_edges: Option<HashSet<EdgeDescriptor<E>>>,
     pub remove_edges: Option<HashSet<EdgeDescriptor<E>>>,
+    pub update_edge_payloads: Option<HashSet<EdgePayloadUpdate<E>>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits> UpdateNode<T, E> {
+impl<T: GraphTraits, E: EdgeTraits, L: GraphTraits> UpdateNode<T, E, L> {
     pub fn new(id: Uid) -> Self {
         Self {
             id,
@@ -21,6 +24,7 @@ impl<T: GraphTraits, E: GraphTraits> UpdateNode<T, E> {
             remove_labels: None,
             add_edges: None,
             remove_edges: None,
+            update_edge_payloads: None,
         }
     }
 
@@ -78,6 +82,16 @@ impl<T: GraphTraits, E: GraphTraits> UpdateNode<T, E> {
             } else {
                 self.add_edges.clone()
             },
+            update_edge_payloads: if let Some(other_updates) = other.update_edge_payloads {
+                Some(
+                    self.update_edge_payloads
+                        .clone()
+                        .unwrap_or_default()
+                        .union(other_updates),
+                )
+            } else {
+                self.update_edge_payloads.clone()
+            },
         })
     }
 
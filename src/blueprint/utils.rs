@@ -1,11 +1,11 @@
 use leptos::logging::log;
 
-use crate::prelude::GraphTraits;
+use crate::prelude::{EdgeTraits, GraphTraits};
 
 use super::FinalizedBlueprint;
 
-pub fn log_finalize_results<T: GraphTraits, E: GraphTraits>(
-    build_blueprint: &FinalizedBlueprint<T, E>,
+pub fn log_finalize_results<T: GraphTraits, E: EdgeTraits, L: GraphTraits>(
+    build_blueprint: &FinalizedBlueprint<T, E, L>,
 ) {
     for node in build_blueprint.new_nodes.values() {
         log!("+ New Node {:?}", node.id);
@@ -36,6 +36,9 @@ pub fn log_finalize_results<T: GraphTraits, E: GraphTraits>(
         for label in node.remove_labels.iter() {
             log!("  - Label: {:?}", label);
         }
+        for update in node.update_edge_payloads.iter() {
+            log!("  ~ Edge Payload: {:?}", update);
+        }
     }
     for node in build_blueprint.delete_nodes.iter() {
         log!("- Delete Node {:?}", node);
@@ -1,34 +1,66 @@
-use super::{EdgeDir, GraphTraits, Uid};
+use std::cmp::Ordering;
+
+use super::{EdgeDir, EdgeTraits, GraphTraits, OrderKey, Uid};
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct EdgeDescriptor<E: GraphTraits> {
+pub struct EdgeDescriptor<E: EdgeTraits> {
+    // Shared by both sides of the edge pair, so parallel edges of the same type can be told apart
+    pub id: Uid,
     pub dir: EdgeDir,
     pub edge_type: E,
     pub host: Uid,
     pub target: Uid,
     pub render_info: Option<EdgeDir>,
+    // Kept the same on both sides of the edge pair
+    pub payload: Option<E::Payload>,
+    // Position among the edges of the same type and direction, assigned when a blueprint is finalized.
+    // Kept the same on both sides, but chosen relative to the siblings of the emitting side.
+    pub order_key: Option<OrderKey>,
 }
 
-impl<E: GraphTraits> EdgeDescriptor<E> {
+impl<E: EdgeTraits> EdgeDescriptor<E> {
+    // Edge ids are assigned by the blueprint which adds the edge.
+    // The edge type names the emitting side's type, so an edge added from the receiving side
+    // is stored the same way as one added from the emitting side.
     pub fn new(
+        id: Uid,
         host_node: Uid,
         edge_type: E,
         other_node: Uid,
         render_info: Option<EdgeDir>,
         direction: EdgeDir,
     ) -> Self {
+        let edge_type = match direction {
+            EdgeDir::Emit => edge_type,
+            EdgeDir::Recv => edge_type.inverse(),
+        };
         Self {
+            id,
             host: host_node,
             dir: direction,
             edge_type,
             target: other_node,
             render_info,
+            payload: None,
+            order_key: None,
+        }
+    }
+
+    pub fn set_id(&self, id: Uid) -> Self {
+        Self { id, ..self.clone() }
+    }
+
+    pub fn payload(&self, payload: Option<E::Payload>) -> Self {
+        Self {
+            payload,
+            ..self.clone()
         }
     }
 
     pub fn invert(&self) -> Self {
         Self {
-            edge_type: self.edge_type.clone(),
+            id: self.id,
+            edge_type: self.edge_type.inverse(),
             host: self.target,
             target: self.host,
             dir: self.dir.invert(),
@@ -36,6 +68,20 @@ impl<E: GraphTraits> EdgeDescriptor<E> {
                 .render_info
                 .clone()
                 .map(|render_info| render_info.invert()),
+            payload: self.payload.clone(),
+            order_key: self.order_key.clone(),
         }
     }
+
+    pub fn order_key(&self, order_key: Option<OrderKey>) -> Self {
+        Self {
+            order_key,
+            ..self.clone()
+        }
+    }
+
+    // Concurrent inserts at the same position can end up with equal keys, so the id breaks ties
+    pub fn cmp_order(&self, other: &Self) -> Ordering {
+        (&self.order_key, self.id).cmp(&(&other.order_key, other.id))
+    }
 }
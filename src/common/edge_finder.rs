@@ -1,15 +1,23 @@
 use core::fmt::Debug;
 use core::hash::Hash;
-use std::{rc::Rc};
+use std::rc::Rc;
 
-use im::{hashset, HashSet};
+use im::{hashset, vector, HashSet, Vector};
 
-use crate::{hooks::GetNodeClosure, prelude::reactive_node::read_reactive_node::ReadReactiveNode};
+use leptos::SignalWithUntracked;
 
-use super::{EdgeDescriptor, EdgeDir, GraphTraits, Uid};
+use crate::{
+    hooks::GetNodeClosure,
+    prelude::{reactive_node::read_reactive_node::ReadReactiveNode, DataPredicate},
+};
+
+use super::{
+    EdgeDescriptor, EdgeDir, EdgeGate, EdgeTraits, GraphError, GraphTraits, TraversalError, Uid,
+};
 
 #[derive(Clone)]
-pub struct EdgeFinder<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
+pub struct EdgeFinder<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub edge_id: Option<HashSet<Uid>>,
     pub edge_type: Option<HashSet<E>>,
     pub dir: Option<EdgeDir>,
     pub host: Option<HashSet<Uid>>,
@@ -17,60 +25,140 @@ pub struct EdgeFinder<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
     pub render_info: Option<Option<EdgeDir>>,
     // requires a reference to the graph in order to look up the node in question and run the closure against it
     // Note that this closure is run against the target node
-    pub gate_closure: Option<(
-        Rc<dyn Fn(&ReadReactiveNode<T, E, A>) -> bool>,
-        GetNodeClosure<T, E, A>,
-    )>,
+    pub gate_closure: Option<EdgeGate<T, E, A, L>>,
     pub match_all: Option<bool>,
+    // Whole finders combined with or/and/not. Checked alongside the fields above.
+    pub combinator: Option<Rc<EdgeFinderCombinator<T, E, A, L>>>,
+    // Conditions on the nodes at either end of the edge. Every listed label has to be present, or absent.
+    pub target_has_label: Option<HashSet<L>>,
+    pub target_lacks_label: Option<HashSet<L>>,
+    pub target_data: Option<DataPredicate<T>>,
+    pub host_has_label: Option<HashSet<L>>,
+    pub host_lacks_label: Option<HashSet<L>>,
+    pub host_data: Option<DataPredicate<T>>,
+    // Edges without a payload don't match
+    pub payload: Option<DataPredicate<E::Payload>>,
+    // Used to look up the nodes for the conditions above. Filled in by bind_graph when the finder is
+    // used through UseRoutableReturn or a blueprint. Without it, any edge which has node conditions won't match,
+    // so searches without a graph should go through ReadReactiveNode::try_search_for_edge.
+    pub get_node_closure: Option<GetNodeClosure<T, E, A, L>>,
+}
+
+#[derive(Clone, PartialEq, Debug, Eq, Hash)]
+pub enum EdgeFinderCombinator<
+    T: GraphTraits,
+    E: EdgeTraits,
+    A: GraphTraits,
+    L: GraphTraits = String,
+> {
+    Any(Vector<EdgeFinder<T, E, A, L>>),
+    All(Vector<EdgeFinder<T, E, A, L>>),
+    Not(Box<EdgeFinder<T, E, A, L>>),
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>
+    EdgeFinderCombinator<T, E, A, L>
+{
+    pub fn matches(&self, edge: &EdgeDescriptor<E>) -> bool {
+        match self {
+            Self::Any(edge_finders) => edge_finders.iter().any(|finder| finder.matches(edge)),
+            Self::All(edge_finders) => edge_finders.iter().all(|finder| finder.matches(edge)),
+            Self::Not(edge_finder) => !edge_finder.matches(edge),
+        }
+    }
+
+    fn map_finders(&self, map: impl Fn(&EdgeFinder<T, E, A, L>) -> EdgeFinder<T, E, A, L>) -> Self {
+        match self {
+            Self::Any(edge_finders) => Self::Any(edge_finders.iter().map(map).collect()),
+            Self::All(edge_finders) => Self::All(edge_finders.iter().map(map).collect()),
+            Self::Not(edge_finder) => Self::Not(Box::new(map(edge_finder))),
+        }
+    }
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> PartialEq for EdgeFinder<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> PartialEq
+    for EdgeFinder<T, E, A, L>
+{
     fn eq(&self, other: &Self) -> bool {
-        self.edge_type == other.edge_type
+        self.edge_id == other.edge_id
+            && self.edge_type == other.edge_type
             && self.dir == other.dir
             && self.host == other.host
             && self.target == other.target
             && self.render_info == other.render_info
-            && self.gate_closure.is_some() == other.gate_closure.is_some()
+            && self.gate_closure == other.gate_closure
             && self.match_all == other.match_all
+            && self.combinator == other.combinator
+            && self.target_has_label == other.target_has_label
+            && self.target_lacks_label == other.target_lacks_label
+            && self.target_data == other.target_data
+            && self.host_has_label == other.host_has_label
+            && self.host_lacks_label == other.host_lacks_label
+            && self.host_data == other.host_data
+            && self.payload == other.payload
     }
 }
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> Eq for EdgeFinder<T, E, A> {}
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> Debug for EdgeFinder<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Eq for EdgeFinder<T, E, A, L> {}
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Debug
+    for EdgeFinder<T, E, A, L>
+{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("EdgeFinder")
+            .field("edge_id", &self.edge_id)
             .field("edge_type", &self.edge_type)
             .field("dir", &self.dir)
             .field("host", &self.host)
             .field("target", &self.target)
             .field("render_info", &self.render_info)
-            .field("gate_closure", &self.gate_closure.is_some())
+            .field("gate_closure", &self.gate_closure)
             .field("match_all", &self.match_all)
+            .field("combinator", &self.combinator)
+            .field("target_has_label", &self.target_has_label)
+            .field("target_lacks_label", &self.target_lacks_label)
+            .field("target_data", &self.target_data)
+            .field("host_has_label", &self.host_has_label)
+            .field("host_lacks_label", &self.host_lacks_label)
+            .field("host_data", &self.host_data)
+            .field("payload", &self.payload)
             .finish()
     }
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> Hash for EdgeFinder<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Hash
+    for EdgeFinder<T, E, A, L>
+{
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.edge_id.hash(state);
         self.edge_type.hash(state);
         self.dir.hash(state);
         self.host.hash(state);
         self.target.hash(state);
         self.render_info.hash(state);
-        self.gate_closure.is_some().hash(state);
+        self.gate_closure.hash(state);
         self.match_all.hash(state);
+        self.combinator.hash(state);
+        self.target_has_label.hash(state);
+        self.target_lacks_label.hash(state);
+        self.target_data.hash(state);
+        self.host_has_label.hash(state);
+        self.host_lacks_label.hash(state);
+        self.host_data.hash(state);
+        self.payload.hash(state);
     }
 }
 
-impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> Default for EdgeFinder<T, E, A> {
+impl<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Default
+    for EdgeFinder<T, E, A, L>
+{
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> EdgeFinder<T, E, A> {
+impl<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> EdgeFinder<T, E, A, L> {
     pub fn new() -> Self {
         Self {
+            edge_id: None,
             edge_type: None,
             dir: None,
             host: None,
@@ -78,6 +166,32 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> EdgeFinder<T, E, A> {
             render_info: None,
             gate_closure: None,
             match_all: None,
+            combinator: None,
+            target_has_label: None,
+            target_lacks_label: None,
+            target_data: None,
+            host_has_label: None,
+            host_lacks_label: None,
+            host_data: None,
+            payload: None,
+            get_node_closure: None,
+        }
+    }
+
+    // Targets one specific edge of a pair, even among parallel edges of the same type
+    pub fn edge_id(&self, edge_id: Uid) -> Self {
+        Self {
+            edge_id: Some(hashset![edge_id]),
+            ..self.clone()
+        }
+    }
+    pub fn edge_ids<I>(&self, edge_ids: I) -> Self
+    where
+        I: IntoIterator<Item = Uid>,
+    {
+        Self {
+            edge_id: Some(edge_ids.into_iter().collect::<HashSet<Uid>>()),
+            ..self.clone()
         }
     }
 
@@ -155,46 +269,323 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> EdgeFinder<T, E, A> {
 
     pub fn gate_closure(
         &self,
-        gate_closure: impl Fn(&ReadReactiveNode<T, E, A>) -> bool + 'static,
-        get_node_closure: GetNodeClosure<T, E, A>,
+        gate_closure: impl Fn(&ReadReactiveNode<T, E, A, L>) -> bool + 'static,
+        get_node_closure: GetNodeClosure<T, E, A, L>,
     ) -> Self {
+        self.gate(EdgeGate::new(gate_closure, get_node_closure))
+    }
+
+    // Use PredicateRegistry::edge_gate for a gate which can be compared and serialized by its key
+    pub fn gate(&self, gate: EdgeGate<T, E, A, L>) -> Self {
         Self {
-            gate_closure: Some((Rc::new(gate_closure), get_node_closure)),
+            gate_closure: Some(gate),
             ..self.clone()
         }
     }
 
     pub fn invert(&self) -> Self {
         Self {
-            edge_type: self.edge_type.clone(),
+            edge_id: self.edge_id.clone(),
+            edge_type: self.inverse_edge_types(),
             dir: self.dir.clone().map(|d| d.invert()),
             host: self.target.clone(),
             target: self.host.clone(),
             render_info: self.render_info.clone().map(|ir| ir.map(|ir| ir.invert())),
             gate_closure: self.gate_closure.clone(),
             match_all: self.match_all,
+            combinator: self.combinator.as_ref().map(|combinator| {
+                Rc::new(combinator.map_finders(|edge_finder| edge_finder.invert()))
+            }),
+            ..self.invert_node_conditions()
         }
     }
 
     pub fn invert_drop_closure(&self) -> Self {
         Self {
-            edge_type: self.edge_type.clone(),
+            edge_id: self.edge_id.clone(),
+            edge_type: self.inverse_edge_types(),
             dir: self.dir.clone().map(|d| d.invert()),
             host: self.target.clone(),
             target: self.host.clone(),
             render_info: self.render_info.clone().map(|ir| ir.map(|ir| ir.invert())),
             gate_closure: None,
             match_all: self.match_all,
+            combinator: self
+                .combinator
+                .as_ref()
+                .and_then(|combinator| match combinator.as_ref() {
+                    // Dropping a gate under a Not would reject edges the gate may have let through, so the Not is dropped instead
+                    EdgeFinderCombinator::Not(edge_finder) if edge_finder.has_gate_closure() => {
+                        None
+                    }
+                    _ => {
+                        Some(Rc::new(combinator.map_finders(|edge_finder| {
+                            edge_finder.invert_drop_closure()
+                        })))
+                    }
+                }),
+            ..self.invert_node_conditions()
+        }
+    }
+
+    fn inverse_edge_types(&self) -> Option<HashSet<E>> {
+        self.edge_type.as_ref().map(|edge_types| {
+            edge_types
+                .iter()
+                .map(|edge_type| edge_type.inverse())
+                .collect()
+        })
+    }
+
+    fn invert_node_conditions(&self) -> Self {
+        Self {
+            target_has_label: self.host_has_label.clone(),
+            target_lacks_label: self.host_lacks_label.clone(),
+            target_data: self.host_data.clone(),
+            host_has_label: self.target_has_label.clone(),
+            host_lacks_label: self.target_lacks_label.clone(),
+            host_data: self.target_data.clone(),
+            ..self.clone()
+        }
+    }
+
+    pub fn target_has_label(&self, label: impl Into<L>) -> Self {
+        Self {
+            target_has_label: Some(add_label(&self.target_has_label, label.into())),
+            ..self.clone()
+        }
+    }
+
+    pub fn target_lacks_label(&self, label: impl Into<L>) -> Self {
+        Self {
+            target_lacks_label: Some(add_label(&self.target_lacks_label, label.into())),
+            ..self.clone()
+        }
+    }
+
+    pub fn target_data(
+        &self,
+        key: impl Into<String>,
+        predicate: impl Fn(&T) -> bool + 'static,
+    ) -> Self {
+        Self {
+            target_data: Some(DataPredicate::new(key, predicate)),
+            ..self.clone()
+        }
+    }
+
+    // Predicates taken from a PredicateRegistry keep the finder serializable
+    pub fn target_data_predicate(&self, predicate: DataPredicate<T>) -> Self {
+        Self {
+            target_data: Some(predicate),
+            ..self.clone()
+        }
+    }
+
+    pub fn host_has_label(&self, label: impl Into<L>) -> Self {
+        Self {
+            host_has_label: Some(add_label(&self.host_has_label, label.into())),
+            ..self.clone()
+        }
+    }
+
+    pub fn host_lacks_label(&self, label: impl Into<L>) -> Self {
+        Self {
+            host_lacks_label: Some(add_label(&self.host_lacks_label, label.into())),
+            ..self.clone()
+        }
+    }
+
+    pub fn host_data(
+        &self,
+        key: impl Into<String>,
+        predicate: impl Fn(&T) -> bool + 'static,
+    ) -> Self {
+        Self {
+            host_data: Some(DataPredicate::new(key, predicate)),
+            ..self.clone()
+        }
+    }
+
+    pub fn host_data_predicate(&self, predicate: DataPredicate<T>) -> Self {
+        Self {
+            host_data: Some(predicate),
+            ..self.clone()
+        }
+    }
+
+    pub fn payload(
+        &self,
+        key: impl Into<String>,
+        predicate: impl Fn(&E::Payload) -> bool + 'static,
+    ) -> Self {
+        Self {
+            payload: Some(DataPredicate::new(key, predicate)),
+            ..self.clone()
+        }
+    }
+
+    pub fn payload_predicate(&self, predicate: DataPredicate<E::Payload>) -> Self {
+        Self {
+            payload: Some(predicate),
+            ..self.clone()
+        }
+    }
+
+    // Keeps any closure the finder, or a finder combined into it, was already bound to
+    pub fn bind_graph(&self, get_node_closure: &GetNodeClosure<T, E, A, L>) -> Self {
+        Self {
+            get_node_closure: Some(
+                self.get_node_closure
+                    .clone()
+                    .unwrap_or_else(|| get_node_closure.clone()),
+            ),
+            combinator: self.combinator.as_ref().map(|combinator| {
+                Rc::new(
+                    combinator.map_finders(|edge_finder| edge_finder.bind_graph(get_node_closure)),
+                )
+            }),
+            ..self.clone()
+        }
+    }
+
+    pub fn node_conditions_match(&self, edge: &EdgeDescriptor<E>) -> bool {
+        self.endpoint_matches(
+            &edge.target,
+            &self.target_has_label,
+            &self.target_lacks_label,
+            &self.target_data,
+        ) && self.endpoint_matches(
+            &edge.host,
+            &self.host_has_label,
+            &self.host_lacks_label,
+            &self.host_data,
+        )
+    }
+
+    fn endpoint_matches(
+        &self,
+        id: &Uid,
+        has_label: &Option<HashSet<L>>,
+        lacks_label: &Option<HashSet<L>>,
+        data: &Option<DataPredicate<T>>,
+    ) -> bool {
+        if has_label.is_none() && lacks_label.is_none() && data.is_none() {
+            return true;
+        }
+        let Some(Ok(node)) = self.get_node_closure.as_ref().map(|get_node| get_node(id)) else {
+            return false;
+        };
+        node.labels.with_untracked(|labels| {
+            has_label
+                .as_ref()
+                .is_none_or(|has_label| has_label.iter().all(|label| labels.contains(label)))
+                && lacks_label.as_ref().is_none_or(|lacks_label| {
+                    !lacks_label.iter().any(|label| labels.contains(label))
+                })
+        }) && data.as_ref().is_none_or(|data| {
+            node.data
+                .with_untracked(|node_data| data.matches(node_data))
+        })
+    }
+
+    // Replaces the node and edge ids the finder refers to, including those of combined finders
+    pub(crate) fn map_ids(&self, map: &impl Fn(Uid) -> Uid) -> Self {
+        let map_ids = |ids: &Option<HashSet<Uid>>| {
+            ids.as_ref()
+                .map(|ids| ids.iter().map(|id| map(*id)).collect())
+        };
+        Self {
+            edge_id: map_ids(&self.edge_id),
+            host: map_ids(&self.host),
+            target: map_ids(&self.target),
+            combinator: self.combinator.as_ref().map(|combinator| {
+                Rc::new(combinator.map_finders(|edge_finder| edge_finder.map_ids(map)))
+            }),
+            ..self.clone()
+        }
+    }
+
+    // Fails if the finder, or a finder combined into it, has node conditions but no graph to look the nodes up in
+    pub fn check_bound(&self) -> Result<(), GraphError> {
+        let has_node_conditions = self.target_has_label.is_some()
+            || self.target_lacks_label.is_some()
+            || self.target_data.is_some()
+            || self.host_has_label.is_some()
+            || self.host_lacks_label.is_some()
+            || self.host_data.is_some();
+        if has_node_conditions && self.get_node_closure.is_none() {
+            return Err(GraphError::Traversal(TraversalError::UnboundEdgeFinder));
+        }
+        match self.combinator.as_deref() {
+            Some(EdgeFinderCombinator::Any(edge_finders))
+            | Some(EdgeFinderCombinator::All(edge_finders)) => edge_finders
+                .iter()
+                .try_for_each(|edge_finder| edge_finder.check_bound()),
+            Some(EdgeFinderCombinator::Not(edge_finder)) => edge_finder.check_bound(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn has_gate_closure(&self) -> bool {
+        self.gate_closure.is_some()
+            || self
+                .combinator
+                .as_ref()
+                .is_some_and(|combinator| match combinator.as_ref() {
+                    EdgeFinderCombinator::Any(edge_finders)
+                    | EdgeFinderCombinator::All(edge_finders) => {
+                        edge_finders.iter().any(|finder| finder.has_gate_closure())
+                    }
+                    EdgeFinderCombinator::Not(edge_finder) => edge_finder.has_gate_closure(),
+                })
+    }
+
+    // Combining finders: the fields of the combined finder are left unset, so further builder calls narrow the whole combination.
+    // match_all is a search setting rather than a condition, so it is carried over from either side.
+    pub fn or(&self, other: EdgeFinder<T, E, A, L>) -> Self {
+        Self::combine(
+            other.match_all.or(self.match_all),
+            EdgeFinderCombinator::Any(vector![self.clone(), other]),
+        )
+    }
+
+    pub fn and(&self, other: EdgeFinder<T, E, A, L>) -> Self {
+        Self::combine(
+            other.match_all.or(self.match_all),
+            EdgeFinderCombinator::All(vector![self.clone(), other]),
+        )
+    }
+
+    pub fn not(&self) -> Self {
+        Self::combine(
+            self.match_all,
+            EdgeFinderCombinator::Not(Box::new(self.clone())),
+        )
+    }
+
+    fn combine(match_all: Option<bool>, combinator: EdgeFinderCombinator<T, E, A, L>) -> Self {
+        Self {
+            match_all,
+            combinator: Some(Rc::new(combinator)),
+            ..Self::new()
         }
     }
 
     // pub fn get_target_node_if_matches(
     //     &self,
-    //     get_node_closure: GetNodeClosure<T, E, A>,
-    // ) -> Option<ReadReactiveNode<T, E, A>> {
+    //     get_node_closure: GetNodeClosure<T, E, A, L>,
+    // ) -> Option<ReadReactiveNode<T, E, A, L>> {
     // }
 
     pub fn matches(&self, edge: &EdgeDescriptor<E>) -> bool {
+        let edge_id_matches = self
+            .edge_id
+            .as_ref()
+            .is_none_or(|edge_ids| edge_ids.contains(&edge.id));
+        if !edge_id_matches {
+            return false;
+        }
         let edge_type_matches = self
             .edge_type
             .as_ref()
@@ -203,7 +594,10 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> EdgeFinder<T, E, A> {
         if !edge_type_matches {
             return false;
         }
-        let direction_matches = self.dir.as_ref().map(|d| d == &edge.dir).unwrap_or(true);
+        let direction_matches = self
+            .dir
+            .as_ref()
+            .is_none_or(|d| d == &edge.dir || edge.edge_type.is_symmetric());
         if !direction_matches {
             return false;
         }
@@ -231,20 +625,33 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> EdgeFinder<T, E, A> {
         if !render_info_matches {
             return false;
         }
-        let gate_closure_matches =
-            self.gate_closure
-                .as_ref()
-                .map_or(true, |(gate_closure, get_node)| {
-                    if let Ok(node) = get_node(&edge.target) {
-                        gate_closure(&node)
-                    } else {
-                        false
-                    }
-                });
+        let gate_closure_matches = self
+            .gate_closure
+            .as_ref()
+            .is_none_or(|gate| gate.allows(&edge.target));
         if !gate_closure_matches {
             return false;
         }
 
-        true
+        let payload_matches = self.payload.as_ref().is_none_or(|predicate| {
+            edge.payload
+                .as_ref()
+                .is_some_and(|payload| predicate.matches(payload))
+        });
+        if !payload_matches {
+            return false;
+        }
+
+        if !self.node_conditions_match(edge) {
+            return false;
+        }
+
+        self.combinator
+            .as_ref()
+            .is_none_or(|combinator| combinator.matches(edge))
     }
 }
+
+fn add_label<L: GraphTraits>(labels: &Option<HashSet<L>>, label: L) -> HashSet<L> {
+    labels.clone().unwrap_or_default().update(label)
+}
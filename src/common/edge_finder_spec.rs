@@ -0,0 +1,151 @@
+use std::rc::Rc;
+
+use im::{HashSet, Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+// A closure-free description of an EdgeFinder which can be saved and bound to a graph later
+// The gate, data and payload predicates are keys of predicates in the PredicateRegistry used when binding
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct EdgeFinderSpec<E: EdgeTraits, L: GraphTraits = String> {
+    pub edge_id: Option<HashSet<Uid>>,
+    pub edge_type: Option<HashSet<E>>,
+    pub dir: Option<EdgeDir>,
+    pub host: Option<HashSet<Uid>>,
+    pub target: Option<HashSet<Uid>>,
+    pub render_info: Option<Option<EdgeDir>>,
+    pub gate: Option<String>,
+    pub match_all: Option<bool>,
+    pub combinator: Option<Box<EdgeFinderCombinatorSpec<E, L>>>,
+    pub target_has_label: Option<HashSet<L>>,
+    pub target_lacks_label: Option<HashSet<L>>,
+    pub target_data: Option<String>,
+    pub host_has_label: Option<HashSet<L>>,
+    pub host_lacks_label: Option<HashSet<L>>,
+    pub host_data: Option<String>,
+    pub payload: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Serialize, Deserialize)]
+pub enum EdgeFinderCombinatorSpec<E: EdgeTraits, L: GraphTraits = String> {
+    Any(Vector<EdgeFinderSpec<E, L>>),
+    All(Vector<EdgeFinderSpec<E, L>>),
+    Not(Box<EdgeFinderSpec<E, L>>),
+}
+
+impl<E: EdgeTraits, L: GraphTraits> EdgeFinderSpec<E, L> {
+    pub fn bind<T: GraphTraits, A: GraphTraits>(
+        &self,
+        registry: &PredicateRegistry<T, E, A, L>,
+        get_node_closure: GetNodeClosure<T, E, A, L>,
+    ) -> Result<EdgeFinder<T, E, A, L>, GraphError> {
+        let bind_all = |edge_finders: &Vector<EdgeFinderSpec<E, L>>| {
+            edge_finders
+                .iter()
+                .map(|edge_finder| edge_finder.bind(registry, get_node_closure.clone()))
+                .collect::<Result<Vector<_>, _>>()
+        };
+        let combinator = match self.combinator.as_deref() {
+            Some(EdgeFinderCombinatorSpec::Any(edge_finders)) => {
+                Some(EdgeFinderCombinator::Any(bind_all(edge_finders)?))
+            }
+            Some(EdgeFinderCombinatorSpec::All(edge_finders)) => {
+                Some(EdgeFinderCombinator::All(bind_all(edge_finders)?))
+            }
+            Some(EdgeFinderCombinatorSpec::Not(edge_finder)) => Some(EdgeFinderCombinator::Not(
+                Box::new(edge_finder.bind(registry, get_node_closure.clone())?),
+            )),
+            None => None,
+        };
+        let gate_closure = match &self.gate {
+            Some(key) => Some(registry.edge_gate(key, get_node_closure.clone())?),
+            None => None,
+        };
+        let bind_data = |key: &Option<String>| {
+            key.as_ref()
+                .map(|key| registry.get_data_predicate(key))
+                .transpose()
+        };
+        Ok(EdgeFinder {
+            edge_id: self.edge_id.clone(),
+            edge_type: self.edge_type.clone(),
+            dir: self.dir.clone(),
+            host: self.host.clone(),
+            target: self.target.clone(),
+            render_info: self.render_info.clone(),
+            gate_closure,
+            match_all: self.match_all,
+            combinator: combinator.map(Rc::new),
+            target_has_label: self.target_has_label.clone(),
+            target_lacks_label: self.target_lacks_label.clone(),
+            target_data: bind_data(&self.target_data)?,
+            host_has_label: self.host_has_label.clone(),
+            host_lacks_label: self.host_lacks_label.clone(),
+            host_data: bind_data(&self.host_data)?,
+            payload: self
+                .payload
+                .as_ref()
+                .map(|key| registry.get_payload_predicate(key))
+                .transpose()?,
+            get_node_closure: Some(get_node_closure),
+        })
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> EdgeFinder<T, E, A, L> {
+    // Fails if the finder has a gate closure or data predicate which wasn't taken from a PredicateRegistry,
+    // since binding the spec looks its key up there
+    pub fn to_spec(&self) -> Result<EdgeFinderSpec<E, L>, GraphError> {
+        let gate = match &self.gate_closure {
+            Some(EdgeGate { key: Some(key), .. }) => Some(key.clone()),
+            Some(EdgeGate { key: None, .. }) => {
+                return Err(GraphError::Traversal(
+                    TraversalError::UnserializablePredicate,
+                ))
+            }
+            None => None,
+        };
+        let all_to_spec = |edge_finders: &Vector<EdgeFinder<T, E, A, L>>| {
+            edge_finders
+                .iter()
+                .map(|edge_finder| edge_finder.to_spec())
+                .collect::<Result<Vector<_>, _>>()
+        };
+        let combinator = match self.combinator.as_deref() {
+            Some(EdgeFinderCombinator::Any(edge_finders)) => {
+                Some(EdgeFinderCombinatorSpec::Any(all_to_spec(edge_finders)?))
+            }
+            Some(EdgeFinderCombinator::All(edge_finders)) => {
+                Some(EdgeFinderCombinatorSpec::All(all_to_spec(edge_finders)?))
+            }
+            Some(EdgeFinderCombinator::Not(edge_finder)) => Some(EdgeFinderCombinatorSpec::Not(
+                Box::new(edge_finder.to_spec()?),
+            )),
+            None => None,
+        };
+        fn data_to_spec<D: GraphTraits>(
+            data: &Option<DataPredicate<D>>,
+        ) -> Result<Option<String>, GraphError> {
+            data.as_ref().map(|data| data.to_spec()).transpose()
+        }
+        Ok(EdgeFinderSpec {
+            edge_id: self.edge_id.clone(),
+            edge_type: self.edge_type.clone(),
+            dir: self.dir.clone(),
+            host: self.host.clone(),
+            target: self.target.clone(),
+            render_info: self.render_info.clone(),
+            gate,
+            match_all: self.match_all,
+            combinator: combinator.map(Box::new),
+            target_has_label: self.target_has_label.clone(),
+            target_lacks_label: self.target_lacks_label.clone(),
+            target_data: data_to_spec(&self.target_data)?,
+            host_has_label: self.host_has_label.clone(),
+            host_lacks_label: self.host_lacks_label.clone(),
+            host_data: data_to_spec(&self.host_data)?,
+            payload: data_to_spec(&self.payload)?,
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use core::fmt::Debug;
+use core::hash::Hash;
+use std::rc::Rc;
+
+use crate::prelude::{reactive_node::read_reactive_node::ReadReactiveNode, *};
+
+// The gate of an EdgeFinder. Run against the target node, which is looked up through get_node_closure.
+// Gates from a PredicateRegistry keep their key, which is what equality, hashing and serialization use.
+// Gates without a key are only equal to clones of themselves.
+#[derive(Clone)]
+pub struct EdgeGate<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub key: Option<String>,
+    pub predicate: GatePredicate<T, E, A, L>,
+    pub get_node_closure: GetNodeClosure<T, E, A, L>,
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> EdgeGate<T, E, A, L> {
+    pub fn new(
+        predicate: impl Fn(&ReadReactiveNode<T, E, A, L>) -> bool + 'static,
+        get_node_closure: GetNodeClosure<T, E, A, L>,
+    ) -> Self {
+        Self {
+            key: None,
+            predicate: Rc::new(predicate),
+            get_node_closure,
+        }
+    }
+
+    pub fn allows(&self, target: &Uid) -> bool {
+        (self.get_node_closure)(target).is_ok_and(|node| (self.predicate)(&node))
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> PartialEq
+    for EdgeGate<T, E, A, L>
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.key, &other.key) {
+            (Some(key), Some(other_key)) => key == other_key,
+            (None, None) => Rc::ptr_eq(&self.predicate, &other.predicate),
+            _ => false,
+        }
+    }
+}
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Eq for EdgeGate<T, E, A, L> {}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Hash for EdgeGate<T, E, A, L> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        match &self.key {
+            Some(key) => key.hash(state),
+            None => (Rc::as_ptr(&self.predicate) as *const ()).hash(state),
+        }
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Debug for EdgeGate<T, E, A, L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.key {
+            Some(key) => f.debug_tuple("EdgeGate").field(key).finish(),
+            None => f
+                .debug_tuple("EdgeGate")
+                .field(&Rc::as_ptr(&self.predicate))
+                .finish(),
+        }
+    }
+}
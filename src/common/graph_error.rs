@@ -1,9 +1,41 @@
-use std::error::Error;
+use std::{
+    any::Any,
+    error::Error,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use super::{EdgeDir, GraphTraits, Uid};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GraphError {
     Blueprint(String),
     Traversal(TraversalError),
+    // Every violation the blueprint would cause
+    Schema(Vec<SchemaError>),
+}
+
+impl GraphError {
+    // Combines the errors of a failed finalize into one, keeping every schema violation.
+    // Other errors stop the blueprint on their own, so the first one is kept.
+    pub fn from_errors(errors: Vec<GraphError>) -> Self {
+        let schema_errors = errors
+            .iter()
+            .filter_map(|error| match error {
+                GraphError::Schema(schema_errors) => Some(schema_errors.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        match errors
+            .into_iter()
+            .find(|error| !matches!(error, GraphError::Schema(_)))
+        {
+            Some(error) => error,
+            None => GraphError::Schema(schema_errors),
+        }
+    }
 }
 
 impl std::fmt::Display for GraphError {
@@ -11,6 +43,16 @@ impl std::fmt::Display for GraphError {
         match self {
             GraphError::Blueprint(e) => write!(f, "Blueprint Error: {}", e),
             GraphError::Traversal(e) => write!(f, "Traversal Error: {}", e),
+            GraphError::Schema(errors) => {
+                write!(f, "Schema Error: ")?;
+                for (index, error) in errors.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", error)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -19,6 +61,7 @@ impl Error for GraphError {
         match self {
             GraphError::Blueprint(_) => None,
             GraphError::Traversal(_) => None,
+            GraphError::Schema(_) => None,
         }
     }
 }
@@ -28,6 +71,14 @@ pub enum TraversalError {
     NotFound,
     InternalError,
     TotalRollback,
+    // A saved query refers to a predicate key which isn't in the registry
+    UnknownPredicate(String),
+    // Closures which aren't registered under a key can't be saved
+    UnserializablePredicate,
+    // A pattern edge refers to a node name which isn't in the pattern
+    UnknownPatternNode(String),
+    // An edge finder with label or data conditions was used without a graph to look the nodes up in
+    UnboundEdgeFinder,
 }
 
 impl std::fmt::Display for TraversalError {
@@ -36,6 +87,144 @@ impl std::fmt::Display for TraversalError {
             TraversalError::NotFound => write!(f, "Traversal Error: Not Found"),
             TraversalError::InternalError => write!(f, "Traversal Error: Internal Error"),
             TraversalError::TotalRollback => write!(f, "Traversal Error: Total Rollback"),
+            TraversalError::UnknownPredicate(key) => {
+                write!(f, "Traversal Error: Unknown Predicate, Key: {:?}", key)
+            }
+            TraversalError::UnserializablePredicate => {
+                write!(f, "Traversal Error: Predicate has no key to serialize")
+            }
+            TraversalError::UnknownPatternNode(name) => {
+                write!(f, "Traversal Error: Unknown Pattern Node, Name: {:?}", name)
+            }
+            TraversalError::UnboundEdgeFinder => {
+                write!(
+                    f,
+                    "Traversal Error: Edge finder has node conditions but no graph"
+                )
+            }
+        }
+    }
+}
+
+// An edge type or node kind in a SchemaError, which can be downcast back to the graph's type.
+// Kept type-erased so the error doesn't depend on the graph's types.
+#[derive(Clone)]
+pub struct SchemaValue(Rc<dyn AnyGraphValue>);
+
+impl SchemaValue {
+    pub fn new<V: GraphTraits>(value: V) -> Self {
+        Self(Rc::new(value))
+    }
+
+    pub fn downcast_ref<V: GraphTraits>(&self) -> Option<&V> {
+        self.0.as_any().downcast_ref()
+    }
+}
+
+impl Debug for SchemaValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PartialEq for SchemaValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_any(other.0.as_any())
+    }
+}
+
+impl Eq for SchemaValue {}
+
+impl Hash for SchemaValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash_any(state);
+    }
+}
+
+trait AnyGraphValue: Debug {
+    fn as_any(&self) -> &dyn Any;
+    fn eq_any(&self, other: &dyn Any) -> bool;
+    fn hash_any(&self, state: &mut dyn Hasher);
+}
+
+impl<V: GraphTraits> AnyGraphValue for V {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn eq_any(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<V>() == Some(self)
+    }
+    fn hash_any(&self, mut state: &mut dyn Hasher) {
+        self.hash(&mut state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SchemaError {
+    // The node's kind isn't allowed at its end of the edge type
+    DisallowedKind {
+        node: Uid,
+        kind: SchemaValue,
+        edge_type: SchemaValue,
+        dir: EdgeDir,
+    },
+    // The node's count of edges of the type in the direction is outside the allowed cardinality
+    Cardinality {
+        node: Uid,
+        edge_type: SchemaValue,
+        dir: EdgeDir,
+        count: usize,
+    },
+    // Edges of an acyclic type would form a cycle. The path starts and ends on the same node.
+    Cycle {
+        edge_type: SchemaValue,
+        path: Vec<Uid>,
+    },
+    // The node can't be deleted while an edge of a restricting type connects it to a remaining node
+    DeleteRestricted {
+        node: Uid,
+        edge_type: SchemaValue,
+        target: Uid,
+    },
+}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::DisallowedKind {
+                node,
+                kind,
+                edge_type,
+                dir,
+            } => write!(
+                f,
+                "Node {:?} of kind {:?} isn't allowed to {:?} edges of type {:?}",
+                node, kind, dir, edge_type
+            ),
+            SchemaError::Cardinality {
+                node,
+                edge_type,
+                dir,
+                count,
+            } => write!(
+                f,
+                "Node {:?} would have {} {:?} edges of type {:?}, outside the allowed cardinality",
+                node, count, dir, edge_type
+            ),
+            SchemaError::Cycle { edge_type, path } => write!(
+                f,
+                "Edges of acyclic type {:?} would form a cycle: {:?}",
+                edge_type, path
+            ),
+            SchemaError::DeleteRestricted {
+                node,
+                edge_type,
+                target,
+            } => write!(
+                f,
+                "Node {:?} can't be deleted while its edge of type {:?} to node {:?} remains",
+                node, edge_type, target
+            ),
         }
     }
 }
@@ -0,0 +1,110 @@
+use std::{cell::Cell, fmt::Debug, rc::Rc};
+
+use uuid::Uuid;
+
+use super::Uid;
+
+// Produces the ids of new nodes and edges. Clones share the same sequence.
+#[derive(Clone)]
+pub struct IdGenerator(Rc<dyn Fn() -> Uid>);
+
+impl IdGenerator {
+    // Any source of ids, e.g. keys handed out by a backend
+    pub fn new(generate: impl Fn() -> Uid + 'static) -> Self {
+        Self(Rc::new(generate))
+    }
+
+    // Random v4 UUIDs
+    pub fn random() -> Self {
+        Self::new(|| Uuid::new_v4().to_u128_le())
+    }
+
+    // v7 UUIDs, which sort by the time they were generated
+    pub fn time_sortable() -> Self {
+        Self::new(|| Uuid::now_v7().as_u128())
+    }
+
+    // start, start + 1, start + 2, ...
+    pub fn sequential(start: Uid) -> Self {
+        let next = Cell::new(start);
+        Self::new(move || {
+            let id = next.get();
+            next.set(id + 1);
+            id
+        })
+    }
+
+    // Random looking ids which are the same for the same seed on every run
+    pub fn seeded(seed: u64) -> Self {
+        let state = Cell::new(seed);
+        let next_u64 = move || {
+            // SplitMix64
+            state.set(state.get().wrapping_add(0x9e3779b97f4a7c15));
+            let mut z = state.get();
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        };
+        Self::new(move || ((next_u64() as Uid) << 64) | next_u64() as Uid)
+    }
+
+    pub fn next_id(&self) -> Uid {
+        (self.0)()
+    }
+}
+
+impl Default for IdGenerator {
+    fn default() -> Self {
+        Self::random()
+    }
+}
+
+// Two generators are only equal if they share the same sequence
+impl PartialEq for IdGenerator {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl Eq for IdGenerator {}
+
+impl Debug for IdGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdGenerator").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::IdGenerator;
+
+    fn take(id_generator: &IdGenerator, count: usize) -> Vec<u128> {
+        (0..count).map(|_| id_generator.next_id()).collect()
+    }
+
+    #[test]
+    fn should_generate_ids_from_each_source() {
+        assert_eq!(take(&IdGenerator::sequential(7), 3), vec![7, 8, 9]);
+
+        // The same seed gives the same ids, and clones continue the same sequence
+        let seeded = IdGenerator::seeded(42);
+        let first_ids = take(&seeded, 2);
+        assert_eq!(
+            [first_ids.clone(), take(&seeded.clone(), 2)].concat(),
+            take(&IdGenerator::seeded(42), 4)
+        );
+        assert_ne!(first_ids, take(&IdGenerator::seeded(43), 2));
+        assert_ne!(first_ids[0], first_ids[1]);
+
+        let time_sortable_ids = take(&IdGenerator::time_sortable(), 50);
+        assert!(time_sortable_ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let next = Cell::new(0);
+        let custom = IdGenerator::new(move || {
+            next.set(next.get() + 10);
+            next.get()
+        });
+        assert_eq!(take(&custom, 2), vec![10, 20]);
+    }
+}
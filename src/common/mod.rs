@@ -1,18 +1,27 @@
 use core::fmt::Debug;
 use core::hash::Hash;
 
-
-
+use serde::{Deserialize, Serialize};
 
 mod edge_descriptor;
 mod edge_finder;
+mod edge_finder_spec;
+mod edge_gate;
 mod graph_error;
+mod id_generator;
+mod order_key;
+mod predicate_registry;
 
 pub use edge_descriptor::*;
 pub use edge_finder::*;
+pub use edge_finder_spec::*;
+pub use edge_gate::*;
 pub use graph_error::*;
+pub use id_generator::*;
+pub use order_key::*;
+pub use predicate_registry::*;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EdgeDir {
     Emit,
     Recv,
@@ -29,4 +38,26 @@ impl EdgeDir {
 
 pub trait GraphTraits = Clone + PartialEq + Debug + Eq + Hash + Default + 'static;
 
+// Implemented by every edge type. Edge types without a payload or an inverse keep the defaults:
+// `impl EdgeTraits for MyEdge {}`
+pub trait EdgeTraits: GraphTraits {
+    // Carried by every edge of the type, e.g. a weight
+    type Payload: GraphTraits = ();
+
+    // The edge type stored on the other side of the edge pair, e.g. `ParentOf` for `ChildOf`.
+    // By default both sides share the type.
+    fn inverse(&self) -> Self {
+        self.clone()
+    }
+
+    // Symmetric edge types, e.g. `Sibling`, read the same from both sides,
+    // so edge finders match them regardless of direction
+    fn is_symmetric(&self) -> bool {
+        false
+    }
+}
+
+impl EdgeTraits for String {}
+impl EdgeTraits for &'static str {}
+
 pub type Uid = u128;
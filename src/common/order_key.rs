@@ -0,0 +1,92 @@
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+// A fractional position among sibling edges. There is always room for another key between two keys,
+// so an edge can be placed anywhere without touching the keys of its siblings.
+// Keys never end in the lowest digit, which keeps room below every key.
+#[derive(Clone, PartialEq, Debug, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct OrderKey(String);
+
+impl OrderKey {
+    // A key sorting after `before` and before `after`, where None is the start or end of the list.
+    // If the bounds are out of order, `after` is ignored.
+    pub fn between(before: Option<&OrderKey>, after: Option<&OrderKey>) -> Self {
+        let before = before.map_or(&b""[..], |key| key.0.as_bytes());
+        let after = after
+            .map(|key| key.0.as_bytes())
+            .filter(|after| *after > before);
+        Self(String::from_utf8(midpoint(before, after)).unwrap())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn digit(character: u8) -> usize {
+    DIGITS
+        .iter()
+        .position(|digit| *digit == character)
+        .expect("Order keys should only contain digits and lowercase letters")
+}
+
+// Expects before < after, with a missing after standing for the end of the list
+fn midpoint(before: &[u8], after: Option<&[u8]>) -> Vec<u8> {
+    if let Some(after) = after {
+        // Keep any shared prefix, with a missing digit of before counting as the lowest digit
+        let prefix_len = after
+            .iter()
+            .zip(before.iter().chain(std::iter::repeat(&DIGITS[0])))
+            .take_while(|(after_digit, before_digit)| after_digit == before_digit)
+            .count();
+        if prefix_len > 0 {
+            let mut key = after[..prefix_len].to_vec();
+            key.extend(midpoint(
+                before.get(prefix_len..).unwrap_or_default(),
+                Some(&after[prefix_len..]),
+            ));
+            return key;
+        }
+    }
+
+    let before_digit = before.first().map_or(0, |character| digit(*character));
+    let after_digit = after.map_or(DIGITS.len(), |after| digit(after[0]));
+    if after_digit - before_digit > 1 {
+        vec![DIGITS[(before_digit + after_digit) / 2]]
+    } else if let Some(after) = after.filter(|after| after.len() > 1) {
+        // The first digit of after alone sorts before after and after before
+        vec![after[0]]
+    } else {
+        let mut key = vec![DIGITS[before_digit]];
+        key.extend(midpoint(before.get(1..).unwrap_or_default(), None));
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrderKey;
+
+    #[test]
+    fn should_always_find_a_key_between_two_keys() {
+        let mut keys = vec![OrderKey::between(None, None)];
+        // Repeatedly insert at the front, the back and between neighbours
+        for step in 0..300 {
+            let index = match step % 3 {
+                0 => 0,
+                1 => keys.len(),
+                _ => keys.len() / 2,
+            };
+            let key = OrderKey::between(
+                index.checked_sub(1).map(|index| &keys[index]),
+                keys.get(index),
+            );
+            keys.insert(index, key);
+        }
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(keys.iter().all(|key| !key.as_str().ends_with('0')));
+
+        // Out of order bounds fall back to placing the key after `before`
+        let key = OrderKey::between(Some(&keys[5]), Some(&keys[2]));
+        assert!(key > keys[5]);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{fmt::Debug, rc::Rc};
+
+use im::HashMap;
+
+use crate::prelude::{reactive_node::read_reactive_node::ReadReactiveNode, *};
+
+pub type GatePredicate<T, E, A, L = String> = Rc<dyn Fn(&ReadReactiveNode<T, E, A, L>) -> bool>;
+
+// Closures can't be saved or sent anywhere, so serializable queries refer to them by key.
+// The registry resolves those keys back into closures when a query is bound to a graph.
+#[derive(Clone)]
+pub struct PredicateRegistry<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String>
+{
+    gates: HashMap<String, GatePredicate<T, E, A, L>>,
+    data_predicates: HashMap<String, DataPredicate<T>>,
+    payload_predicates: HashMap<String, DataPredicate<E::Payload>>,
+}
+
+// Registries are equal when they hold the same closures under the same keys
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> PartialEq
+    for PredicateRegistry<T, E, A, L>
+{
+    fn eq(&self, other: &Self) -> bool {
+        fn same_closures<V, P: ?Sized>(
+            left: &HashMap<String, V>,
+            right: &HashMap<String, V>,
+            closure: impl Fn(&V) -> &Rc<P>,
+        ) -> bool {
+            left.len() == right.len()
+                && left.iter().all(|(key, value)| {
+                    right
+                        .get(key)
+                        .is_some_and(|other| Rc::ptr_eq(closure(value), closure(other)))
+                })
+        }
+        same_closures(&self.gates, &other.gates, |gate| gate)
+            && same_closures(&self.data_predicates, &other.data_predicates, |data| {
+                &data.predicate
+            })
+            && same_closures(
+                &self.payload_predicates,
+                &other.payload_predicates,
+                |payload| &payload.predicate,
+            )
+    }
+}
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Eq
+    for PredicateRegistry<T, E, A, L>
+{
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Debug
+    for PredicateRegistry<T, E, A, L>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PredicateRegistry")
+            .field("gates", &self.gates.keys().collect::<Vec<_>>())
+            .field(
+                "data_predicates",
+                &self.data_predicates.keys().collect::<Vec<_>>(),
+            )
+            .field(
+                "payload_predicates",
+                &self.payload_predicates.keys().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Default
+    for PredicateRegistry<T, E, A, L>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> PredicateRegistry<T, E, A, L> {
+    pub fn new() -> Self {
+        Self {
+            gates: HashMap::new(),
+            data_predicates: HashMap::new(),
+            payload_predicates: HashMap::new(),
+        }
+    }
+
+    // Gates are run against the target node of an edge, see EdgeFinder::gate_closure
+    pub fn register_gate(
+        &self,
+        key: impl Into<String>,
+        gate: impl Fn(&ReadReactiveNode<T, E, A, L>) -> bool + 'static,
+    ) -> Self {
+        let mut new_gates = self.gates.clone();
+        new_gates.insert(key.into(), Rc::new(gate));
+        Self {
+            gates: new_gates,
+            ..self.clone()
+        }
+    }
+
+    pub fn register_data_predicate(
+        &self,
+        key: impl Into<String>,
+        predicate: impl Fn(&T) -> bool + 'static,
+    ) -> Self {
+        let key = key.into();
+        let mut new_data_predicates = self.data_predicates.clone();
+        new_data_predicates.insert(key.clone(), DataPredicate::registered(key, predicate));
+        Self {
+            data_predicates: new_data_predicates,
+            ..self.clone()
+        }
+    }
+
+    pub fn register_payload_predicate(
+        &self,
+        key: impl Into<String>,
+        predicate: impl Fn(&E::Payload) -> bool + 'static,
+    ) -> Self {
+        let key = key.into();
+        let mut new_payload_predicates = self.payload_predicates.clone();
+        new_payload_predicates.insert(key.clone(), DataPredicate::registered(key, predicate));
+        Self {
+            payload_predicates: new_payload_predicates,
+            ..self.clone()
+        }
+    }
+
+    pub fn get_gate(&self, key: &str) -> Result<GatePredicate<T, E, A, L>, GraphError> {
+        self.gates
+            .get(key)
+            .cloned()
+            .ok_or_else(|| GraphError::Traversal(TraversalError::UnknownPredicate(key.to_string())))
+    }
+
+    // A gate which keeps its key, so finders using it can be compared and serialized
+    pub fn edge_gate(
+        &self,
+        key: &str,
+        get_node_closure: GetNodeClosure<T, E, A, L>,
+    ) -> Result<EdgeGate<T, E, A, L>, GraphError> {
+        Ok(EdgeGate {
+            key: Some(key.to_string()),
+            predicate: self.get_gate(key)?,
+            get_node_closure,
+        })
+    }
+
+    pub fn get_data_predicate(&self, key: &str) -> Result<DataPredicate<T>, GraphError> {
+        self.data_predicates
+            .get(key)
+            .cloned()
+            .ok_or_else(|| GraphError::Traversal(TraversalError::UnknownPredicate(key.to_string())))
+    }
+
+    // A NodeFilter::Data which keeps its key, so traversals using it can be saved
+    pub fn data_filter(&self, key: &str) -> Result<NodeFilter<T, E, A, L>, GraphError> {
+        Ok(NodeFilter::Data(self.get_data_predicate(key)?))
+    }
+
+    pub fn get_payload_predicate(
+        &self,
+        key: &str,
+    ) -> Result<DataPredicate<E::Payload>, GraphError> {
+        self.payload_predicates
+            .get(key)
+            .cloned()
+            .ok_or_else(|| GraphError::Traversal(TraversalError::UnknownPredicate(key.to_string())))
+    }
+}
@@ -0,0 +1,425 @@
+use std::{collections::VecDeque, fmt::Debug, rc::Rc};
+
+use im::{HashMap, HashSet};
+use leptos::*;
+
+use crate::prelude::*;
+
+use super::view_graph::ViewGraph;
+
+// Node data belonging to a kind (e.g. Folder or File), which a GraphSchema can constrain edges by.
+// Only needed for graphs with a schema. Data without kinds can take the default with `impl NodeKind for MyData {}`
+pub trait NodeKind: GraphTraits {
+    type Kind: GraphTraits = ();
+    fn kind(&self) -> Self::Kind {
+        Self::Kind::default()
+    }
+}
+
+impl NodeKind for String {}
+impl NodeKind for &'static str {}
+
+// How many edges of a type a node may have in one direction. A max of None is unbounded.
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash, Default)]
+pub struct Cardinality {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Cardinality {
+    pub fn any() -> Self {
+        Self::default()
+    }
+    pub fn at_least(min: usize) -> Self {
+        Self { min, max: None }
+    }
+    pub fn at_most(max: usize) -> Self {
+        Self {
+            min: 0,
+            max: Some(max),
+        }
+    }
+    pub fn exactly(count: usize) -> Self {
+        Self {
+            min: count,
+            max: Some(count),
+        }
+    }
+    pub fn between(min: usize, max: usize) -> Self {
+        Self {
+            min,
+            max: Some(max),
+        }
+    }
+    pub fn allows(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+// The rules for one edge type, seen from the side which emits it
+#[derive(Clone, PartialEq, Debug, Eq, Default)]
+pub struct EdgeTypeSchema<K: GraphTraits> {
+    // None allows any kind at that end
+    pub emitter_kinds: Option<HashSet<K>>,
+    pub receiver_kinds: Option<HashSet<K>>,
+    // Only checked on nodes whose kind is allowed at that end
+    pub emit_cardinality: Cardinality,
+    pub recv_cardinality: Cardinality,
+    // Following edges of the type in the Emit direction never leads back to the start
+    pub acyclic: bool,
+    // What happens to the node on the other end when the node at each end is deleted
+    pub on_emitter_delete: OnDelete,
+    pub on_receiver_delete: OnDelete,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash, Default)]
+pub enum OnDelete {
+    // Only the edge is removed
+    #[default]
+    Detach,
+    // The node on the other end is deleted too
+    Cascade,
+    // The deletion is refused while the node on the other end remains
+    Restrict,
+}
+
+// Rules every blueprint has to leave the graph satisfying. Edge types without rules are unconstrained.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct GraphSchema<T: NodeKind, E: EdgeTraits> {
+    pub edge_types: HashMap<E, EdgeTypeSchema<T::Kind>>,
+}
+
+impl<T: NodeKind, E: EdgeTraits> Default for GraphSchema<T, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: NodeKind, E: EdgeTraits> GraphSchema<T, E> {
+    pub fn new() -> Self {
+        Self {
+            edge_types: HashMap::new(),
+        }
+    }
+    pub fn emitter_kinds(&self, edge_type: E, kinds: impl IntoIterator<Item = T::Kind>) -> Self {
+        let kinds = kinds.into_iter().collect();
+        self.update_edge_type(edge_type, |rules| rules.emitter_kinds = Some(kinds))
+    }
+    pub fn receiver_kinds(&self, edge_type: E, kinds: impl IntoIterator<Item = T::Kind>) -> Self {
+        let kinds = kinds.into_iter().collect();
+        self.update_edge_type(edge_type, |rules| rules.receiver_kinds = Some(kinds))
+    }
+    pub fn emit_cardinality(&self, edge_type: E, cardinality: Cardinality) -> Self {
+        self.update_edge_type(edge_type, |rules| rules.emit_cardinality = cardinality)
+    }
+    pub fn recv_cardinality(&self, edge_type: E, cardinality: Cardinality) -> Self {
+        self.update_edge_type(edge_type, |rules| rules.recv_cardinality = cardinality)
+    }
+    pub fn acyclic(&self, edge_type: E) -> Self {
+        self.update_edge_type(edge_type, |rules| rules.acyclic = true)
+    }
+    // The direction is the deleted node's end of the edge, so Emit applies when the emitter is deleted
+    pub fn on_delete(&self, edge_type: E, dir: EdgeDir, policy: OnDelete) -> Self {
+        self.update_edge_type(edge_type, |rules| match dir {
+            EdgeDir::Emit => rules.on_emitter_delete = policy,
+            EdgeDir::Recv => rules.on_receiver_delete = policy,
+        })
+    }
+    fn update_edge_type(
+        &self,
+        edge_type: E,
+        update: impl FnOnce(&mut EdgeTypeSchema<T::Kind>),
+    ) -> Self {
+        let mut edge_types = self.edge_types.clone();
+        update(edge_types.entry(edge_type).or_default());
+        Self { edge_types }
+    }
+
+    // Every node the blueprint touches is checked, which covers both ends of any added or removed edge
+    pub fn validate<A: GraphTraits, L: GraphTraits>(
+        &self,
+        blueprint: &FinalizedBlueprint<T, E, L>,
+        graph: &ViewGraph<T, E, A, L>,
+    ) -> Result<(), Vec<SchemaError>> {
+        let resulting_graph = ResultingGraph { blueprint, graph };
+        let mut errors = Vec::new();
+        for id in resulting_graph.touched_nodes() {
+            let Some(data) = resulting_graph.data(&id) else {
+                continue;
+            };
+            let kind = data.kind();
+            let edges = resulting_graph.edges(&id);
+
+            for edge in edges.iter() {
+                let (edge_type, allowed_kinds) = match edge.dir {
+                    EdgeDir::Emit => (
+                        edge.edge_type.clone(),
+                        self.edge_types
+                            .get(&edge.edge_type)
+                            .map(|rules| &rules.emitter_kinds),
+                    ),
+                    EdgeDir::Recv => {
+                        let edge_type = edge.edge_type.inverse();
+                        let allowed_kinds = self
+                            .edge_types
+                            .get(&edge_type)
+                            .map(|rules| &rules.receiver_kinds);
+                        (edge_type, allowed_kinds)
+                    }
+                };
+                if let Some(Some(allowed_kinds)) = allowed_kinds {
+                    if !allowed_kinds.contains(&kind) {
+                        errors.push(SchemaError::DisallowedKind {
+                            node: id,
+                            kind: SchemaValue::new(kind.clone()),
+                            edge_type: SchemaValue::new(edge_type),
+                            dir: edge.dir.clone(),
+                        });
+                    }
+                }
+            }
+
+            for (edge_type, rules) in self.edge_types.iter() {
+                let inverse_type = edge_type.inverse();
+                let checks = [
+                    (
+                        EdgeDir::Emit,
+                        edge_type,
+                        &rules.emitter_kinds,
+                        rules.emit_cardinality,
+                    ),
+                    (
+                        EdgeDir::Recv,
+                        &inverse_type,
+                        &rules.receiver_kinds,
+                        rules.recv_cardinality,
+                    ),
+                ];
+                for (dir, stored_type, allowed_kinds, cardinality) in checks {
+                    if !allowed_kinds
+                        .as_ref()
+                        .is_none_or(|allowed_kinds| allowed_kinds.contains(&kind))
+                    {
+                        continue;
+                    }
+                    let count = edges
+                        .iter()
+                        .filter(|edge| edge.dir == dir && &edge.edge_type == stored_type)
+                        .count();
+                    if !cardinality.allows(count) {
+                        errors.push(SchemaError::Cardinality {
+                            node: id,
+                            edge_type: SchemaValue::new(edge_type.clone()),
+                            dir,
+                            count,
+                        });
+                    }
+                }
+            }
+        }
+        errors.extend(self.find_cycles(&resulting_graph));
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(())
+    }
+
+    // Any new cycle has to run through an added edge, so only paths back from the targets of added edges are searched
+    fn find_cycles<A: GraphTraits, L: GraphTraits>(
+        &self,
+        resulting_graph: &ResultingGraph<T, E, A, L>,
+    ) -> Vec<SchemaError> {
+        let mut found_cycles = HashSet::<(E, Vec<Uid>)>::new();
+        let mut errors = Vec::new();
+        for edge in resulting_graph.added_edges() {
+            let is_acyclic = self
+                .edge_types
+                .get(&edge.edge_type)
+                .is_some_and(|rules| rules.acyclic);
+            if edge.dir != EdgeDir::Emit || !is_acyclic {
+                continue;
+            }
+            let Some(path_back) =
+                resulting_graph.find_path(edge.target, edge.host, &edge.edge_type)
+            else {
+                continue;
+            };
+            let mut path = vec![edge.host];
+            path.extend(path_back);
+            // The same cycle can be closed by several added edges, so it's compared by its sorted nodes
+            let mut cycle_nodes = path.clone();
+            cycle_nodes.sort();
+            if found_cycles
+                .insert((edge.edge_type.clone(), cycle_nodes))
+                .is_none()
+            {
+                errors.push(SchemaError::Cycle {
+                    edge_type: SchemaValue::new(edge.edge_type.clone()),
+                    path,
+                });
+            }
+        }
+        errors
+    }
+}
+
+// Lets the graph hold a schema as a trait object, without naming the node kind type
+pub trait SchemaRules<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    fn validate(
+        &self,
+        blueprint: &FinalizedBlueprint<T, E, L>,
+        graph: &ViewGraph<T, E, A, L>,
+    ) -> Result<(), Vec<SchemaError>>;
+    // The edge is seen from the deleted node
+    fn on_delete(&self, edge: &EdgeDescriptor<E>) -> OnDelete;
+}
+
+impl<T: NodeKind, E: EdgeTraits, A: GraphTraits, L: GraphTraits> SchemaRules<T, E, A, L>
+    for GraphSchema<T, E>
+{
+    fn validate(
+        &self,
+        blueprint: &FinalizedBlueprint<T, E, L>,
+        graph: &ViewGraph<T, E, A, L>,
+    ) -> Result<(), Vec<SchemaError>> {
+        GraphSchema::validate(self, blueprint, graph)
+    }
+    fn on_delete(&self, edge: &EdgeDescriptor<E>) -> OnDelete {
+        match edge.dir {
+            EdgeDir::Emit => self
+                .edge_types
+                .get(&edge.edge_type)
+                .map(|rules| rules.on_emitter_delete),
+            EdgeDir::Recv => self
+                .edge_types
+                .get(&edge.edge_type.inverse())
+                .map(|rules| rules.on_receiver_delete),
+        }
+        .unwrap_or_default()
+    }
+}
+
+#[derive(Clone)]
+pub struct SchemaHandle<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String>(
+    pub Rc<dyn SchemaRules<T, E, A, L>>,
+);
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> PartialEq
+    for SchemaHandle<T, E, A, L>
+{
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Eq
+    for SchemaHandle<T, E, A, L>
+{
+}
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Debug
+    for SchemaHandle<T, E, A, L>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaHandle").finish()
+    }
+}
+
+// The graph as it will be once the blueprint is applied
+struct ResultingGraph<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> {
+    blueprint: &'a FinalizedBlueprint<T, E, L>,
+    graph: &'a ViewGraph<T, E, A, L>,
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> ResultingGraph<'_, T, E, A, L> {
+    fn touched_nodes(&self) -> HashSet<Uid> {
+        self.blueprint
+            .new_nodes
+            .keys()
+            .chain(self.blueprint.update_nodes.keys())
+            .filter(|id| !self.blueprint.delete_nodes.contains_key(id))
+            .copied()
+            .collect()
+    }
+
+    fn added_edges(&self) -> impl Iterator<Item = &EdgeDescriptor<E>> {
+        self.blueprint
+            .new_nodes
+            .values()
+            .flat_map(|new_node| new_node.add_edges.iter())
+            .chain(
+                self.blueprint
+                    .update_nodes
+                    .values()
+                    .flat_map(|update_node| update_node.add_edges.iter().flatten()),
+            )
+    }
+
+    // The shortest path from start to end along Emit edges of the type, including both ends
+    fn find_path(&self, start: Uid, end: Uid, edge_type: &E) -> Option<Vec<Uid>> {
+        let mut previous = HashMap::<Uid, Uid>::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(id) = queue.pop_front() {
+            if id == end {
+                let mut path = vec![end];
+                while let Some(previous_id) = previous.get(path.last().unwrap()) {
+                    path.push(*previous_id);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for edge in self.edges(&id).iter() {
+                if edge.dir == EdgeDir::Emit
+                    && &edge.edge_type == edge_type
+                    && edge.target != start
+                    && !previous.contains_key(&edge.target)
+                {
+                    previous.insert(edge.target, id);
+                    queue.push_back(edge.target);
+                }
+            }
+        }
+        None
+    }
+
+    fn data(&self, id: &Uid) -> Option<T> {
+        if self.blueprint.delete_nodes.contains_key(id) {
+            return None;
+        }
+        if let Some(new_node) = self.blueprint.new_nodes.get(id) {
+            return Some(new_node.data.clone());
+        }
+        if let Some(replacement_data) = self
+            .blueprint
+            .update_nodes
+            .get(id)
+            .and_then(|update_node| update_node.replacement_data.as_ref())
+        {
+            return Some(replacement_data.new_data.clone());
+        }
+        self.graph
+            .nodes
+            .get(id)
+            .map(|node| node.0.data.get_untracked())
+    }
+
+    fn edges(&self, id: &Uid) -> HashSet<EdgeDescriptor<E>> {
+        if self.blueprint.delete_nodes.contains_key(id) {
+            return HashSet::new();
+        }
+        if let Some(new_node) = self.blueprint.new_nodes.get(id) {
+            return new_node.add_edges.clone();
+        }
+        let mut edges = self
+            .graph
+            .nodes
+            .get(id)
+            .map(|node| node.0.convert_all_edges_to_hashset())
+            .unwrap_or_default();
+        if let Some(update_node) = self.blueprint.update_nodes.get(id) {
+            for removed_edge in update_node.remove_edges.iter().flatten() {
+                edges.remove(removed_edge);
+            }
+            edges.extend(update_node.add_edges.iter().flatten().cloned());
+        }
+        edges
+    }
+}
@@ -0,0 +1,207 @@
+use im::{HashMap, HashSet, Vector};
+use leptos::*;
+
+use crate::prelude::{GraphTraits, Uid};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LabelIndexState<L: GraphTraits> {
+    nodes_by_label: HashMap<L, HashSet<Uid>>,
+    labels_by_node: HashMap<Uid, HashSet<L>>,
+}
+
+impl<L: GraphTraits> Default for LabelIndexState<L> {
+    fn default() -> Self {
+        Self {
+            nodes_by_label: HashMap::new(),
+            labels_by_node: HashMap::new(),
+        }
+    }
+}
+
+impl<L: GraphTraits> LabelIndexState<L> {
+    fn set_labels(&mut self, id: Uid, labels: HashSet<L>) {
+        self.clear_labels(id);
+        for label in labels.iter() {
+            self.nodes_by_label
+                .entry(label.clone())
+                .or_default()
+                .insert(id);
+        }
+        self.labels_by_node.insert(id, labels);
+    }
+
+    fn clear_labels(&mut self, id: Uid) {
+        for label in self.labels_by_node.remove(&id).unwrap_or_default() {
+            if let Some(nodes) = self.nodes_by_label.get_mut(&label) {
+                nodes.remove(&id);
+                if nodes.is_empty() {
+                    self.nodes_by_label.remove(&label);
+                }
+            }
+        }
+    }
+
+    fn nodes_with_label(&self, label: &L) -> HashSet<Uid> {
+        self.nodes_by_label.get(label).cloned().unwrap_or_default()
+    }
+
+    fn query(&self, query: &LabelQuery<L>) -> Vector<Uid> {
+        // Start from the smallest set of nodes the query could match
+        let candidates = if let Some(label) = query.all_of.iter().next() {
+            self.nodes_with_label(label)
+        } else if !query.any_of.is_empty() {
+            HashSet::unions(
+                query
+                    .any_of
+                    .iter()
+                    .map(|label| self.nodes_with_label(label)),
+            )
+        } else {
+            self.labels_by_node.keys().copied().collect()
+        };
+        let mut ids = candidates
+            .into_iter()
+            .filter(|id| {
+                self.labels_by_node
+                    .get(id)
+                    .is_some_and(|labels| query.matches(labels))
+            })
+            .collect::<Vector<_>>();
+        ids.sort();
+        ids
+    }
+}
+
+// Which nodes have which labels, kept in a signal so lookups can be tracked.
+// Copies share the same index.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LabelIndex<L: GraphTraits = String> {
+    state: RwSignal<LabelIndexState<L>>,
+}
+
+// Derived Clone and Copy would require L: Copy
+impl<L: GraphTraits> Clone for LabelIndex<L> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<L: GraphTraits> Copy for LabelIndex<L> {}
+
+impl<L: GraphTraits> Default for LabelIndex<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L: GraphTraits> LabelIndex<L> {
+    pub fn new() -> Self {
+        Self {
+            state: create_rw_signal(LabelIndexState::default()),
+        }
+    }
+
+    // Replaces every label of the node
+    pub(crate) fn set_node_labels(&self, id: Uid, labels: impl IntoIterator<Item = L>) {
+        let labels = labels.into_iter().collect();
+        self.state.update(|state| state.set_labels(id, labels));
+    }
+
+    pub(crate) fn remove_node(&self, id: Uid) {
+        self.state.update(|state| state.clear_labels(id));
+    }
+
+    // Sorted by id
+    pub fn ids_with_label_untracked(&self, label: &L) -> Vector<Uid> {
+        let mut ids = self
+            .state
+            .with_untracked(|state| state.nodes_with_label(label))
+            .into_iter()
+            .collect::<Vector<_>>();
+        ids.sort();
+        ids
+    }
+
+    // Every label in use, with the ids of its nodes sorted by id
+    pub fn label_map_untracked(&self) -> HashMap<L, Vector<Uid>> {
+        self.state.with_untracked(|state| {
+            state
+                .nodes_by_label
+                .iter()
+                .map(|(label, ids)| {
+                    let mut ids = ids.iter().copied().collect::<Vector<_>>();
+                    ids.sort();
+                    (label.clone(), ids)
+                })
+                .collect()
+        })
+    }
+
+    pub fn query_untracked(&self, query: &LabelQuery<L>) -> Vector<Uid> {
+        self.state.with_untracked(|state| state.query(query))
+    }
+
+    // Reruns whenever a node is added, deleted or relabelled, but only notifies when the ids change
+    pub fn query(&self, query: LabelQuery<L>) -> Memo<Vector<Uid>> {
+        let state = self.state;
+        create_memo(move |_| state.with(|state| state.query(&query)))
+    }
+
+    pub fn ids_with_label(&self, label: impl Into<L>) -> Memo<Vector<Uid>> {
+        self.query(LabelQuery::new().all_of([label]))
+    }
+}
+
+// Matches nodes with every label in all_of, at least one label in any_of and none of the labels in none_of.
+// Empty sets don't restrict the match, so the default query matches every node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelQuery<L: GraphTraits = String> {
+    pub all_of: HashSet<L>,
+    pub any_of: HashSet<L>,
+    pub none_of: HashSet<L>,
+}
+
+impl<L: GraphTraits> Default for LabelQuery<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L: GraphTraits> LabelQuery<L> {
+    pub fn new() -> Self {
+        Self {
+            all_of: HashSet::new(),
+            any_of: HashSet::new(),
+            none_of: HashSet::new(),
+        }
+    }
+    pub fn all_of(&self, labels: impl IntoIterator<Item = impl Into<L>>) -> Self {
+        let mut all_of = self.all_of.clone();
+        all_of.extend(labels.into_iter().map(Into::into));
+        Self {
+            all_of,
+            ..self.clone()
+        }
+    }
+    pub fn any_of(&self, labels: impl IntoIterator<Item = impl Into<L>>) -> Self {
+        let mut any_of = self.any_of.clone();
+        any_of.extend(labels.into_iter().map(Into::into));
+        Self {
+            any_of,
+            ..self.clone()
+        }
+    }
+    pub fn none_of(&self, labels: impl IntoIterator<Item = impl Into<L>>) -> Self {
+        let mut none_of = self.none_of.clone();
+        none_of.extend(labels.into_iter().map(Into::into));
+        Self {
+            none_of,
+            ..self.clone()
+        }
+    }
+
+    pub fn matches(&self, labels: &HashSet<L>) -> bool {
+        self.all_of.iter().all(|label| labels.contains(label))
+            && (self.any_of.is_empty() || self.any_of.iter().any(|label| labels.contains(label)))
+            && !self.none_of.iter().any(|label| labels.contains(label))
+    }
+}
@@ -1,2 +1,4 @@
+pub mod graph_schema;
+pub mod label_index;
 pub mod reactive_node;
 pub mod view_graph;
@@ -9,27 +9,34 @@ use leptos::*;
 use crate::blueprint::new_node::NewNode;
 use crate::prelude::*;
 
+use super::edge_index::EdgeIndex;
 use super::last_action::{ActionData, LastAction};
 use super::read_reactive_node::ReadReactiveNode;
+use super::utils::insert_in_order;
 use super::write_reactive_node::WriteReactiveNode;
 
+type ReactiveNodePair<T, E, A, L> = (ReadReactiveNode<T, E, A, L>, WriteReactiveNode<T, E, A, L>);
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct BuildReactiveNode<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
+pub struct BuildReactiveNode<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String>
+{
     id: Option<Uid>,
     data: Option<T>,
-    labels: Option<Vector<String>>,
+    labels: Option<Vector<L>>,
     incoming_edges: Option<HashMap<E, Vector<EdgeDescriptor<E>>>>,
     outgoing_edges: Option<HashMap<E, Vector<EdgeDescriptor<E>>>>,
-    last_action: Option<LastAction<T, E, A>>,
+    last_action: Option<LastAction<T, E, A, L>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> Default for BuildReactiveNode<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Default
+    for BuildReactiveNode<T, E, A, L>
+{
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildReactiveNode<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> BuildReactiveNode<T, E, A, L> {
     // Creates a new ConstructNode with all fields set to None
     pub fn new() -> Self {
         Self {
@@ -61,7 +68,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildReactiveNode<T, E, A>
     // Sets the labels field and returns self for chaining
     pub fn add_labels<I>(&self, labels: I) -> Self
     where
-        I: IntoIterator<Item = String>,
+        I: IntoIterator<Item = L>,
     {
         let mut updated_labels = self.labels.clone().unwrap_or_default();
         updated_labels.extend(labels);
@@ -97,7 +104,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildReactiveNode<T, E, A>
         }
     }
 
-    pub fn add_last_action(&self, last_action: LastAction<T, E, A>) -> Self {
+    pub fn add_last_action(&self, last_action: LastAction<T, E, A, L>) -> Self {
         Self {
             last_action: Some(last_action),
             ..self.clone()
@@ -115,7 +122,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildReactiveNode<T, E, A>
             };
             let edge_list = map_to_edit.entry(edge.edge_type.clone()).or_default();
             if !edge_list.contains(edge) {
-                edge_list.push_back(edge.clone());
+                insert_in_order(edge_list, edge.clone());
             }
         }
 
@@ -126,23 +133,27 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildReactiveNode<T, E, A>
         }
     }
 
-    pub fn ingest_from_blueprint(&self, bp: NewNode<T, E>, action_data: Rc<ActionData<A>>) -> Self {
+    pub fn ingest_from_blueprint(
+        &self,
+        bp: NewNode<T, E, L>,
+        action_data: Rc<ActionData<A>>,
+    ) -> Self {
         self.data(bp.data)
             .id(bp.id)
             .map_edges_from_bp(&bp.add_edges)
             .add_labels(bp.add_labels)
-            .add_last_action(LastAction::<T, E, A> {
+            .add_last_action(LastAction::<T, E, A, L> {
                 action_data,
                 update_info: None,
             })
     }
 
     // Constructs the final Node object
-    pub fn build(&self) -> (ReadReactiveNode<T, E, A>, WriteReactiveNode<T, E, A>) {
+    pub fn build(&self) -> ReactiveNodePair<T, E, A, L> {
         let (read_data, write_data) =
             create_signal::<T>(self.data.clone().expect("Data must be set"));
         let (read_labels, write_labels) =
-            create_signal::<Vector<String>>(self.labels.clone().unwrap_or_default());
+            create_signal::<Vector<L>>(self.labels.clone().unwrap_or_default());
         let (read_incoming_edges, write_incoming_edges) =
             create_signal::<HashMap<E, Vector<EdgeDescriptor<E>>>>(
                 self.incoming_edges.clone().unwrap_or_default(),
@@ -151,8 +162,15 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildReactiveNode<T, E, A>
             create_signal::<HashMap<E, Vector<EdgeDescriptor<E>>>>(
                 self.outgoing_edges.clone().unwrap_or_default(),
             );
-
-        let (read_last_action, write_last_action) = create_signal::<LastAction<T, E, A>>(
+        let (read_edge_index, write_edge_index) = create_signal(EdgeIndex::from_edges(
+            self.incoming_edges
+                .iter()
+                .chain(self.outgoing_edges.iter())
+                .flat_map(|edges| edges.values())
+                .flatten(),
+        ));
+
+        let (read_last_action, write_last_action) = create_signal::<LastAction<T, E, A, L>>(
             self.last_action
                 .clone()
                 .expect("Last action must be set")
@@ -164,6 +182,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildReactiveNode<T, E, A>
             labels: read_labels,
             incoming_edges: read_incoming_edges,
             outgoing_edges: read_outgoing_edges,
+            edge_index: read_edge_index,
             last_action: read_last_action,
         };
         let write_node = WriteReactiveNode {
@@ -172,6 +191,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> BuildReactiveNode<T, E, A>
             labels: write_labels,
             incoming_edges: write_incoming_edges,
             outgoing_edges: write_outgoing_edges,
+            edge_index: write_edge_index,
             last_action: write_last_action,
         };
 
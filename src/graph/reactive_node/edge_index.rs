@@ -0,0 +1,111 @@
+use im::{HashMap, HashSet};
+
+use crate::prelude::*;
+
+// Lookups for a node's edges which would otherwise need a scan over every edge of the node
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EdgeIndex<E: EdgeTraits> {
+    // Both sides of a self-referencing edge pair share an id, so there can be two edges per id
+    by_id: HashMap<Uid, HashSet<EdgeDescriptor<E>>>,
+    // Incoming and outgoing edges, by edge type and then by target
+    by_target: HashMap<E, HashMap<Uid, HashSet<EdgeDescriptor<E>>>>,
+    // Only the edges which render one of their ends
+    by_render_info: HashMap<EdgeDir, HashSet<EdgeDescriptor<E>>>,
+}
+
+impl<E: EdgeTraits> EdgeIndex<E> {
+    pub fn from_edges<'a>(edges: impl IntoIterator<Item = &'a EdgeDescriptor<E>>) -> Self {
+        let mut index = Self::default();
+        for edge in edges {
+            index.insert(edge);
+        }
+        index
+    }
+
+    pub fn insert(&mut self, edge: &EdgeDescriptor<E>) {
+        self.by_id.entry(edge.id).or_default().insert(edge.clone());
+        self.by_target
+            .entry(edge.edge_type.clone())
+            .or_default()
+            .entry(edge.target)
+            .or_default()
+            .insert(edge.clone());
+        if let Some(render_info) = &edge.render_info {
+            self.by_render_info
+                .entry(render_info.clone())
+                .or_default()
+                .insert(edge.clone());
+        }
+    }
+
+    pub fn remove(&mut self, edge: &EdgeDescriptor<E>) {
+        if let Some(edges) = self.by_id.get_mut(&edge.id) {
+            edges.remove(edge);
+            if edges.is_empty() {
+                self.by_id.remove(&edge.id);
+            }
+        }
+        if let Some(by_target) = self.by_target.get_mut(&edge.edge_type) {
+            if let Some(edges) = by_target.get_mut(&edge.target) {
+                edges.remove(edge);
+                if edges.is_empty() {
+                    by_target.remove(&edge.target);
+                }
+            }
+            if by_target.is_empty() {
+                self.by_target.remove(&edge.edge_type);
+            }
+        }
+        if let Some(render_info) = &edge.render_info {
+            if let Some(edges) = self.by_render_info.get_mut(render_info) {
+                edges.remove(edge);
+                if edges.is_empty() {
+                    self.by_render_info.remove(render_info);
+                }
+            }
+        }
+    }
+
+    // Every edge which could match the finder, or None if the finder sets no edge id, target or render direction
+    // The candidates still have to be checked against the finder
+    pub fn candidates<T: GraphTraits, A: GraphTraits, L: GraphTraits>(
+        &self,
+        edge_finder: &EdgeFinder<T, E, A, L>,
+    ) -> Option<HashSet<EdgeDescriptor<E>>> {
+        if let Some(edge_ids) = &edge_finder.edge_id {
+            return Some(
+                edge_ids
+                    .iter()
+                    .filter_map(|edge_id| self.by_id.get(edge_id))
+                    .flat_map(|edges| edges.iter().cloned())
+                    .collect(),
+            );
+        }
+        if let Some(targets) = &edge_finder.target {
+            let by_target_for_types: Vec<&HashMap<Uid, HashSet<EdgeDescriptor<E>>>> =
+                match &edge_finder.edge_type {
+                    Some(edge_types) => edge_types
+                        .iter()
+                        .filter_map(|edge_type| self.by_target.get(edge_type))
+                        .collect(),
+                    None => self.by_target.values().collect(),
+                };
+            return Some(
+                by_target_for_types
+                    .into_iter()
+                    .flat_map(|by_target| targets.iter().filter_map(|target| by_target.get(target)))
+                    .flat_map(|edges| edges.iter().cloned())
+                    .collect(),
+            );
+        }
+        if let Some(Some(render_info)) = &edge_finder.render_info {
+            return Some(
+                self.by_render_info
+                    .get(render_info)
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
+        None
+    }
+}
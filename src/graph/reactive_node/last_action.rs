@@ -8,9 +8,9 @@ pub struct ActionData<A: GraphTraits> {
     pub secondary_action: Option<Rc<A>>,
 }
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct LastAction<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
+pub struct LastAction<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
     pub action_data: Rc<ActionData<A>>,
-    pub update_info: Option<FinalizedUpdateNode<T, E>>,
+    pub update_info: Option<FinalizedUpdateNode<T, E, L>>,
 }
 
 impl<A: GraphTraits> ActionData<A> {
@@ -1,4 +1,5 @@
 pub mod build_reactive_node;
+pub mod edge_index;
 pub mod last_action;
 pub mod read_reactive_node;
 mod utils;
@@ -6,20 +6,25 @@ use leptos::*;
 
 use crate::prelude::*;
 
-use super::{last_action::LastAction, utils::search_map_for_edge};
+use super::{edge_index::EdgeIndex, last_action::LastAction, utils::search_map_for_edge};
 use im::hashmap::HashMap;
 
 #[derive(Clone, PartialEq, Eq, Hash)]
-pub struct ReadReactiveNode<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
+pub struct ReadReactiveNode<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String>
+{
     pub id: Uid,
     pub data: ReadSignal<T>,
-    pub labels: ReadSignal<Vector<String>>,
+    pub labels: ReadSignal<Vector<L>>,
     pub incoming_edges: ReadSignal<HashMap<E, Vector<EdgeDescriptor<E>>>>,
     pub outgoing_edges: ReadSignal<HashMap<E, Vector<EdgeDescriptor<E>>>>,
-    pub last_action: ReadSignal<LastAction<T, E, A>>,
+    // Kept in step with incoming_edges and outgoing_edges
+    pub edge_index: ReadSignal<EdgeIndex<E>>,
+    pub last_action: ReadSignal<LastAction<T, E, A, L>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> Debug for ReadReactiveNode<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Debug
+    for ReadReactiveNode<T, E, A, L>
+{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ReadReactiveNode")
             .field("id", &self.id)
@@ -32,40 +37,39 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> Debug for ReadReactiveNode<
     }
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> ReadReactiveNode<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> ReadReactiveNode<T, E, A, L> {
     pub fn search_for_edge(
         &self,
-        edge_finder: &EdgeFinder<T, E, A>,
+        edge_finder: &EdgeFinder<T, E, A, L>,
     ) -> Option<HashSet<EdgeDescriptor<E>>> {
         if edge_finder.host.is_some() && !edge_finder.host.as_ref().unwrap().contains(&self.id) {
             return None;
         }
 
-        let mut found_edges = HashSet::new();
-        let search_incoming = edge_finder.dir.as_ref().is_none()
-            || edge_finder.dir.as_ref().unwrap() == &EdgeDir::Recv;
-        let search_outgoing = edge_finder.dir.as_ref().is_none()
-            || edge_finder.dir.as_ref().unwrap() == &EdgeDir::Emit;
-
-        if search_incoming {
-            found_edges.extend(search_map_for_edge(
-                edge_finder,
-                &self.incoming_edges.get_untracked(),
-            ));
-        }
-        if !found_edges.is_empty() && edge_finder.match_all.is_none()
-            || (edge_finder.match_all.is_some() && !edge_finder.match_all.unwrap())
+        if let Some(candidates) = self
+            .edge_index
+            .with_untracked(|edge_index| edge_index.candidates(edge_finder))
         {
-            return Some(found_edges);
+            return select_indexed_edges(edge_finder, candidates);
         }
 
-        if search_outgoing {
-            found_edges.extend(search_map_for_edge(
-                edge_finder,
-                &self.outgoing_edges.get_untracked(),
-            ));
+        // Both maps are searched whatever the finder's direction, since symmetric edge types match either way
+        let mut found_edges = search_map_for_edge(
+            edge_finder,
+            &self.incoming_edges.get_untracked(),
+            EdgeDir::Recv,
+        );
+        // Only falls through to the outgoing edges if nothing was found, or every match is wanted
+        if !found_edges.is_empty() && !edge_finder.match_all.unwrap_or(false) {
+            return Some(found_edges);
         }
 
+        found_edges.extend(search_map_for_edge(
+            edge_finder,
+            &self.outgoing_edges.get_untracked(),
+            EdgeDir::Emit,
+        ));
+
         if found_edges.is_empty() {
             return None;
         }
@@ -73,6 +77,23 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> ReadReactiveNode<T, E, A> {
         Some(found_edges)
     }
 
+    // Fails rather than matching nothing when the finder has node conditions but was never bound to a graph
+    pub fn try_search_for_edge(
+        &self,
+        edge_finder: &EdgeFinder<T, E, A, L>,
+    ) -> Result<Option<HashSet<EdgeDescriptor<E>>>, GraphError> {
+        edge_finder.check_bound()?;
+        Ok(self.search_for_edge(edge_finder))
+    }
+
+    // The payload of the first edge the finder matches
+    pub fn get_edge_payload(&self, edge_finder: &EdgeFinder<T, E, A, L>) -> Option<E::Payload> {
+        self.search_for_edge(edge_finder)?
+            .iter()
+            .next()
+            .and_then(|edge| edge.payload.clone())
+    }
+
     pub fn get_render_edge(&self) -> EdgeDescriptor<E> {
         self.search_for_edge(&EdgeFinder::new().render_info(Some(EdgeDir::Recv)))
             .expect("Should have render edge if node exists")
@@ -102,3 +123,381 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> ReadReactiveNode<T, E, A> {
             ))
     }
 }
+
+fn select_indexed_edges<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>(
+    edge_finder: &EdgeFinder<T, E, A, L>,
+    candidates: HashSet<EdgeDescriptor<E>>,
+) -> Option<HashSet<EdgeDescriptor<E>>> {
+    let mut found_edges = candidates
+        .into_iter()
+        .filter(|edge| edge_finder.matches(edge))
+        .collect::<HashSet<EdgeDescriptor<E>>>();
+    // Without match_all only one edge is returned, preferring incoming edges like the unindexed search
+    if !edge_finder.match_all.unwrap_or(false) {
+        found_edges = found_edges
+            .iter()
+            .find(|edge| edge.dir == EdgeDir::Recv)
+            .or_else(|| found_edges.iter().next())
+            .cloned()
+            .into_iter()
+            .collect();
+    }
+    if found_edges.is_empty() {
+        return None;
+    }
+    Some(found_edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use im::{hashset, HashMap, HashSet, Vector};
+    use leptos::SignalGetUntracked;
+
+    use crate::hooks::GetNodeClosure;
+    use crate::prelude::reactive_node::build_reactive_node::BuildReactiveNode;
+    use crate::prelude::reactive_node::last_action::{ActionData, LastAction};
+    use crate::prelude::*;
+
+    use super::ReadReactiveNode;
+
+    fn edge(
+        dir: EdgeDir,
+        edge_type: &str,
+        target: Uid,
+        render_info: Option<EdgeDir>,
+    ) -> EdgeDescriptor<String> {
+        EdgeDescriptor::new(target, 1, edge_type.to_string(), target, render_info, dir)
+    }
+
+    fn edge_map(
+        edges: &[EdgeDescriptor<String>],
+    ) -> HashMap<String, Vector<EdgeDescriptor<String>>> {
+        edges.iter().fold(HashMap::new(), |mut acc, edge| {
+            acc.entry(edge.edge_type.clone())
+                .or_default()
+                .push_back(edge.clone());
+            acc
+        })
+    }
+
+    fn build_node(
+        incoming_edges: &[EdgeDescriptor<String>],
+        outgoing_edges: &[EdgeDescriptor<String>],
+    ) -> ReadReactiveNode<String, String, String> {
+        build_labelled_node(1, &[], incoming_edges, outgoing_edges)
+    }
+
+    fn build_labelled_node(
+        id: Uid,
+        labels: &[&str],
+        incoming_edges: &[EdgeDescriptor<String>],
+        outgoing_edges: &[EdgeDescriptor<String>],
+    ) -> ReadReactiveNode<String, String, String> {
+        BuildReactiveNode::<String, String, String>::new()
+            .id(id)
+            .data(format!("node{}", id))
+            .add_labels(labels.iter().map(|label| label.to_string()))
+            .add_incoming_edges(edge_map(incoming_edges))
+            .add_outgoing_edges(edge_map(outgoing_edges))
+            .add_last_action(LastAction {
+                action_data: Rc::new(ActionData::new("manual create".to_string())),
+                update_info: None,
+            })
+            .build()
+            .0
+    }
+
+    // Written out separately from EdgeFinder::matches, so the search is checked against an independent definition
+    fn expected_match(
+        edge_finder: &EdgeFinder<String, String, String>,
+        edge: &EdgeDescriptor<String>,
+    ) -> bool {
+        edge_finder
+            .edge_type
+            .as_ref()
+            .is_none_or(|edge_types| edge_types.contains(&edge.edge_type))
+            && edge_finder.dir.as_ref().is_none_or(|dir| dir == &edge.dir)
+            && edge_finder
+                .host
+                .as_ref()
+                .is_none_or(|hosts| hosts.contains(&edge.host))
+            && edge_finder
+                .target
+                .as_ref()
+                .is_none_or(|targets| targets.contains(&edge.target))
+            && edge_finder
+                .render_info
+                .as_ref()
+                .is_none_or(|render_info| render_info == &edge.render_info)
+    }
+
+    #[test]
+    fn should_match_every_edge_finder_field_combination() {
+        let incoming_edges = [
+            edge(EdgeDir::Recv, "a", 2, Some(EdgeDir::Recv)),
+            edge(EdgeDir::Recv, "b", 3, None),
+        ];
+        let outgoing_edges = [
+            edge(EdgeDir::Emit, "a", 4, Some(EdgeDir::Emit)),
+            edge(EdgeDir::Emit, "b", 5, None),
+            edge(EdgeDir::Emit, "a", 3, None),
+        ];
+        let node = build_node(&incoming_edges, &outgoing_edges);
+        let all_edges = incoming_edges
+            .iter()
+            .chain(outgoing_edges.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let edge_types = [
+            None,
+            Some(hashset!["a".to_string()]),
+            Some(hashset!["a".to_string(), "b".to_string()]),
+            Some(hashset!["c".to_string()]),
+        ];
+        let dirs = [None, Some(EdgeDir::Recv), Some(EdgeDir::Emit)];
+        let hosts = [None, Some(hashset![1]), Some(hashset![9])];
+        let targets = [
+            None,
+            Some(hashset![3]),
+            Some(hashset![2, 4]),
+            Some(hashset![8]),
+        ];
+        let render_infos = [
+            None,
+            Some(None),
+            Some(Some(EdgeDir::Recv)),
+            Some(Some(EdgeDir::Emit)),
+        ];
+
+        for edge_type in edge_types.iter() {
+            for dir in dirs.iter() {
+                for host in hosts.iter() {
+                    for target in targets.iter() {
+                        for render_info in render_infos.iter() {
+                            let edge_finder = EdgeFinder {
+                                edge_type: edge_type.clone(),
+                                dir: dir.clone(),
+                                host: host.clone(),
+                                target: target.clone(),
+                                render_info: render_info.clone(),
+                                ..EdgeFinder::new()
+                            };
+                            let expected = all_edges
+                                .iter()
+                                .filter(|edge| expected_match(&edge_finder, edge))
+                                .cloned()
+                                .collect::<HashSet<_>>();
+
+                            let found = node.search_for_edge(&edge_finder.match_all());
+                            assert_eq!(
+                                found.unwrap_or_default(),
+                                expected,
+                                "match_all: {:?}",
+                                edge_finder
+                            );
+
+                            // Otherwise a single edge is returned, from the incoming edges if any of them match
+                            let not_match_all = EdgeFinder {
+                                match_all: Some(false),
+                                ..edge_finder.clone()
+                            };
+                            for edge_finder in [edge_finder, not_match_all] {
+                                let found = node.search_for_edge(&edge_finder);
+                                if expected.is_empty() {
+                                    assert_eq!(found, None, "{:?}", edge_finder);
+                                    continue;
+                                }
+                                let found = found.unwrap();
+                                assert_eq!(found.len(), 1, "{:?}", edge_finder);
+                                let found_edge = found.iter().next().unwrap();
+                                assert!(expected.contains(found_edge), "{:?}", edge_finder);
+                                if expected.iter().any(|edge| edge.dir == EdgeDir::Recv) {
+                                    assert_eq!(found_edge.dir, EdgeDir::Recv, "{:?}", edge_finder);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_skip_edges_in_the_other_direction() {
+        // An outgoing edge which was filed with the incoming edges
+        let node = build_node(
+            &[
+                edge(EdgeDir::Emit, "a", 3, None),
+                edge(EdgeDir::Recv, "a", 2, None),
+            ],
+            &[],
+        );
+        assert_eq!(
+            node.search_for_edge(&EdgeFinder::new().dir(EdgeDir::Recv).match_all()),
+            Some(hashset![edge(EdgeDir::Recv, "a", 2, None)])
+        );
+        assert_eq!(
+            node.search_for_edge(&EdgeFinder::new().dir(EdgeDir::Emit).match_all()),
+            None
+        );
+    }
+
+    #[test]
+    fn should_search_outgoing_edges_when_nothing_incoming_matches() {
+        let node = build_node(
+            &[edge(EdgeDir::Recv, "a", 2, None)],
+            &[edge(EdgeDir::Emit, "b", 3, None)],
+        );
+        let edge_finder = EdgeFinder {
+            match_all: Some(false),
+            ..EdgeFinder::new().edge_type("b".into())
+        };
+        assert_eq!(
+            node.search_for_edge(&edge_finder),
+            Some(hashset![edge(EdgeDir::Emit, "b", 3, None)])
+        );
+        assert_eq!(
+            node.search_for_edge(&EdgeFinder::new().edge_type("c".into()).match_all()),
+            None
+        );
+        assert_eq!(
+            node.search_for_edge(&EdgeFinder::new().match_all())
+                .map(|edges| edges.len()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn should_match_gates_node_conditions_payloads_and_combinations() {
+        // Labels of the host node 1 and of the nodes at the other end of its edges
+        fn node_labels(id: Uid) -> &'static [&'static str] {
+            match id {
+                1 => &["host"],
+                2 => &["x"],
+                3 => &["x", "y"],
+                5 => &["y"],
+                _ => &[],
+            }
+        }
+        fn has_label(id: Uid, label: &str) -> bool {
+            node_labels(id).contains(&label)
+        }
+        let with_payload = |edge: EdgeDescriptor<String>| EdgeDescriptor {
+            payload: Some(()),
+            ..edge
+        };
+        let incoming_edges = [
+            edge(EdgeDir::Recv, "a", 2, Some(EdgeDir::Recv)),
+            with_payload(edge(EdgeDir::Recv, "b", 3, None)),
+        ];
+        let outgoing_edges = [
+            with_payload(edge(EdgeDir::Emit, "a", 4, Some(EdgeDir::Emit))),
+            edge(EdgeDir::Emit, "b", 5, None),
+            edge(EdgeDir::Emit, "a", 3, None),
+        ];
+        let node = build_labelled_node(1, node_labels(1), &incoming_edges, &outgoing_edges);
+        let nodes = (1..=5)
+            .map(|id| {
+                (
+                    id,
+                    Rc::new(build_labelled_node(id, node_labels(id), &[], &[])),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let get_node_closure: GetNodeClosure<String, String, String> = Rc::new(move |id: &Uid| {
+            nodes
+                .get(id)
+                .cloned()
+                .ok_or(GraphError::Traversal(TraversalError::NotFound))
+        });
+        let all_edges = incoming_edges
+            .iter()
+            .chain(outgoing_edges.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Each finder alongside an independent definition of the edges it should match
+        type Expected = Rc<dyn Fn(&EdgeDescriptor<String>) -> bool>;
+        let conditions: Vec<(EdgeFinder<String, String, String>, Expected)> = vec![
+            (
+                EdgeFinder::new().gate_closure(
+                    |node| node.data.get_untracked() == "node4",
+                    get_node_closure.clone(),
+                ),
+                Rc::new(|edge| edge.target == 4),
+            ),
+            (
+                EdgeFinder::new().target_has_label("x"),
+                Rc::new(|edge| has_label(edge.target, "x")),
+            ),
+            (
+                EdgeFinder::new().target_lacks_label("y"),
+                Rc::new(|edge| !has_label(edge.target, "y")),
+            ),
+            (
+                EdgeFinder::new().target_data("is_node3", |data: &String| data == "node3"),
+                Rc::new(|edge| edge.target == 3),
+            ),
+            (
+                EdgeFinder::new().host_has_label("host"),
+                Rc::new(|edge| has_label(edge.host, "host")),
+            ),
+            (
+                EdgeFinder::new().host_lacks_label("host"),
+                Rc::new(|edge| !has_label(edge.host, "host")),
+            ),
+            (
+                EdgeFinder::new().payload("has_payload", |_| true),
+                Rc::new(|edge| edge.payload.is_some()),
+            ),
+            (
+                EdgeFinder::new().edge_type("a".into()),
+                Rc::new(|edge| edge.edge_type == "a"),
+            ),
+        ];
+
+        let mut cases: Vec<(EdgeFinder<String, String, String>, Expected)> = Vec::new();
+        for (finder, expected) in conditions.iter() {
+            let negated = expected.clone();
+            cases.push((finder.clone(), expected.clone()));
+            cases.push((finder.not(), Rc::new(move |edge| !negated(edge))));
+            for (other_finder, other_expected) in conditions.iter() {
+                let (left, right) = (expected.clone(), other_expected.clone());
+                cases.push((
+                    finder.and(other_finder.clone()),
+                    Rc::new(move |edge| left(edge) && right(edge)),
+                ));
+                let (left, right) = (expected.clone(), other_expected.clone());
+                cases.push((
+                    finder.or(other_finder.clone()),
+                    Rc::new(move |edge| left(edge) || right(edge)),
+                ));
+                let (left, right) = (expected.clone(), other_expected.clone());
+                cases.push((
+                    finder.and(other_finder.not()),
+                    Rc::new(move |edge| left(edge) && !right(edge)),
+                ));
+            }
+        }
+
+        for (edge_finder, expected) in cases {
+            let edge_finder = edge_finder.bind_graph(&get_node_closure).match_all();
+            let expected_edges = all_edges
+                .iter()
+                .filter(|edge| expected(edge))
+                .cloned()
+                .collect::<HashSet<_>>();
+            assert_eq!(
+                node.try_search_for_edge(&edge_finder)
+                    .unwrap()
+                    .unwrap_or_default(),
+                expected_edges,
+                "{:?}",
+                edge_finder
+            );
+        }
+    }
+}
@@ -1,70 +1,54 @@
-use im::{HashMap, HashSet};
+use im::{HashMap, HashSet, Vector};
 
-use crate::prelude::{EdgeDescriptor, EdgeFinder, GraphTraits};
+use crate::prelude::{EdgeDescriptor, EdgeDir, EdgeFinder, EdgeTraits, GraphTraits};
 
-pub fn search_map_for_edge<T: GraphTraits, E: GraphTraits, A: GraphTraits, I>(
-    edge_finder: &EdgeFinder<T, E, A>,
+pub fn search_map_for_edge<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits, I>(
+    edge_finder: &EdgeFinder<T, E, A, L>,
     map: &HashMap<E, I>,
+    map_dir: EdgeDir,
 ) -> HashSet<EdgeDescriptor<E>>
 where
     I: IntoIterator<Item = EdgeDescriptor<E>> + Clone,
 {
+    let match_all = edge_finder.match_all.unwrap_or(false);
     let mut found_edges = HashSet::new();
     for (edge_type, edges) in map.iter() {
-        // Return the array of one if the edgefinder is not set to match all
-        if !found_edges.is_empty()
-            && (edge_finder.match_all.is_none()
-                || (edge_finder.match_all.is_some() && !edge_finder.match_all.unwrap()))
+        if edge_finder
+            .edge_type
+            .as_ref()
+            .is_some_and(|edge_types| !edge_types.contains(edge_type))
         {
-            break;
+            continue;
         }
-
-        if edge_finder.edge_type.as_ref().is_some()
-            && !edge_finder.edge_type.as_ref().unwrap().contains(edge_type)
+        // Only symmetric edge types can match a finder for the other direction
+        if edge_finder
+            .dir
+            .as_ref()
+            .is_some_and(|dir| dir != &map_dir && !edge_type.is_symmetric())
         {
             continue;
         }
         for edge in edges.clone().into_iter() {
-            // Return the array of one if the edgefinder is not set to match all
-            if !found_edges.is_empty()
-                && (edge_finder.match_all.is_none()
-                    || (edge_finder.match_all.is_some() && !edge_finder.match_all.unwrap()))
-            {
-                break;
-            }
-
-            if edge_finder.dir.as_ref().is_some() && edge_finder.dir.as_ref().unwrap() != &edge.dir
-            {
-                panic!("Edge direction does not match");
-            }
-
-            if edge_finder.host.is_some()
-                && !edge_finder.host.as_ref().unwrap().contains(&edge.host)
-            {
-                continue;
-            }
-            if edge_finder.target.is_some()
-                && !edge_finder.target.as_ref().unwrap().contains(&edge.target)
-            {
-                continue;
-            }
-            if edge_finder.render_info.is_some()
-                && edge_finder.render_info.as_ref().unwrap() != &edge.render_info
-            {
-                continue;
-            }
-            if edge_finder.gate_closure.is_some() {
-                let (gate_closure, get_node) = edge_finder.gate_closure.as_ref().unwrap();
-                if let Ok(node) = get_node(&edge.target) {
-                    if !gate_closure(&node) {
-                        continue;
-                    }
-                } else {
-                    continue;
+            // Edges in the other direction are skipped like any other edge which doesn't match
+            if edge_finder.matches(&edge) {
+                found_edges.insert(edge);
+                // Return the array of one if the edgefinder is not set to match all
+                if !match_all {
+                    return found_edges;
                 }
             }
-            found_edges.insert(edge.clone());
         }
     }
     found_edges
 }
+
+// Keeps the edges of a type sorted by their order keys
+pub fn insert_in_order<E: EdgeTraits>(
+    edges: &mut Vector<EdgeDescriptor<E>>,
+    edge: EdgeDescriptor<E>,
+) {
+    let index = edges
+        .binary_search_by(|other| other.cmp_order(&edge))
+        .unwrap_or_else(|index| index);
+    edges.insert(index, edge);
+}
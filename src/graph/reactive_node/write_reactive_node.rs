@@ -4,29 +4,38 @@ use im::{HashSet, Vector};
 // use leptos_reactive::{SignalSet, SignalUpdate, WriteSignal};
 use leptos::*;
 
-use crate::prelude::finalized_update_node::FinalizedUpdateNode;
+use crate::prelude::finalized_update_node::{EdgePayloadUpdate, FinalizedUpdateNode};
 use crate::prelude::*;
 
+use super::edge_index::EdgeIndex;
 use super::last_action::ActionData;
 use super::last_action::LastAction;
+use super::utils::insert_in_order;
 use im::hashmap::HashMap;
 
 #[derive(Clone, PartialEq, Debug, Eq)]
-pub struct WriteReactiveNode<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
+pub struct WriteReactiveNode<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String>
+{
     pub id: Uid,
     pub data: WriteSignal<T>,
-    pub labels: WriteSignal<Vector<String>>,
+    pub labels: WriteSignal<Vector<L>>,
     pub incoming_edges: WriteSignal<HashMap<E, Vector<EdgeDescriptor<E>>>>,
     pub outgoing_edges: WriteSignal<HashMap<E, Vector<EdgeDescriptor<E>>>>,
-    pub last_action: WriteSignal<LastAction<T, E, A>>,
+    pub edge_index: WriteSignal<EdgeIndex<E>>,
+    pub last_action: WriteSignal<LastAction<T, E, A, L>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> WriteReactiveNode<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> WriteReactiveNode<T, E, A, L> {
     pub fn update(
         &mut self,
-        update_node: FinalizedUpdateNode<T, E>,
+        update_node: FinalizedUpdateNode<T, E, L>,
         action_data: Rc<ActionData<A>>,
-    ) {
+    ) -> Result<(), GraphError> {
+        // Checked before anything is changed, so a bad update leaves the node as it was
+        if let Some(updates) = &update_node.update_edge_payloads {
+            self.check_edge_payload_updates(updates, update_node.remove_edges.as_ref())?;
+        }
+
         let new_last_action = LastAction {
             action_data,
             update_info: Some(update_node.clone()),
@@ -68,15 +77,60 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> WriteReactiveNode<T, E, A>
                 self.add_edges(outgoing_edges, EdgeDir::Emit);
             }
         }
+
+        if let Some(updates) = update_node.update_edge_payloads {
+            let mut incoming_updates = updates.clone();
+            incoming_updates.retain(|update| update.edge.dir == EdgeDir::Recv);
+            let mut outgoing_updates = updates.clone();
+            outgoing_updates.retain(|update| update.edge.dir == EdgeDir::Emit);
+            if !incoming_updates.is_empty() {
+                self.update_edge_payloads(incoming_updates, EdgeDir::Recv);
+            }
+            if !outgoing_updates.is_empty() {
+                self.update_edge_payloads(outgoing_updates, EdgeDir::Emit);
+            }
+        }
+        Ok(())
     }
 
-    fn add_labels(&self, labels: HashSet<String>) {
+    // Every updated edge has to exist, and not be removed by the same update
+    fn check_edge_payload_updates(
+        &self,
+        updates: &HashSet<EdgePayloadUpdate<E>>,
+        remove_edges: Option<&HashSet<EdgeDescriptor<E>>>,
+    ) -> Result<(), GraphError> {
+        for update in updates.iter() {
+            let prev_edge = update.prev_edge();
+            let edge_map = match prev_edge.dir {
+                EdgeDir::Recv => &self.incoming_edges,
+                EdgeDir::Emit => &self.outgoing_edges,
+            };
+            // Only read, so nothing is notified
+            let exists = edge_map
+                .try_update_untracked(|edge_map| {
+                    edge_map
+                        .get(&prev_edge.edge_type)
+                        .is_some_and(|edges| edges.contains(&prev_edge))
+                })
+                .unwrap_or(false);
+            let is_removed = remove_edges.is_some_and(|edges| edges.contains(&prev_edge));
+            if !exists || is_removed {
+                return Err(GraphError::Blueprint(format!(
+                    "Update Node: Tried to update the payload of an edge that doesn't exist, Edge: {:?}",
+                    prev_edge
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn add_labels(&self, labels: HashSet<L>) {
         self.labels.update(|prev| {
             prev.extend(labels);
         });
     }
 
-    fn remove_labels(&mut self, labels: HashSet<String>) {
+    fn remove_labels(&mut self, labels: HashSet<L>) {
         self.labels.update(|prev| {
             let new_labels = prev
                 .iter()
@@ -94,6 +148,11 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> WriteReactiveNode<T, E, A>
             EdgeDir::Emit => &mut self.outgoing_edges,
         };
 
+        self.edge_index.update(|edge_index| {
+            for edge in edges.iter() {
+                edge_index.remove(edge);
+            }
+        });
         map_to_edit.update(|prev| {
             for edge in edges {
                 if prev.get(&edge.edge_type).is_none() {
@@ -115,16 +174,46 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> WriteReactiveNode<T, E, A>
             EdgeDir::Emit => &mut self.outgoing_edges,
         };
 
+        self.edge_index.update(|edge_index| {
+            for edge in edges.iter() {
+                edge_index.insert(edge);
+            }
+        });
         map_to_edit.update(|prev| {
             for edge in edges {
-                prev.entry(edge.edge_type.clone())
-                    .or_default()
-                    .push_back(edge.clone());
+                insert_in_order(prev.entry(edge.edge_type.clone()).or_default(), edge);
+            }
+        });
+    }
+
+    // Edges are replaced in place so they keep their position among the edges of their type
+    fn update_edge_payloads(&mut self, updates: HashSet<EdgePayloadUpdate<E>>, direction: EdgeDir) {
+        let map_to_edit = match direction {
+            EdgeDir::Recv => &mut self.incoming_edges,
+            EdgeDir::Emit => &mut self.outgoing_edges,
+        };
+
+        self.edge_index.update(|edge_index| {
+            for update in updates.iter() {
+                edge_index.remove(&update.prev_edge());
+                edge_index.insert(&update.new_edge());
+            }
+        });
+        map_to_edit.update(|prev| {
+            // Every edge was checked to exist before the update started
+            for update in updates {
+                let prev_edge = update.prev_edge();
+                let Some(edges) = prev.get_mut(&prev_edge.edge_type) else {
+                    continue;
+                };
+                if let Some(index) = edges.index_of(&prev_edge) {
+                    edges.set(index, update.new_edge());
+                }
             }
         });
     }
 
-    // fn set_last_action(&mut self, action: LastAction<T, E, A>) {
+    // fn set_last_action(&mut self, action: LastAction<T, E, A, L>) {
     //     self.last_action.set(action);
     // }
 }
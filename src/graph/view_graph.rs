@@ -3,50 +3,83 @@ use leptos::*;
 use std::{cell::RefCell, rc::Rc};
 
 use im::hashmap::HashMap;
+use im::Vector;
 
 use crate::prelude::{
     delete_node::DeleteNode, finalized_update_node::FinalizedUpdateNode, new_node::NewNode, *,
 };
-use im::Vector;
 
+use super::graph_schema::SchemaHandle;
+use super::label_index::LabelIndex;
 use super::reactive_node::{
     build_reactive_node::BuildReactiveNode, last_action::ActionData,
     read_reactive_node::ReadReactiveNode, write_reactive_node::WriteReactiveNode,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub struct ViewGraph<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
+pub struct ViewGraph<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
     pub nodes: HashMap<
         Uid,
         (
-            Rc<ReadReactiveNode<T, E, A>>,
-            RefCell<WriteReactiveNode<T, E, A>>,
+            Rc<ReadReactiveNode<T, E, A, L>>,
+            RefCell<WriteReactiveNode<T, E, A, L>>,
         ),
     >,
-    pub label_map: HashMap<String, Vector<Uid>>,
+    // Kept in sync with the labels of every node as they're added, updated and deleted
+    pub label_index: LabelIndex<L>,
+    // Checked against every blueprint before it is applied
+    pub schema: Option<SchemaHandle<T, E, A, L>>,
+    // Resolves the predicate keys of edge finders bound while finalizing blueprints
+    pub predicate_registry: PredicateRegistry<T, E, A, L>,
+    // Gives ids to the new nodes and edges of blueprints built without their own generator
+    pub id_generator: IdGenerator,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> Default for ViewGraph<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Default
+    for ViewGraph<T, E, A, L>
+{
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> ViewGraph<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> ViewGraph<T, E, A, L> {
     pub fn new() -> Self {
         Self {
             nodes: HashMap::new(),
-            label_map: HashMap::new(),
+            label_index: LabelIndex::new(),
+            schema: None,
+            predicate_registry: PredicateRegistry::new(),
+            id_generator: IdGenerator::default(),
         }
     }
-    pub fn add_nodes(&mut self, nodes: HashMap<Uid, NewNode<T, E>>, action_data: ActionData<A>) {
+
+    // Was a public field before the label index replaced it
+    #[deprecated(note = "use label_index, which can also be queried reactively")]
+    pub fn label_map(&self) -> HashMap<L, Vector<Uid>> {
+        self.label_index.label_map_untracked()
+    }
+
+    // Looks nodes up in the graph as it is now
+    pub fn get_node_closure(&self) -> GetNodeClosure<T, E, A, L> {
+        let nodes = self.nodes.clone();
+        Rc::new(move |id: &Uid| {
+            nodes
+                .get(id)
+                .map(|(read_node, _)| read_node.clone())
+                .ok_or_else(|| {
+                    GraphError::Blueprint(format!("View Graph: Failed to find node, ID: {:?}", id))
+                })
+        })
+    }
+    pub fn add_nodes(&mut self, nodes: HashMap<Uid, NewNode<T, E, L>>, action_data: ActionData<A>) {
         for (_id, node) in nodes {
             self.add_node(node, Rc::new(action_data.clone()));
         }
     }
     pub fn delete_nodes(
         &mut self,
-        nodes: HashMap<Uid, DeleteNode<T, E>>,
+        nodes: HashMap<Uid, DeleteNode<T, E, L>>,
     ) -> Result<(), GraphError> {
         for (id, _node) in nodes {
             self.delete_node(id)?;
@@ -55,7 +88,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> ViewGraph<T, E, A> {
     }
     pub fn update_nodes(
         &self,
-        update_nodes: HashMap<Uid, FinalizedUpdateNode<T, E>>,
+        update_nodes: HashMap<Uid, FinalizedUpdateNode<T, E, L>>,
         action_data: ActionData<A>,
     ) -> Result<(), GraphError> {
         let action_data = Rc::new(action_data);
@@ -65,32 +98,29 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> ViewGraph<T, E, A> {
         Ok(())
     }
 
-    fn add_node(&mut self, add_node: NewNode<T, E>, action_data: Rc<ActionData<A>>) {
+    fn add_node(&mut self, add_node: NewNode<T, E, L>, action_data: Rc<ActionData<A>>) {
         let id = add_node.id;
         let (read_node, write_node) = BuildReactiveNode::new()
             .ingest_from_blueprint(add_node, action_data)
             .build();
-        for label in read_node.labels.get_untracked().iter() {
-            let mut nodes_with_label = self
-                .label_map
-                .get::<String>(label)
-                .unwrap_or(&Vector::new())
-                .clone();
-            nodes_with_label.push_back(id);
-            self.label_map.insert(label.clone(), nodes_with_label);
-        }
+        self.label_index
+            .set_node_labels(id, read_node.labels.get_untracked());
         self.nodes
             .insert(id, (Rc::new(read_node), RefCell::new(write_node)));
     }
 
     fn update_node(
         &self,
-        update_node: FinalizedUpdateNode<T, E>,
+        update_node: FinalizedUpdateNode<T, E, L>,
         action_data: Rc<ActionData<A>>,
     ) -> Result<(), GraphError> {
         let graph_node = self.nodes.get(&update_node.id);
         if let Some(graph_node) = graph_node {
-            graph_node.1.borrow_mut().update(update_node, action_data);
+            let id = update_node.id;
+            graph_node.1.borrow_mut().update(update_node, action_data)?;
+            // Read back from the node so the index matches however the labels were changed
+            self.label_index
+                .set_node_labels(id, graph_node.0.labels.get_untracked());
         } else {
             return Err(GraphError::Blueprint(format!(
                 "Update Node: Failed to find node, ID: {:?}",
@@ -101,28 +131,13 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> ViewGraph<T, E, A> {
     }
 
     fn delete_node(&mut self, node_id: Uid) -> Result<(), GraphError> {
-        let node = self.nodes.get(&node_id).ok_or_else(|| {
+        self.nodes.remove(&node_id).ok_or_else(|| {
             GraphError::Blueprint(format!(
                 "Delete Node: Failed to find node, ID: {:?}",
                 node_id
             ))
         })?;
-        let node = &node.0;
-        for label in node.labels.get().iter() {
-            let mut nodes_with_label = self
-                .label_map
-                .get(label)
-                .ok_or_else(|| {
-                    GraphError::Blueprint(format!(
-                        "Delete Node: Failed to find label, Label: {:?}",
-                        label
-                    ))
-                })?
-                .clone();
-            nodes_with_label.retain(|item| *item != node_id);
-            self.label_map.insert(label.clone(), nodes_with_label);
-        }
-        self.nodes.remove(&node_id);
+        self.label_index.remove_node(node_id);
         Ok(())
     }
 }
@@ -4,17 +4,18 @@ use crate::prelude::*;
 
 use super::{history_item::HistoryItem, history_store::HistoryStore};
 
-pub struct HistoryInterface<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub history: HistoryStore<T, E, A>,
+pub struct HistoryInterface<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String>
+{
+    pub history: HistoryStore<T, E, A, L>,
     apply_finalized_blueprint:
-        Rc<dyn Fn(FinalizedBlueprint<T, E>, A, Option<Rc<A>>) -> Result<(), GraphError>>,
+        Rc<dyn Fn(FinalizedBlueprint<T, E, L>, A, Option<Rc<A>>) -> Result<(), GraphError>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> HistoryInterface<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> HistoryInterface<T, E, A, L> {
     pub fn new(
-        history_store: HistoryStore<T, E, A>,
+        history_store: HistoryStore<T, E, A, L>,
         apply_finalized_blueprint: Rc<
-            dyn Fn(FinalizedBlueprint<T, E>, A, Option<Rc<A>>) -> Result<(), GraphError>,
+            dyn Fn(FinalizedBlueprint<T, E, L>, A, Option<Rc<A>>) -> Result<(), GraphError>,
         >,
     ) -> Self {
         Self {
@@ -46,7 +47,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> HistoryInterface<T, E, A> {
         }
     }
 
-    pub fn push(&self, item: HistoryItem<T, E, A>) {
+    pub fn push(&self, item: HistoryItem<T, E, A, L>) {
         self.history.push(item);
     }
 }
@@ -1,14 +1,14 @@
 use std::rc::Rc;
 
-use crate::prelude::{FinalizedBlueprint, GraphTraits};
+use crate::prelude::{EdgeTraits, FinalizedBlueprint, GraphTraits};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct HistoryItem<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub blueprint: FinalizedBlueprint<T, E>,
+pub struct HistoryItem<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub blueprint: FinalizedBlueprint<T, E, L>,
     pub action_data: Rc<A>,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> HistoryItem<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> HistoryItem<T, E, A, L> {
     pub fn history_invert(&self) -> Self {
         Self {
             blueprint: self.blueprint.invert_blueprint(),
@@ -1,11 +1,22 @@
-use std::rc::Rc;
+use std::{cell::Cell, rc::Rc};
 
 use im::Vector;
-use leptos::SignalGetUntracked;
+use leptos::{create_effect, SignalGet, SignalGetUntracked};
 
-use crate::{prelude::*, traversal::traversal_step::TraversalCount};
+use crate::{
+    prelude::{
+        graph_schema::{Cardinality, GraphSchema, NodeKind, OnDelete},
+        label_index::LabelQuery,
+        reactive_node::read_reactive_node::ReadReactiveNode,
+        *,
+    },
+    traversal::{traversal_step::TraversalCount, traversal_step_result::TraversalStepResult},
+};
 
-use super::{use_routable, use_routable_store, UseRoutableReturn};
+use super::{
+    use_routable, use_routable_store, use_routable_store_with_schema,
+    use_routable_store_with_settings, GraphSettings, UseRoutableReturn,
+};
 
 fn setup_context() -> Rc<UseRoutableReturn<String, String, String>> {
     use_routable_store::<String, String, String>(
@@ -75,6 +86,49 @@ fn add_branch_to_graph(routable: Rc<UseRoutableReturn<String, String, String>>)
         .unwrap();
 }
 
+// (4)-d->(8)-d->(9)
+// (2)-d->(10)-d->(11)
+fn add_different_edge_types_to_graph(routable: Rc<UseRoutableReturn<String, String, String>>) {
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(4).add_edge_new(
+        EdgeDir::Emit,
+        "different_edge_type".into(),
+        |blue_new| {
+            blue_new
+                .set_temp_id(8)
+                .set_id(8)
+                .set_data("node8".to_string())
+                .add_edge_new(EdgeDir::Emit, "different_edge_type".into(), |blue_new| {
+                    blue_new
+                        .set_temp_id(9)
+                        .set_id(9)
+                        .set_data("node9".to_string())
+                })
+        },
+    );
+
+    blueprint.start_with_update_node(2).add_edge_new(
+        EdgeDir::Emit,
+        "different_edge_type".into(),
+        |blue_new| {
+            blue_new
+                .set_temp_id(10)
+                .set_id(10)
+                .set_data("node10".to_string())
+                .add_edge_new(EdgeDir::Emit, "different_edge_type".into(), |blue_new| {
+                    blue_new
+                        .set_temp_id(11)
+                        .set_id(11)
+                        .set_data("node11".to_string())
+                })
+        },
+    );
+
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+}
+
 #[test]
 fn should_setup_context() {
     let routable = setup_context();
@@ -353,3 +407,2182 @@ fn should_return_correct_multi_step_branching_traversal_results() {
     assert_eq!(final_step_9_endpoint.step_index, 2);
     assert_eq!(final_step_9_endpoint.traversal_index, 7);
 }
+
+#[test]
+fn should_filter_nodes_within_traversal_steps() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(6)
+        .add_label("branch".to_string());
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+
+    let endpoint_ids = |node_filter: NodeFilter<String, String, String>| {
+        let traversal = routable
+            .traverse_search(1)
+            .add_filtered_step(
+                EdgeFinder::new()
+                    .dir(EdgeDir::Emit)
+                    .edge_type("edge_type".into())
+                    .match_all(),
+                TraversalCount::AtLeastInclusive(1),
+                node_filter,
+            )
+            .execute()
+            .unwrap();
+        let mut ids = traversal.step_results[0][0]
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.node.id)
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(
+        endpoint_ids(NodeFilter::lacks_label("branch")),
+        vec![2, 3, 4, 5]
+    );
+    assert_eq!(
+        endpoint_ids(NodeFilter::data("not_node4", |data: &String| data != "node4")),
+        vec![2, 3, 6, 7]
+    );
+    assert_eq!(
+        endpoint_ids(
+            NodeFilter::has_edge(EdgeFinder::new().dir(EdgeDir::Emit))
+                .and(NodeFilter::has_label("branch").not())
+        ),
+        vec![2, 3, 4]
+    );
+}
+
+fn sorted_endpoint_ids<L: GraphTraits>(
+    step_results: &Vector<TraversalStepResult<String, String, String, L>>,
+) -> Vec<Uid> {
+    let mut ids = step_results
+        .iter()
+        .flat_map(|step_result| {
+            step_result
+                .endpoints
+                .iter()
+                .map(|endpoint| endpoint.node.id)
+        })
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+#[test]
+fn should_return_correct_optional_step_traversal_results() {
+    let routable = set_up_basic_graph();
+
+    let traversal = routable
+        .traverse_search(1)
+        .add_optional_step(EdgeFinder::new().dir(EdgeDir::Emit))
+        .execute()
+        .unwrap();
+    assert_eq!(sorted_endpoint_ids(&traversal.step_results[0]), vec![1, 2]);
+
+    let traversal = routable
+        .traverse_search(5)
+        .add_optional_step(EdgeFinder::new().dir(EdgeDir::Emit))
+        .execute()
+        .unwrap();
+    assert_eq!(sorted_endpoint_ids(&traversal.step_results[0]), vec![5]);
+
+    let traversal = routable
+        .traverse_search(1)
+        .add_step(
+            EdgeFinder::new().dir(EdgeDir::Emit),
+            TraversalCount::Exactly(1),
+        )
+        .add_optional_step(EdgeFinder::new().dir(EdgeDir::Emit))
+        .execute()
+        .unwrap();
+    assert_eq!(sorted_endpoint_ids(&traversal.step_results[1]), vec![2, 3]);
+}
+
+#[test]
+fn should_return_correct_alternation_traversal_results() {
+    let routable = set_up_basic_graph();
+    add_different_edge_types_to_graph(routable.clone());
+
+    let traversal = routable
+        .traverse_search(4)
+        .add_alternation_step(
+            vec![
+                EdgeFinder::new()
+                    .dir(EdgeDir::Emit)
+                    .edge_type("edge_type".into()),
+                EdgeFinder::new()
+                    .dir(EdgeDir::Emit)
+                    .edge_type("different_edge_type".into()),
+            ],
+            TraversalCount::Exactly(1),
+        )
+        .execute()
+        .unwrap();
+    assert_eq!(sorted_endpoint_ids(&traversal.step_results[0]), vec![5, 8]);
+
+    let traversal = routable
+        .traverse_search(2)
+        .add_traversal_step(
+            TraversalStep::new(
+                EdgeFinder::new()
+                    .dir(EdgeDir::Emit)
+                    .edge_type("different_edge_type".into())
+                    .match_all(),
+                TraversalCount::Exactly(2),
+            )
+            .or_edge_finder(
+                EdgeFinder::new()
+                    .dir(EdgeDir::Recv)
+                    .edge_type("edge_type".into())
+                    .match_all(),
+            ),
+        )
+        .execute()
+        .unwrap();
+    assert_eq!(sorted_endpoint_ids(&traversal.step_results[0]), vec![11]);
+
+    // An empty alternation follows no edges
+    let traversal = routable
+        .traverse_search(4)
+        .add_alternation_step(Vec::new(), TraversalCount::Exactly(1))
+        .execute();
+    assert!(traversal.is_none());
+}
+
+#[test]
+fn should_return_correct_repeat_until_traversal_results() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+
+    let traversal = routable
+        .traverse_search(1)
+        .add_repeat_until_step(
+            EdgeFinder::new().dir(EdgeDir::Emit).match_all(),
+            NodeFilter::data("is_node7", |data: &String| data == "node7"),
+        )
+        .execute()
+        .unwrap();
+    assert_eq!(sorted_endpoint_ids(&traversal.step_results[0]), vec![7]);
+    assert_eq!(traversal.step_results[0][0].endpoints[0].traversal_index, 4);
+
+    // The entry node is never checked, so the repetition continues until the next matching node
+    let traversal = routable
+        .traverse_search(3)
+        .add_repeat_until_step(
+            EdgeFinder::new().match_all(),
+            NodeFilter::has_edge(EdgeFinder::new().dir(EdgeDir::Emit)).not(),
+        )
+        .execute()
+        .unwrap();
+    assert_eq!(sorted_endpoint_ids(&traversal.step_results[0]), vec![5, 7]);
+
+    let traversal = routable
+        .traverse_search(1)
+        .add_repeat_until_step(
+            EdgeFinder::new().dir(EdgeDir::Emit).match_all(),
+            NodeFilter::has_label("missing_label"),
+        )
+        .execute();
+    assert!(traversal.is_none());
+}
+
+#[test]
+fn should_return_named_bindings_for_each_match() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+
+    let traversal = routable
+        .traverse_search(1)
+        .add_step(
+            EdgeFinder::new().dir(EdgeDir::Emit),
+            TraversalCount::Exactly(1),
+        )
+        .add_named_step(
+            "child",
+            EdgeFinder::new().dir(EdgeDir::Emit),
+            TraversalCount::Exactly(1),
+        )
+        .add_named_step(
+            "grandchild",
+            EdgeFinder::new().dir(EdgeDir::Emit).match_all(),
+            TraversalCount::Exactly(1),
+        )
+        .execute()
+        .unwrap();
+
+    let rows = traversal.bindings();
+    assert_eq!(rows.len(), 2);
+    for row in rows.iter() {
+        assert_eq!(row.len(), 2);
+        assert_eq!(row.get("child").unwrap().node.id, 3);
+        let grandchild = row.get("grandchild").unwrap();
+        let grandchild_edge = grandchild.edge.clone().unwrap();
+        assert_eq!(grandchild_edge.host, 3);
+        assert_eq!(grandchild_edge.target, grandchild.node.id);
+    }
+    let mut grandchild_ids = rows
+        .iter()
+        .map(|row| row.get("grandchild").unwrap().node.id)
+        .collect::<Vec<_>>();
+    grandchild_ids.sort();
+    assert_eq!(grandchild_ids, vec![4, 6]);
+
+    let traversal = routable
+        .traverse_search(1)
+        .add_named_step(
+            "maybe_child",
+            EdgeFinder::new().dir(EdgeDir::Emit),
+            TraversalCount::Optional,
+        )
+        .execute()
+        .unwrap();
+    let rows = traversal.bindings();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().any(|row| {
+        let binding = row.get("maybe_child").unwrap();
+        binding.node.id == 1 && binding.edge.is_none()
+    }));
+}
+
+fn path_ids(path: &TraversalStepResult<String, String, String>) -> Vec<Uid> {
+    let mut ids = vec![path.entry.node.id];
+    let mut current = path.entry.clone();
+    while let Some(downstream_edge) = current.downstream_edges.head().cloned() {
+        current = downstream_edge.target().clone();
+        ids.push(current.node.id);
+    }
+    assert_eq!(path.endpoints[0].node.id, *ids.last().unwrap());
+    ids
+}
+
+#[test]
+fn should_find_shortest_and_cheapest_paths() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    // Creates a second route from 3 to 5 which is the same length: (3)->(6)->(5)
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(6).add_edge_existing(
+        EdgeDir::Emit,
+        "edge_type".into(),
+        5,
+        |blue_existing| blue_existing,
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    let emit_finder = EdgeFinder::new().dir(EdgeDir::Emit);
+
+    let shortest_path = routable
+        .path_search(1, 7, emit_finder.clone())
+        .shortest_path()
+        .unwrap();
+    assert_eq!(path_ids(&shortest_path), vec![1, 2, 3, 6, 7]);
+    assert_eq!(shortest_path.endpoints[0].traversal_index, 4);
+
+    let mut all_shortest_paths = routable
+        .path_search(1, 5, emit_finder.clone())
+        .all_shortest_paths()
+        .iter()
+        .map(path_ids)
+        .collect::<Vec<_>>();
+    all_shortest_paths.sort();
+    assert_eq!(
+        all_shortest_paths,
+        vec![vec![1, 2, 3, 4, 5], vec![1, 2, 3, 6, 5]]
+    );
+
+    let (cost, cheapest_path) = routable
+        .path_search(1, 5, emit_finder.clone())
+        .cheapest_path(|edge| if edge.target == 6 { 10 } else { 1 })
+        .unwrap();
+    assert_eq!(cost, 4);
+    assert_eq!(path_ids(&cheapest_path), vec![1, 2, 3, 4, 5]);
+
+    // Float costs, where the route through 6 is cheaper
+    let (cost, cheapest_path) = routable
+        .path_search(1, 5, emit_finder.clone())
+        .cheapest_path(|edge| if edge.target == 4 { 2.5 } else { 0.5 })
+        .unwrap();
+    assert_eq!(cost, 2.0);
+    assert_eq!(path_ids(&cheapest_path), vec![1, 2, 3, 6, 5]);
+
+    assert!(routable
+        .path_search(5, 1, emit_finder)
+        .shortest_path()
+        .is_none());
+    assert_eq!(
+        path_ids(
+            &routable
+                .path_search(7, 1, EdgeFinder::new().dir(EdgeDir::Recv))
+                .shortest_path()
+                .unwrap()
+        ),
+        vec![7, 6, 3, 2, 1]
+    );
+}
+
+#[test]
+fn should_limit_and_paginate_traversal_results() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    let traversal = routable.traverse_search(1).add_step(
+        EdgeFinder::new().dir(EdgeDir::Emit).match_all(),
+        TraversalCount::AtLeastInclusive(1),
+    );
+    let endpoint_ids = |result: &TraversalResult<String, String, String>| {
+        result
+            .final_endpoints()
+            .iter()
+            .map(|endpoint| endpoint.node.id)
+            .collect::<Vec<_>>()
+    };
+
+    let result = traversal.execute().unwrap();
+    assert!(!result.truncated);
+    assert_eq!(endpoint_ids(&result), vec![2, 3, 4, 5, 6, 7]);
+
+    let result = traversal
+        .with_limits(TraversalLimits::new().max_depth(2))
+        .execute()
+        .unwrap();
+    assert!(result.truncated);
+    assert_eq!(endpoint_ids(&result), vec![2, 3]);
+
+    let result = traversal
+        .with_limits(TraversalLimits::new().max_results(3))
+        .execute()
+        .unwrap();
+    assert!(result.truncated);
+    assert_eq!(endpoint_ids(&result).len(), 3);
+
+    let result = traversal
+        .with_limits(TraversalLimits::new().max_visited(4))
+        .execute()
+        .unwrap();
+    assert!(result.truncated);
+    assert_eq!(endpoint_ids(&result).len(), 3);
+
+    let result = traversal
+        .with_limits(TraversalLimits::new().max_operations(3))
+        .execute()
+        .unwrap();
+    assert!(result.truncated);
+    assert_eq!(endpoint_ids(&result), vec![2, 3]);
+
+    // A limit hit before any route is found still gives a result, so the truncation isn't lost
+    let result = traversal
+        .with_limits(TraversalLimits::new().max_visited(1))
+        .execute()
+        .unwrap();
+    assert!(result.truncated);
+    assert!(endpoint_ids(&result).is_empty());
+    assert!(result.step_results.iter().all(|step| step.is_empty()));
+
+    let page_ids = |page: &TraversalPage<String, String, String>| {
+        page.endpoints
+            .iter()
+            .map(|endpoint| endpoint.node.id)
+            .collect::<Vec<_>>()
+    };
+    let first_page = traversal.execute_page(None, 4);
+    assert_eq!(page_ids(&first_page), vec![2, 3, 4, 5]);
+    assert!(first_page.next_cursor.is_some());
+    let second_page = traversal.execute_page(first_page.next_cursor, 4);
+    assert_eq!(page_ids(&second_page), vec![6, 7]);
+    assert_eq!(second_page.next_cursor, None);
+
+    // Limits apply to each page, and a page cut short by one is picked up by the next
+    let limited = traversal.with_limits(TraversalLimits::new().max_results(3));
+    let first_page = limited.execute_page(None, 4);
+    assert_eq!(page_ids(&first_page), vec![2, 3, 4]);
+    assert!(first_page.truncated);
+    let second_page = limited.execute_page(first_page.next_cursor, 4);
+    assert_eq!(page_ids(&second_page), vec![5, 6, 7]);
+    assert!(!second_page.truncated);
+    assert_eq!(second_page.next_cursor, None);
+
+    let limited = traversal.with_limits(TraversalLimits::new().max_operations(2));
+    let mut cursor = None;
+    let mut ids = Vec::new();
+    loop {
+        let page = limited.execute_page(cursor, 10);
+        ids.extend(page_ids(&page));
+        match page.next_cursor {
+            Some(next_cursor) => cursor = Some(next_cursor),
+            None => break,
+        }
+    }
+    assert_eq!(ids, vec![2, 3, 4, 5, 6, 7]);
+
+    // Pages of a multi-step traversal hold the same endpoints as a full execution
+    let multi_step = traversal.add_step(
+        EdgeFinder::new().dir(EdgeDir::Recv).match_all(),
+        TraversalCount::Exactly(1),
+    );
+    let mut page_endpoints = multi_step
+        .execute_page(None, 100)
+        .endpoints
+        .iter()
+        .map(EndpointKey::from_endpoint)
+        .collect::<Vec<_>>();
+    page_endpoints.sort();
+    assert_eq!(
+        page_endpoints,
+        multi_step
+            .execute()
+            .unwrap()
+            .final_endpoints()
+            .iter()
+            .map(EndpointKey::from_endpoint)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn should_guard_cycles_and_list_endpoints_once() {
+    let routable = set_up_basic_graph();
+    // (5)->(1) closes a cycle, and (2)->(4) gives two routes from 1 to 4
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(5).add_edge_existing(
+        EdgeDir::Emit,
+        "edge_type".into(),
+        1,
+        |blue_existing| blue_existing,
+    );
+    blueprint.start_with_update_node(2).add_edge_existing(
+        EdgeDir::Emit,
+        "edge_type".into(),
+        4,
+        |blue_existing| blue_existing,
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    let emit_finder = EdgeFinder::new().dir(EdgeDir::Emit).match_all();
+
+    // The cycle is only followed until it would reach a node already on the route
+    let result = routable
+        .traverse_search(1)
+        .add_step(emit_finder.clone(), TraversalCount::AtLeastInclusive(1))
+        .execute()
+        .unwrap();
+    assert!(!result.truncated);
+    assert_eq!(
+        sorted_endpoint_ids(&result.step_results[0]),
+        vec![2, 3, 4, 5]
+    );
+
+    // Node 4 is two hops away by both routes, and is only listed and counted once
+    let result = routable
+        .traverse_search(1)
+        .add_step(emit_finder.clone(), TraversalCount::Exactly(2))
+        .with_limits(TraversalLimits::new().max_results(2))
+        .execute()
+        .unwrap();
+    assert_eq!(result.step_results[0].len(), 1);
+    let mut endpoint_ids = result.step_results[0][0]
+        .endpoints
+        .iter()
+        .map(|endpoint| endpoint.node.id)
+        .collect::<Vec<_>>();
+    endpoint_ids.sort();
+    assert_eq!(endpoint_ids, vec![3, 4]);
+    assert!(!result.truncated);
+
+    // The entry of a later step keeps the edge it was reached by
+    let result = routable
+        .traverse_search(1)
+        .add_step(emit_finder.clone(), TraversalCount::Exactly(1))
+        .add_step(emit_finder, TraversalCount::Exactly(1))
+        .execute()
+        .unwrap();
+    let entry = &result.step_results[1][0].entry;
+    assert_eq!(entry.node.id, 2);
+    let upstream_edge = entry.upstream_edge.as_ref().unwrap();
+    assert_eq!((upstream_edge.edge.host, upstream_edge.edge.target), (1, 2));
+}
+
+#[test]
+fn should_traverse_from_multiple_start_nodes() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    let blueprint = BuildBlueprint::new();
+    for id in [2, 3, 6] {
+        blueprint
+            .start_with_update_node(id)
+            .add_label("task".to_string());
+    }
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    let descendants = EdgeFinder::new().dir(EdgeDir::Emit).match_all();
+    let group_ids = |groups: &Vector<(Uid, TraversalResult<String, String, String>)>| {
+        groups
+            .iter()
+            .map(|(start_id, result)| (*start_id, sorted_endpoint_ids(&result.step_results[0])))
+            .collect::<Vec<_>>()
+    };
+
+    let traversal = routable
+        .traverse_search_from(TraversalOrigin::label("task"))
+        .add_step(descendants.clone(), TraversalCount::AtLeastInclusive(1));
+    let mut groups = group_ids(&traversal.execute_grouped());
+    groups.sort();
+    assert_eq!(
+        groups,
+        vec![
+            (2, vec![3, 4, 5, 6, 7]),
+            (3, vec![4, 5, 6, 7]),
+            (6, vec![7])
+        ]
+    );
+    assert_eq!(traversal.execute().unwrap().step_results[0].len(), 3);
+
+    let traversal = routable
+        .traverse_search_from(TraversalOrigin::nodes([3, 2]))
+        .add_step(descendants.clone(), TraversalCount::AtLeastInclusive(1));
+    assert_eq!(
+        group_ids(&traversal.execute_grouped()),
+        vec![(3, vec![4, 5, 6, 7]), (2, vec![3, 4, 5, 6, 7])]
+    );
+    // Node 2 can only reach the rest of the graph through node 3, which was already visited
+    assert_eq!(
+        group_ids(
+            &traversal
+                .visited_scope(VisitedScope::Shared)
+                .execute_grouped()
+        ),
+        vec![(3, vec![4, 5, 6, 7])]
+    );
+    // Node 3 was already visited from node 2, so it isn't used as a start node
+    assert_eq!(
+        group_ids(
+            &routable
+                .traverse_search_from(TraversalOrigin::nodes([2, 3]))
+                .visited_scope(VisitedScope::Shared)
+                .add_step(descendants.clone(), TraversalCount::AtLeastInclusive(1))
+                .execute_grouped()
+        ),
+        vec![(2, vec![3, 4, 5, 6, 7])]
+    );
+
+    // Only the start node which ran out of the shared budget is marked truncated
+    let groups = routable
+        .traverse_search_from(TraversalOrigin::nodes([6, 2]))
+        .add_step(descendants.clone(), TraversalCount::AtLeastInclusive(1))
+        .with_limits(TraversalLimits::new().max_results(4))
+        .execute_grouped();
+    assert_eq!(
+        groups
+            .iter()
+            .map(|(start_id, result)| (*start_id, result.truncated))
+            .collect::<Vec<_>>(),
+        vec![(6, false), (2, true)]
+    );
+
+    // A label origin needs a closure to look up the label
+    let unlabelled = TraversalDescriptor::new(
+        TraversalOrigin::label("task"),
+        routable.get_node_closure.clone(),
+    )
+    .add_step(descendants.clone(), TraversalCount::AtLeastInclusive(1));
+    assert!(unlabelled.execute().is_none());
+    assert_eq!(
+        unlabelled
+            .with_labels(routable.get_label_closure.clone())
+            .execute_grouped()
+            .len(),
+        3
+    );
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(6)
+        .remove_label("task".to_string());
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    let mut task_ids = routable
+        .get_node_ids_with_label("task")
+        .into_iter()
+        .collect::<Vec<_>>();
+    task_ids.sort();
+    assert_eq!(task_ids, vec![2, 3]);
+}
+
+#[test]
+fn should_aggregate_traversal_results() {
+    let routable = set_up_basic_graph();
+    add_different_edge_types_to_graph(routable.clone());
+    let traversal = routable
+        .traverse_search(3)
+        .add_step(
+            EdgeFinder::new().dir(EdgeDir::Emit).match_all(),
+            TraversalCount::Exactly(1),
+        )
+        .add_step(
+            EdgeFinder::new().dir(EdgeDir::Emit).match_all(),
+            TraversalCount::AtLeastInclusive(1),
+        );
+    let result = traversal.execute().unwrap();
+
+    assert_eq!(result.count(AggregateScope::Step(0)), 1);
+    assert_eq!(result.count(AggregateScope::FinalEndpoints), 3);
+    assert_eq!(
+        result.collect_data(AggregateScope::FinalEndpoints),
+        Vector::from(vec![
+            "node5".to_string(),
+            "node8".to_string(),
+            "node9".to_string()
+        ])
+    );
+    let by_edge_type = result.group_by_edge_type(AggregateScope::FinalEndpoints);
+    assert_eq!(
+        by_edge_type
+            .get(&Some("edge_type".to_string()))
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        by_edge_type
+            .get(&Some("different_edge_type".to_string()))
+            .unwrap()
+            .len(),
+        2
+    );
+    let by_depth = result.group_by(AggregateScope::FinalEndpoints, |endpoint| {
+        endpoint.traversal_index
+    });
+    assert_eq!(by_depth.get(&2).unwrap().len(), 2);
+    assert_eq!(by_depth.get(&3).unwrap().len(), 1);
+    assert_eq!(
+        result.fold(AggregateScope::FinalEndpoints, 0, |total, endpoint| total
+            + endpoint.node.id),
+        5 + 8 + 9
+    );
+
+    let endpoint_count = traversal.create_aggregate_memo(&routable.graph_lock, |result| {
+        result.map_or(0, |result| result.count(AggregateScope::FinalEndpoints))
+    });
+    assert_eq!(endpoint_count.get_untracked(), 3);
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(9).add_edge_new(
+        EdgeDir::Emit,
+        "different_edge_type".into(),
+        |blue_new| blue_new.set_temp_id(12).set_id(12),
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    assert_eq!(endpoint_count.get_untracked(), 4);
+}
+
+#[test]
+fn should_save_and_rebind_traversal_queries() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    let registry = PredicateRegistry::<String, String, String>::new()
+        .register_data_predicate("not_node4", |data: &String| data != "node4")
+        .register_gate("not_node6", |node| node.id != 6);
+
+    let traversal = routable
+        .traverse_search(1)
+        .add_filtered_step(
+            EdgeFinder::new().dir(EdgeDir::Emit).match_all(),
+            TraversalCount::AtLeastInclusive(1),
+            registry.data_filter("not_node4").unwrap(),
+        )
+        .with_limits(TraversalLimits::new().max_depth(10));
+    // A filter built from a closure has no registered key, so it can't be bound again once saved
+    let unregistered = routable.traverse_search(1).add_filtered_step(
+        EdgeFinder::new().dir(EdgeDir::Emit).match_all(),
+        TraversalCount::AtLeastInclusive(1),
+        NodeFilter::data("not_node4", |data: &String| data != "node4"),
+    );
+    assert_eq!(
+        unregistered.to_query().err(),
+        Some(GraphError::Traversal(
+            TraversalError::UnserializablePredicate
+        ))
+    );
+    let saved = serde_json::to_string(&traversal.to_query().unwrap()).unwrap();
+    let query: TraversalQuery<String> = serde_json::from_str(&saved).unwrap();
+    assert_eq!(query, traversal.to_query().unwrap());
+
+    let rebound = query.bind(&routable, &registry).unwrap();
+    assert_eq!(rebound, traversal);
+    assert_eq!(
+        sorted_endpoint_ids(&rebound.execute().unwrap().step_results[0]),
+        vec![2, 3, 6, 7]
+    );
+
+    let mut gated_query = query.clone();
+    gated_query.steps[0].edge_finder.gate = Some("not_node6".to_string());
+    let gated = gated_query.bind(&routable, &registry).unwrap();
+    assert_eq!(
+        sorted_endpoint_ids(&gated.execute().unwrap().step_results[0]),
+        vec![2, 3]
+    );
+
+    gated_query.steps[0].edge_finder.gate = Some("missing".to_string());
+    assert_eq!(
+        gated_query.bind(&routable, &registry).err(),
+        Some(GraphError::Traversal(TraversalError::UnknownPredicate(
+            "missing".to_string()
+        )))
+    );
+    // Gates taken from the registry keep their key, so the bound query can be saved again
+    assert_eq!(
+        gated.to_query().unwrap().steps[0].edge_finder.gate,
+        Some("not_node6".to_string())
+    );
+}
+
+#[test]
+fn should_find_subgraph_pattern_matches() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    // Closes a triangle: (1)->(2)->(3) and (1)->(3)
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(1).add_edge_existing(
+        EdgeDir::Emit,
+        "edge_type".into(),
+        3,
+        |blue_existing| blue_existing,
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    let emit_finder = EdgeFinder::new().dir(EdgeDir::Emit);
+    let match_ids = |pattern: GraphPattern<String, String, String>, names: &[&str]| {
+        let mut ids = pattern
+            .find_matches()
+            .unwrap()
+            .iter()
+            .map(|pattern_match| {
+                names
+                    .iter()
+                    .map(|name| pattern_match.node_id(name).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    let triangle = routable
+        .pattern_search()
+        .add_edge("a", "b", emit_finder.clone())
+        .add_edge("b", "c", emit_finder.clone())
+        .add_edge("a", "c", emit_finder.clone());
+    assert_eq!(
+        match_ids(triangle.clone(), &["a", "b", "c"]),
+        vec![vec![1, 2, 3]]
+    );
+    let triangle_match = triangle.find_matches().unwrap()[0].clone();
+    assert_eq!(triangle_match.edges.len(), 3);
+    assert!(triangle_match.edges.iter().all(|edges| edges.len() == 1));
+
+    let shared_target = routable
+        .pattern_search()
+        .add_edge("a", "c", emit_finder.clone())
+        .add_edge("b", "c", emit_finder.clone());
+    assert_eq!(
+        match_ids(shared_target, &["a", "b", "c"]),
+        vec![vec![1, 2, 3], vec![2, 1, 3]]
+    );
+
+    let shared_source = routable
+        .pattern_search()
+        .add_edge("a", "b", emit_finder.clone())
+        .add_edge("a", "c", emit_finder.clone());
+    assert_eq!(
+        match_ids(shared_source.clone(), &["a", "b", "c"]),
+        vec![vec![1, 2, 3], vec![1, 3, 2], vec![3, 4, 6], vec![3, 6, 4]]
+    );
+    assert_eq!(
+        shared_source.max_matches(1).find_matches().unwrap().len(),
+        1
+    );
+
+    let pinned = routable
+        .pattern_search()
+        .add_pinned_node("a", 3)
+        .add_filtered_node(
+            "b",
+            NodeFilter::Data(DataPredicate::new("is_node6", |data: &String| {
+                data == "node6"
+            })),
+        )
+        .add_edge("a", "b", emit_finder.clone());
+    assert_eq!(match_ids(pinned, &["a", "b"]), vec![vec![3, 6]]);
+
+    // Edges pushed straight onto the pattern can name nodes it doesn't have
+    let mut dangling = routable.pattern_search().add_node("a");
+    dangling.edges.push_back(PatternEdge {
+        source: "a".into(),
+        target: "missing".into(),
+        edge_finder: emit_finder,
+    });
+    assert_eq!(
+        dangling.find_matches().err(),
+        Some(GraphError::Traversal(TraversalError::UnknownPatternNode(
+            "missing".into()
+        )))
+    );
+}
+
+#[test]
+fn should_combine_edge_finders() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    add_different_edge_types_to_graph(routable.clone());
+    let edge_targets = |node_id: Uid, edge_finder: &EdgeFinder<String, String, String>| {
+        let mut targets = routable
+            .get_node(&node_id)
+            .unwrap()
+            .search_for_edge(&edge_finder.match_all())
+            .unwrap_or_default()
+            .iter()
+            .map(|edge| edge.target)
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets
+    };
+    let emitted = EdgeFinder::new()
+        .edge_type("edge_type".into())
+        .dir(EdgeDir::Emit);
+    let received = EdgeFinder::new().dir(EdgeDir::Recv);
+
+    // Node 2: (1)->(2)->(3) and (2)-d->(10)
+    assert_eq!(edge_targets(2, &emitted.or(received.clone())), vec![1, 3]);
+    assert_eq!(
+        edge_targets(2, &EdgeFinder::new().dir(EdgeDir::Emit).not()),
+        vec![1]
+    );
+    assert_eq!(
+        edge_targets(
+            2,
+            &EdgeFinder::new()
+                .dir(EdgeDir::Emit)
+                .and(EdgeFinder::new().edge_type("edge_type".into()).not())
+        ),
+        vec![10]
+    );
+    // "Child edges emitted OR edges received, but NOT to node 1"
+    let combined = emitted.or(received).and(EdgeFinder::new().target(1).not());
+    assert_eq!(edge_targets(2, &combined), vec![3]);
+    assert!(!combined.matches(&EdgeDescriptor {
+        id: 0,
+        dir: EdgeDir::Recv,
+        edge_type: "edge_type".into(),
+        host: 2,
+        target: 1,
+        render_info: Some(EdgeDir::Recv),
+        payload: None,
+        order_key: None,
+    }));
+    assert_eq!(
+        combined
+            .to_spec()
+            .unwrap()
+            .bind(&PredicateRegistry::new(), routable.get_node_closure.clone()),
+        Ok(combined.clone())
+    );
+
+    let result = routable
+        .traverse_search(3)
+        .add_step(
+            EdgeFinder::new()
+                .dir(EdgeDir::Emit)
+                .and(EdgeFinder::new().target(6).not())
+                .match_all(),
+            TraversalCount::AtLeastInclusive(1),
+        )
+        .execute()
+        .unwrap();
+    let mut endpoint_ids = result
+        .final_endpoints()
+        .iter()
+        .map(|endpoint| endpoint.node.id)
+        .collect::<Vec<_>>();
+    endpoint_ids.sort();
+    assert_eq!(endpoint_ids, vec![4, 5, 8, 9]);
+}
+
+#[test]
+fn should_compare_edge_finders_by_gate() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    let get_node_closure = routable.get_node_closure.clone();
+    let to_node4 = EdgeFinder::new().dir(EdgeDir::Emit).gate_closure(
+        |node| node.data.get_untracked() == "node4",
+        get_node_closure.clone(),
+    );
+    let to_node6 = EdgeFinder::new().dir(EdgeDir::Emit).gate_closure(
+        |node| node.data.get_untracked() == "node6",
+        get_node_closure.clone(),
+    );
+    assert_ne!(to_node4, to_node6);
+    assert_eq!(to_node4, to_node4.clone());
+    assert!(to_node4.to_spec().is_err());
+
+    let registry = PredicateRegistry::new()
+        .register_gate("is_node4", |node| node.data.get_untracked() == "node4")
+        .register_gate("is_node6", |node| node.data.get_untracked() == "node6");
+    let keyed_finder = |key: &str| {
+        EdgeFinder::new()
+            .dir(EdgeDir::Emit)
+            .gate(registry.edge_gate(key, get_node_closure.clone()).unwrap())
+    };
+    assert_eq!(keyed_finder("is_node4"), keyed_finder("is_node4"));
+    assert_ne!(keyed_finder("is_node4"), keyed_finder("is_node6"));
+    assert!(format!("{:?}", keyed_finder("is_node4")).contains("is_node4"));
+    let spec = keyed_finder("is_node6").to_spec().unwrap();
+    assert_eq!(spec.gate, Some("is_node6".to_string()));
+    assert_eq!(
+        spec.bind(&registry, get_node_closure.clone()),
+        Ok(keyed_finder("is_node6"))
+    );
+    assert!(registry
+        .edge_gate("missing", get_node_closure.clone())
+        .is_err());
+
+    // (2)-extra->(4) and (2)-extra->(6)
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(2)
+        .add_edge_existing(EdgeDir::Emit, "extra".into(), 4, |blue_existing| {
+            blue_existing
+        })
+        .add_edge_existing(EdgeDir::Emit, "extra".into(), 6, |blue_existing| {
+            blue_existing
+        });
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    // Both finders are kept by the blueprint, so both edges are removed
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(2)
+        .remove_edge(to_node4.edge_type("extra".into()))
+        .remove_edge(to_node6.edge_type("extra".into()));
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    assert!(routable
+        .get_node(&2)
+        .unwrap()
+        .search_for_edge(&EdgeFinder::new().edge_type("extra".into()))
+        .is_none());
+}
+
+#[test]
+fn should_filter_edges_by_node_labels_and_data() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(6)
+        .add_label("branch".to_string());
+    blueprint
+        .start_with_update_node(3)
+        .add_label("fork".to_string());
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    let edge_targets = |node_id: Uid, edge_finder: EdgeFinder<String, String, String>| {
+        let mut targets = routable
+            .get_node(&node_id)
+            .unwrap()
+            .search_for_edge(&edge_finder.match_all())
+            .unwrap_or_default()
+            .iter()
+            .map(|edge| edge.target)
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets
+    };
+    let emit_finder = routable.edge_finder().dir(EdgeDir::Emit);
+
+    assert_eq!(
+        edge_targets(3, emit_finder.target_has_label("branch")),
+        vec![6]
+    );
+    assert_eq!(
+        edge_targets(3, emit_finder.target_lacks_label("branch")),
+        vec![4]
+    );
+    assert_eq!(
+        edge_targets(
+            3,
+            emit_finder.target_data("is_node4", |data: &String| data == "node4")
+        ),
+        vec![4]
+    );
+    assert!(edge_targets(2, emit_finder.host_has_label("fork")).is_empty());
+    assert_eq!(
+        edge_targets(2, routable.edge_finder().target_has_label("fork")),
+        vec![3]
+    );
+    // Without a graph to look the nodes up in, node conditions can't be checked
+    let node3 = routable.get_node(&3).unwrap();
+    for unbound in [
+        EdgeFinder::new().target_has_label("branch"),
+        EdgeFinder::new().target_has_label("branch").not(),
+        EdgeFinder::new()
+            .target(4)
+            .or(EdgeFinder::new().host_has_label("fork")),
+    ] {
+        assert_eq!(
+            node3.try_search_for_edge(&unbound),
+            Err(GraphError::Traversal(TraversalError::UnboundEdgeFinder))
+        );
+    }
+    assert_eq!(
+        node3
+            .try_search_for_edge(
+                &routable
+                    .edge_finder()
+                    .target_has_label("branch")
+                    .not()
+                    .dir(EdgeDir::Emit)
+                    .match_all()
+            )
+            .unwrap()
+            .map(|edges| edges.iter().map(|edge| edge.target).collect::<Vec<_>>()),
+        Some(vec![4])
+    );
+
+    // Finders given to a blueprint are bound to the graph when it is finalized
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(3).update_edge_payload(
+        EdgeFinder::new()
+            .target_has_label("branch")
+            .not()
+            .dir(EdgeDir::Emit)
+            .match_all(),
+        Some(()),
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    let payload_targets = routable
+        .get_node(&3)
+        .unwrap()
+        .search_for_edge(&EdgeFinder::new().dir(EdgeDir::Emit).match_all())
+        .unwrap()
+        .iter()
+        .filter(|edge| edge.payload.is_some())
+        .map(|edge| edge.target)
+        .collect::<Vec<_>>();
+    assert_eq!(payload_targets, vec![4]);
+
+    // Finders given to a traversal are bound to the graph automatically
+    let result = routable
+        .traverse_search(1)
+        .add_step(
+            EdgeFinder::new()
+                .dir(EdgeDir::Emit)
+                .target_lacks_label("fork")
+                .or(EdgeFinder::new().dir(EdgeDir::Emit).host_has_label("fork"))
+                .match_all(),
+            TraversalCount::AtLeastInclusive(1),
+        )
+        .execute()
+        .unwrap();
+    let mut endpoint_ids = result
+        .final_endpoints()
+        .iter()
+        .map(|endpoint| endpoint.node.id)
+        .collect::<Vec<_>>();
+    endpoint_ids.sort();
+    assert_eq!(endpoint_ids, vec![2]);
+
+    let registry = PredicateRegistry::new()
+        .register_data_predicate("is_node4", |data: &String| data == "node4");
+    let finder = EdgeFinder::new()
+        .host_has_label("fork")
+        .target_data("is_node4", |data: &String| data == "node4");
+    assert!(finder.to_spec().is_err());
+    let finder = finder.target_data_predicate(registry.get_data_predicate("is_node4").unwrap());
+    let spec = finder.to_spec().unwrap();
+    assert_eq!(spec.target_data, Some("is_node4".to_string()));
+    let bound = spec
+        .bind(&registry, routable.get_node_closure.clone())
+        .unwrap();
+    assert_eq!(bound, finder);
+    assert_eq!(edge_targets(3, bound), vec![4]);
+    assert_eq!(
+        finder.invert().host_data.map(|data| data.key),
+        Some("is_node4".to_string())
+    );
+}
+
+#[test]
+fn should_look_up_edges_by_target_and_render_info() {
+    let routable = set_up_basic_graph();
+    add_branch_to_graph(routable.clone());
+    let edge_targets = |edge_finder: EdgeFinder<String, String, String>| {
+        let mut targets = routable
+            .get_node(&3)
+            .unwrap()
+            .search_for_edge(&edge_finder)
+            .unwrap_or_default()
+            .iter()
+            .map(|edge| edge.target)
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets
+    };
+
+    assert_eq!(edge_targets(EdgeFinder::new().target(6)), vec![6]);
+    assert_eq!(
+        edge_targets(EdgeFinder::new().targets([2, 4, 6]).match_all()),
+        vec![2, 4, 6]
+    );
+    // Without match_all, incoming edges are preferred
+    assert_eq!(edge_targets(EdgeFinder::new().targets([2, 4, 6])), vec![2]);
+    assert!(edge_targets(
+        EdgeFinder::new()
+            .target(4)
+            .edge_type("different_edge_type".into())
+    )
+    .is_empty());
+    assert!(edge_targets(EdgeFinder::new().target(4).dir(EdgeDir::Recv)).is_empty());
+    assert_eq!(
+        edge_targets(
+            EdgeFinder::new()
+                .render_info(Some(EdgeDir::Emit))
+                .match_all()
+        ),
+        vec![4, 6]
+    );
+    assert_eq!(routable.get_node(&3).unwrap().get_render_edge().target, 2);
+
+    // The index follows edges being added and removed
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(3).add_edge_existing(
+        EdgeDir::Emit,
+        "reference".into(),
+        5,
+        |blue_existing| blue_existing,
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    assert_eq!(edge_targets(EdgeFinder::new().target(5)), vec![5]);
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(3)
+        .remove_edge(EdgeFinder::new().target(5));
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    assert!(edge_targets(EdgeFinder::new().target(5)).is_empty());
+    assert!(routable
+        .get_node(&5)
+        .unwrap()
+        .search_for_edge(&EdgeFinder::new().target(3))
+        .is_none());
+}
+
+// An edge type whose edges carry a weight
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Default)]
+struct WeightedEdge;
+
+impl EdgeTraits for WeightedEdge {
+    type Payload = u32;
+}
+
+#[test]
+fn should_store_update_and_undo_edge_payloads() {
+    use_routable_store::<String, WeightedEdge, String>(
+        None::<Vector<AllowedRenderEdgeSpecifier<WeightedEdge>>>,
+    );
+    let routable = use_routable::<String, WeightedEdge, String>();
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .add_edge_new_with_payload(EdgeDir::Emit, WeightedEdge, Some(5), |blue_new| {
+            blue_new.set_id(2)
+        });
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+    let payloads = || {
+        (
+            routable
+                .get_node(&1)
+                .unwrap()
+                .get_edge_payload(&EdgeFinder::new().target(2)),
+            routable
+                .get_node(&2)
+                .unwrap()
+                .get_edge_payload(&EdgeFinder::new().target(1)),
+        )
+    };
+    let heavy_edge_found = || {
+        routable
+            .get_node(&1)
+            .unwrap()
+            .search_for_edge(&EdgeFinder::new().payload("heavy", |weight| *weight > 10))
+            .is_some()
+    };
+
+    // Stored on both sides of the edge pair
+    assert_eq!(payloads(), (Some(5), Some(5)));
+    assert!(!heavy_edge_found());
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(1)
+        .update_edge_payload(EdgeFinder::new().target(2), Some(12));
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(payloads(), (Some(12), Some(12)));
+    assert!(heavy_edge_found());
+    // The edge keeps its render info
+    assert_eq!(
+        routable.get_node(&2).unwrap().get_render_edge().payload,
+        Some(12)
+    );
+
+    routable.history.undo("undo".to_string());
+    assert_eq!(payloads(), (Some(5), Some(5)));
+    assert!(!heavy_edge_found());
+
+    routable.history.redo("redo".to_string());
+    assert_eq!(payloads(), (Some(12), Some(12)));
+}
+
+#[test]
+fn should_merge_payload_updates_with_other_edge_changes() {
+    use_routable_store::<String, WeightedEdge, String>(
+        None::<Vector<AllowedRenderEdgeSpecifier<WeightedEdge>>>,
+    );
+    let routable = use_routable::<String, WeightedEdge, String>();
+    // (1)->(2), (1)->(3) and a reference edge (2)->(3) which isn't rendering anything
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .add_edge_new_with_payload(EdgeDir::Emit, WeightedEdge, Some(2), |blue_new| {
+            blue_new.set_id(2)
+        })
+        .add_edge_new_with_payload(EdgeDir::Emit, WeightedEdge, Some(3), |blue_new| {
+            blue_new.set_id(3)
+        });
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(2).add_edge_existing(
+        EdgeDir::Emit,
+        WeightedEdge,
+        3,
+        |blue_existing| blue_existing,
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    let payloads = |host: Uid, target: Uid| {
+        (
+            routable
+                .get_node(&host)
+                .unwrap()
+                .get_edge_payload(&EdgeFinder::new().target(target)),
+            routable
+                .get_node(&target)
+                .unwrap()
+                .get_edge_payload(&EdgeFinder::new().target(host)),
+        )
+    };
+
+    // The same edge updated from both sides
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(1)
+        .update_edge_payload(EdgeFinder::new().target(2), Some(7));
+    blueprint
+        .start_with_update_node(2)
+        .update_edge_payload(EdgeFinder::new().target(1), Some(7));
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(payloads(1, 2), (Some(7), Some(7)));
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(1)
+        .update_edge_payload(EdgeFinder::new().target(2), Some(8));
+    blueprint
+        .start_with_update_node(2)
+        .update_edge_payload(EdgeFinder::new().target(1), Some(9));
+    assert!(matches!(
+        routable.process_blueprint(blueprint, "action_data".to_string()),
+        Err(GraphError::Blueprint(_))
+    ));
+    assert_eq!(payloads(1, 2), (Some(7), Some(7)));
+
+    // A removed edge stays removed
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(2)
+        .update_edge_payload(EdgeFinder::new().dir(EdgeDir::Emit).target(3), Some(4))
+        .remove_edge(EdgeFinder::new().dir(EdgeDir::Emit).target(3));
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert!(routable
+        .get_node(&2)
+        .unwrap()
+        .search_for_edge(&EdgeFinder::new().dir(EdgeDir::Emit).target(3))
+        .is_none());
+
+    // A moved edge is replaced, and the replacement takes the payload
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(1)
+        .move_to(EdgeFinder::new().target(3), 0)
+        .update_edge_payload(EdgeFinder::new().target(3), Some(5));
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    let children = routable
+        .get_node(&1)
+        .unwrap()
+        .outgoing_edges
+        .get_untracked()
+        .get(&WeightedEdge)
+        .unwrap()
+        .iter()
+        .map(|edge| edge.target)
+        .collect::<Vec<_>>();
+    assert_eq!(children, vec![3, 2]);
+    assert_eq!(payloads(1, 3), (Some(5), Some(5)));
+    assert_eq!(
+        routable.get_node(&3).unwrap().get_render_edge().payload,
+        Some(5)
+    );
+
+    routable.history.undo("undo".to_string());
+    assert_eq!(payloads(1, 3), (Some(3), Some(3)));
+}
+
+#[test]
+fn should_keep_parallel_edges_apart_by_id() {
+    let routable = set_up_basic_graph();
+    let edge_ids = |host: Uid, target: Uid| {
+        routable
+            .get_node(&host)
+            .unwrap()
+            .search_for_edge(
+                &EdgeFinder::new()
+                    .target(target)
+                    .edge_type("reference".into())
+                    .match_all(),
+            )
+            .unwrap_or_default()
+            .iter()
+            .map(|edge| edge.id)
+            .collect::<im::HashSet<Uid>>()
+    };
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(3)
+        .add_edge_existing(EdgeDir::Emit, "reference".into(), 5, |blue_existing| {
+            blue_existing
+        })
+        .add_edge_existing(EdgeDir::Emit, "reference".into(), 5, |blue_existing| {
+            blue_existing
+        });
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    let parallel_edge_ids = edge_ids(3, 5);
+    assert_eq!(parallel_edge_ids.len(), 2);
+    // Both sides of each pair share the id
+    assert_eq!(edge_ids(5, 3), parallel_edge_ids);
+
+    let removed_id = *parallel_edge_ids.iter().next().unwrap();
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(3)
+        .remove_edge(EdgeFinder::new().edge_id(removed_id));
+    routable
+        .process_blueprint(blueprint, "action_data".into())
+        .unwrap();
+    let remaining_ids = parallel_edge_ids.without(&removed_id);
+    assert_eq!(edge_ids(3, 5), remaining_ids);
+    assert_eq!(edge_ids(5, 3), remaining_ids);
+
+    routable.history.undo("undo".into());
+    assert_eq!(edge_ids(3, 5), parallel_edge_ids);
+    assert_eq!(edge_ids(5, 3), parallel_edge_ids);
+}
+
+#[test]
+fn should_insert_and_move_ordered_children() {
+    let routable = set_up_basic_graph();
+    let children = || {
+        routable
+            .get_node(&3)
+            .unwrap()
+            .outgoing_edges
+            .get_untracked()
+            .get("edge_type")
+            .unwrap()
+            .iter()
+            .map(|edge| edge.target)
+            .collect::<Vec<_>>()
+    };
+    let process = |blueprint: BuildBlueprint<String, String, String>| {
+        routable
+            .process_blueprint(blueprint, "action_data".into())
+            .unwrap();
+    };
+
+    // Appended in the order they were added
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(3)
+        .add_edge_new(EdgeDir::Emit, "edge_type".into(), |blue_new| {
+            blue_new.set_id(10)
+        })
+        .add_edge_new(EdgeDir::Emit, "edge_type".into(), |blue_new| {
+            blue_new.set_id(11)
+        })
+        .add_edge_new(EdgeDir::Emit, "edge_type".into(), |blue_new| {
+            blue_new.set_id(12)
+        });
+    process(blueprint);
+    assert_eq!(children(), vec![4, 10, 11, 12]);
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(3)
+        .add_edge_new(EdgeDir::Emit, "edge_type".into(), |blue_new| {
+            blue_new.set_id(13)
+        })
+        .insert_before(EdgeFinder::new().target(13), EdgeFinder::new().target(11));
+    process(blueprint);
+    assert_eq!(children(), vec![4, 10, 13, 11, 12]);
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(3)
+        .move_to(EdgeFinder::new().target(12), 0)
+        .insert_after(EdgeFinder::new().target(4), EdgeFinder::new().target(11));
+    process(blueprint);
+    assert_eq!(children(), vec![12, 10, 13, 11, 4]);
+    assert_eq!(routable.get_node(&4).unwrap().get_render_edge().target, 3);
+    // Both sides of a moved edge keep the same key
+    let moved_edge = routable
+        .get_node(&3)
+        .unwrap()
+        .search_for_edge(&EdgeFinder::new().target(12))
+        .unwrap();
+    let other_side = routable
+        .get_node(&12)
+        .unwrap()
+        .search_for_edge(&EdgeFinder::new().target(3))
+        .unwrap();
+    assert_eq!(
+        moved_edge.iter().next().unwrap().order_key,
+        other_side.iter().next().unwrap().order_key
+    );
+
+    routable.history.undo("undo".into());
+    assert_eq!(children(), vec![4, 10, 13, 11, 12]);
+    routable.history.undo("undo".into());
+    assert_eq!(children(), vec![4, 10, 11, 12]);
+    routable.history.redo("redo".into());
+    assert_eq!(children(), vec![4, 10, 13, 11, 12]);
+}
+
+// Edge types which read differently, or the same, from either side
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Default)]
+enum FamilyEdge {
+    #[default]
+    ParentOf,
+    ChildOf,
+    Sibling,
+}
+
+impl EdgeTraits for FamilyEdge {
+    fn inverse(&self) -> Self {
+        match self {
+            FamilyEdge::ParentOf => FamilyEdge::ChildOf,
+            FamilyEdge::ChildOf => FamilyEdge::ParentOf,
+            FamilyEdge::Sibling => FamilyEdge::Sibling,
+        }
+    }
+
+    fn is_symmetric(&self) -> bool {
+        *self == FamilyEdge::Sibling
+    }
+}
+
+#[test]
+fn should_store_inverse_and_symmetric_edge_types() {
+    use_routable_store::<String, FamilyEdge, String>(
+        None::<Vector<AllowedRenderEdgeSpecifier<FamilyEdge>>>,
+    );
+    let routable = use_routable::<String, FamilyEdge, String>();
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .add_edge_new(EdgeDir::Emit, FamilyEdge::ParentOf, |blue_new| {
+            blue_new.set_id(2)
+        })
+        .add_edge_new(EdgeDir::Emit, FamilyEdge::ParentOf, |blue_new| {
+            blue_new.set_id(3)
+        });
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(2).add_edge_existing(
+        EdgeDir::Emit,
+        FamilyEdge::Sibling,
+        3,
+        |blue_existing| blue_existing,
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    let edge_targets = |id: Uid, edge_finder: EdgeFinder<String, FamilyEdge, String>| {
+        let mut targets = routable
+            .get_node(&id)
+            .unwrap()
+            .search_for_edge(&edge_finder.match_all())
+            .unwrap_or_default()
+            .iter()
+            .map(|edge| edge.target)
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets
+    };
+
+    // The child stores the inverse type
+    assert_eq!(
+        edge_targets(1, EdgeFinder::new().edge_type(FamilyEdge::ParentOf)),
+        vec![2, 3]
+    );
+    assert_eq!(
+        edge_targets(2, EdgeFinder::new().edge_type(FamilyEdge::ChildOf)),
+        vec![1]
+    );
+    assert!(edge_targets(2, EdgeFinder::new().edge_type(FamilyEdge::ParentOf)).is_empty());
+    assert!(routable
+        .get_node(&2)
+        .unwrap()
+        .incoming_edges
+        .get_untracked()
+        .contains_key(&FamilyEdge::ChildOf));
+
+    // Symmetric edges match in either direction, from either side
+    for dir in [EdgeDir::Emit, EdgeDir::Recv] {
+        let siblings = EdgeFinder::new().edge_type(FamilyEdge::Sibling).dir(dir);
+        assert_eq!(edge_targets(2, siblings.clone()), vec![3]);
+        assert_eq!(edge_targets(3, siblings), vec![2]);
+    }
+
+    // Inverting a finder inverts its edge types
+    let edge_finder = EdgeFinder::<String, FamilyEdge, String>::new()
+        .edge_type(FamilyEdge::ParentOf)
+        .dir(EdgeDir::Emit)
+        .host(1);
+    assert_eq!(
+        edge_finder.invert(),
+        EdgeFinder::new()
+            .edge_type(FamilyEdge::ChildOf)
+            .dir(EdgeDir::Recv)
+            .target(1)
+    );
+    assert_eq!(edge_targets(2, edge_finder.invert_drop_closure()), vec![1]);
+
+    // Adding the edge from the receiving side stores it the same way, with the emitting side's type
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(3).add_edge_existing(
+        EdgeDir::Recv,
+        FamilyEdge::ParentOf,
+        2,
+        |blue_existing| blue_existing,
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    let stored_types = |host: Uid, target: Uid| {
+        routable
+            .get_node(&host)
+            .unwrap()
+            .search_for_edge(&EdgeFinder::new().target(target).match_all())
+            .unwrap()
+            .iter()
+            .filter(|edge| !edge.edge_type.is_symmetric())
+            .map(|edge| (edge.edge_type.clone(), edge.dir.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        stored_types(2, 3),
+        vec![(FamilyEdge::ParentOf, EdgeDir::Emit)]
+    );
+    assert_eq!(
+        stored_types(3, 2),
+        vec![(FamilyEdge::ChildOf, EdgeDir::Recv)]
+    );
+    // The same as the edges added from the emitting side
+    assert_eq!(
+        stored_types(1, 2),
+        vec![(FamilyEdge::ParentOf, EdgeDir::Emit)]
+    );
+    assert_eq!(
+        stored_types(2, 1),
+        vec![(FamilyEdge::ChildOf, EdgeDir::Recv)]
+    );
+}
+
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Default)]
+enum FsKind {
+    #[default]
+    Folder,
+    File,
+}
+
+// Node data for a file system, where only folders may contain nodes and each node has one parent
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Default)]
+struct FsNode(FsKind);
+
+impl NodeKind for FsNode {
+    type Kind = FsKind;
+    fn kind(&self) -> FsKind {
+        self.0.clone()
+    }
+}
+
+#[test]
+fn should_reject_blueprints_breaking_the_schema() {
+    use_routable_store_with_schema::<FsNode, String, String>(
+        None::<Vector<AllowedRenderEdgeSpecifier<String>>>,
+        GraphSchema::new()
+            .emitter_kinds("contains".into(), [FsKind::Folder])
+            .recv_cardinality("contains".into(), Cardinality::at_most(1)),
+    );
+    let routable = use_routable::<FsNode, String, String>();
+
+    // (1 Folder)-contains->(2 File), (1 Folder)-contains->(3 Folder)
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .set_data(FsNode(FsKind::Folder))
+        .add_edge_new(EdgeDir::Emit, "contains".into(), |blue_new| {
+            blue_new.set_id(2).set_data(FsNode(FsKind::File))
+        })
+        .add_edge_new(EdgeDir::Emit, "contains".into(), |blue_new| {
+            blue_new.set_id(3).set_data(FsNode(FsKind::Folder))
+        });
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+    let parents = |id: Uid| {
+        routable
+            .get_node(&id)
+            .unwrap()
+            .search_for_edge(&EdgeFinder::new().dir(EdgeDir::Recv).match_all())
+            .unwrap_or_default()
+            .iter()
+            .map(|edge| edge.target)
+            .collect::<Vector<Uid>>()
+    };
+
+    // A file can't contain anything
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(2).add_edge_existing(
+        EdgeDir::Emit,
+        "contains".into(),
+        1,
+        |blue_existing| blue_existing,
+    );
+    assert_eq!(
+        routable.process_blueprint(blueprint, "action_data".to_string()),
+        Err(GraphError::Schema(vec![SchemaError::DisallowedKind {
+            node: 2,
+            kind: SchemaValue::new(FsKind::File),
+            edge_type: SchemaValue::new("contains".to_string()),
+            dir: EdgeDir::Emit,
+        }]))
+    );
+    assert_eq!(parents(1), Vector::new());
+
+    // A second parent breaks the cardinality
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(3).add_edge_existing(
+        EdgeDir::Emit,
+        "contains".into(),
+        2,
+        |blue_existing| blue_existing,
+    );
+    assert_eq!(
+        routable.process_blueprint(blueprint, "action_data".to_string()),
+        Err(GraphError::Schema(vec![SchemaError::Cardinality {
+            node: 2,
+            edge_type: SchemaValue::new("contains".to_string()),
+            dir: EdgeDir::Recv,
+            count: 2,
+        }]))
+    );
+    assert_eq!(parents(2), Vector::from(vec![1]));
+
+    // Every violation is reported, not just the first
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(2).add_edge_existing(
+        EdgeDir::Emit,
+        "contains".into(),
+        3,
+        |blue_existing| blue_existing,
+    );
+    let Err(GraphError::Schema(errors)) =
+        routable.process_blueprint(blueprint, "action_data".to_string())
+    else {
+        panic!("expected a schema error");
+    };
+    assert_eq!(errors.len(), 2);
+    assert!(errors.contains(&SchemaError::DisallowedKind {
+        node: 2,
+        kind: SchemaValue::new(FsKind::File),
+        edge_type: SchemaValue::new("contains".to_string()),
+        dir: EdgeDir::Emit,
+    }));
+    assert!(errors.contains(&SchemaError::Cardinality {
+        node: 3,
+        edge_type: SchemaValue::new("contains".to_string()),
+        dir: EdgeDir::Recv,
+        count: 2,
+    }));
+
+    // Moving the file keeps it at one parent
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(3).add_edge_existing(
+        EdgeDir::Emit,
+        "contains".into(),
+        2,
+        |blue_existing| blue_existing.remove_edge(EdgeFinder::new().target(1)),
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(parents(2), Vector::from(vec![3]));
+}
+
+#[test]
+fn should_reject_cycles_of_acyclic_edge_types() {
+    use_routable_store_with_schema::<String, String, String>(
+        None::<Vector<AllowedRenderEdgeSpecifier<String>>>,
+        GraphSchema::new().acyclic("depends_on".into()),
+    );
+    let routable = use_routable::<String, String, String>();
+
+    // (1)-depends_on->(2)-depends_on->(3)
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .add_edge_new(EdgeDir::Emit, "depends_on".into(), |blue_new| {
+            blue_new
+                .set_id(2)
+                .add_edge_new(EdgeDir::Emit, "depends_on".into(), |blue_new| {
+                    blue_new.set_id(3)
+                })
+        });
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+
+    let add_edge = |host: Uid, edge_type: &str, target: Uid| {
+        let blueprint = BuildBlueprint::new();
+        blueprint.start_with_update_node(host).add_edge_existing(
+            EdgeDir::Emit,
+            edge_type.into(),
+            target,
+            |blue_existing| blue_existing,
+        );
+        routable.process_blueprint(blueprint, "action_data".to_string())
+    };
+
+    assert_eq!(
+        add_edge(3, "depends_on", 1),
+        Err(GraphError::Schema(vec![SchemaError::Cycle {
+            edge_type: SchemaValue::new("depends_on".to_string()),
+            path: vec![3, 1, 2, 3],
+        }]))
+    );
+    assert!(routable
+        .get_node(&3)
+        .unwrap()
+        .search_for_edge(&EdgeFinder::new().target(1))
+        .is_none());
+
+    // A shortcut isn't a cycle, and other edge types may loop back
+    assert!(add_edge(1, "depends_on", 3).is_ok());
+    assert!(add_edge(3, "mentions", 1).is_ok());
+}
+
+#[test]
+fn should_cascade_and_restrict_deletes_by_edge_type() {
+    use_routable_store_with_schema::<String, String, String>(
+        None::<Vector<AllowedRenderEdgeSpecifier<String>>>,
+        GraphSchema::new()
+            .on_delete("has_comment".into(), EdgeDir::Emit, OnDelete::Cascade)
+            .on_delete("shows".into(), EdgeDir::Emit, OnDelete::Restrict),
+    );
+    let routable = use_routable::<String, String, String>();
+
+    // (1)->(2 Document), (1)->(3 Feed)-shows->(4 Comment)
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .add_edge_new(EdgeDir::Emit, "contains".into(), |blue_new| {
+            blue_new.set_id(2)
+        })
+        .add_edge_new(EdgeDir::Emit, "contains".into(), |blue_new| {
+            blue_new
+                .set_id(3)
+                .add_edge_new(EdgeDir::Emit, "shows".into(), |blue_new| blue_new.set_id(4))
+        });
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+    // The comment belongs to the document, though it's rendered by the feed
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(2).add_edge_existing(
+        EdgeDir::Emit,
+        "has_comment".into(),
+        4,
+        |blue_existing| blue_existing,
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    let delete_node = |id: Uid| {
+        let blueprint = BuildBlueprint::new();
+        blueprint.delete_node(id);
+        routable.process_blueprint(blueprint, "action_data".to_string())
+    };
+    let exists = |id: Uid| routable.get_node(&id).is_ok();
+
+    // The feed can't be deleted while it shows the comment
+    assert_eq!(
+        delete_node(3),
+        Err(GraphError::Schema(vec![SchemaError::DeleteRestricted {
+            node: 3,
+            edge_type: SchemaValue::new("shows".to_string()),
+            target: 4,
+        }]))
+    );
+    assert!(exists(3));
+
+    // Also reported once when the feed renders a node which would be left without a render edge
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(3).add_edge_new(
+        EdgeDir::Emit,
+        "contains".into(),
+        |blue_new| blue_new.set_id(5),
+    );
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(
+        delete_node(3),
+        Err(GraphError::Schema(vec![SchemaError::DeleteRestricted {
+            node: 3,
+            edge_type: SchemaValue::new("shows".to_string()),
+            target: 4,
+        }]))
+    );
+    assert!(exists(3) && exists(5));
+
+    // Deleting the document deletes its comment too
+    delete_node(2).unwrap();
+    assert!(!exists(2) && !exists(4));
+    assert!(routable
+        .get_node(&3)
+        .unwrap()
+        .search_for_edge(&EdgeFinder::new().target(4))
+        .is_none());
+
+    routable.history.undo("undo".to_string());
+    assert!(exists(2) && exists(4));
+    assert!(routable
+        .get_node(&3)
+        .unwrap()
+        .search_for_edge(&EdgeFinder::new().target(4))
+        .is_some());
+
+    routable.history.redo("redo".to_string());
+    assert!(!exists(2) && !exists(4));
+
+    // With the comment gone the feed can be deleted
+    delete_node(3).unwrap();
+    assert!(!exists(3) && !exists(5));
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash, Default)]
+enum Status {
+    #[default]
+    Draft,
+    Published,
+}
+
+#[test]
+fn should_use_typed_labels() {
+    let routable =
+        use_routable_store_with_settings::<String, String, String, Status>(GraphSettings::new());
+
+    // (1 Published)->(2 Draft)->(3 Published)
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .add_label(Status::Published)
+        .add_edge_new(EdgeDir::Emit, "edge_type".into(), |blue_new| {
+            blue_new.set_id(2).add_label(Status::Draft).add_edge_new(
+                EdgeDir::Emit,
+                "edge_type".into(),
+                |blue_new| blue_new.set_id(3).add_label(Status::Published),
+            )
+        });
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+    let sorted_ids = |ids: Vector<Uid>| {
+        let mut ids = ids.into_iter().collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(
+        sorted_ids(routable.get_node_ids_with_label(Status::Published)),
+        vec![1, 3]
+    );
+    assert!(routable
+        .get_node(&1)
+        .unwrap()
+        .search_for_edge(&routable.edge_finder().target_has_label(Status::Draft))
+        .is_some());
+    let traversal = routable
+        .traverse_search_from(TraversalOrigin::label(Status::Published))
+        .add_filtered_step(
+            EdgeFinder::new().dir(EdgeDir::Emit).match_all(),
+            TraversalCount::Exactly(1),
+            NodeFilter::has_label(Status::Draft),
+        );
+    assert_eq!(
+        sorted_endpoint_ids(&traversal.execute().unwrap().step_results[0]),
+        vec![2]
+    );
+
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(2)
+        .remove_label(Status::Draft)
+        .add_label(Status::Published);
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(
+        sorted_ids(routable.get_node_ids_with_label(Status::Published)),
+        vec![1, 2, 3]
+    );
+    assert!(routable.get_node_ids_with_label(Status::Draft).is_empty());
+}
+
+#[test]
+fn should_take_ids_from_the_store_id_generator() {
+    use_routable_store_with_settings::<String, String, String, String>(
+        GraphSettings::new().id_generator(IdGenerator::sequential(100)),
+    );
+    let routable = use_routable::<String, String, String>();
+
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_new_node().set_temp_id(1).add_edge_new(
+        EdgeDir::Emit,
+        "edge_type".into(),
+        |blue_new| blue_new.set_temp_id(2),
+    );
+    let temp_id_map = routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+    // The first node, then the node and edge it adds
+    assert_eq!(temp_id_map.get(&1), Some(&100));
+    assert_eq!(temp_id_map.get(&2), Some(&101));
+    let edge_ids = routable
+        .get_node(&100)
+        .unwrap()
+        .convert_all_edges_to_hashset()
+        .iter()
+        .map(|edge| edge.id)
+        .collect::<Vec<_>>();
+    assert_eq!(edge_ids, vec![102]);
+
+    // The sequence carries on across blueprints
+    let blueprint = BuildBlueprint::new();
+    blueprint.start_with_update_node(101).add_edge_new(
+        EdgeDir::Emit,
+        "edge_type".into(),
+        |blue_new| blue_new.set_temp_id(3),
+    );
+    let temp_id_map = routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(temp_id_map.get(&3), Some(&103));
+
+    // Ids replaced with set_id don't use up the store's ids
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(101)
+        .add_edge_new(EdgeDir::Emit, "edge_type".into(), |blue_new| {
+            blue_new.set_id(50)
+        })
+        .add_edge_new(EdgeDir::Emit, "edge_type".into(), |blue_new| {
+            blue_new.set_temp_id(4)
+        });
+    let temp_id_map = routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(temp_id_map.get(&4), Some(&106));
+
+    // A blueprint with its own generator keeps its ids
+    let blueprint = BuildBlueprint::with_id_generator(IdGenerator::sequential(1_000));
+    blueprint.start_with_update_node(101).add_edge_new(
+        EdgeDir::Emit,
+        "edge_type".into(),
+        |blue_new| blue_new.set_temp_id(5),
+    );
+    let temp_id_map = routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(temp_id_map.get(&5), Some(&1_000));
+}
+
+#[test]
+fn should_keep_label_queries_in_sync_with_the_graph() {
+    let routable = setup_context();
+
+    // (1 task, urgent)->(2 task)->(3 note)
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_new_node()
+        .set_id(1)
+        .set_temp_id(1)
+        .add_label("task".into())
+        .add_label("urgent".into())
+        .add_edge_new(EdgeDir::Emit, "edge_type".into(), |blue_new| {
+            blue_new.set_id(2).add_label("task".into()).add_edge_new(
+                EdgeDir::Emit,
+                "edge_type".into(),
+                |blue_new| blue_new.set_id(3).add_label("note".into()),
+            )
+        });
+    routable
+        .initiate_graph(blueprint, "action_data".to_string(), 1)
+        .unwrap();
+
+    let tasks = routable.nodes_with_label("task");
+    let calm_tasks = routable.nodes_with_labels(
+        LabelQuery::new()
+            .any_of(["task", "note"])
+            .none_of(["urgent"]),
+    );
+    let ids = |nodes: Vector<Rc<ReadReactiveNode<String, String, String>>>| {
+        nodes.iter().map(|node| node.id).collect::<Vec<_>>()
+    };
+    // Counts how often the memo notifies
+    let task_runs = Rc::new(Cell::new(0));
+    create_effect({
+        let task_runs = task_runs.clone();
+        move |_| {
+            tasks.get();
+            task_runs.set(task_runs.get() + 1);
+        }
+    });
+    assert_eq!(ids(tasks.get()), vec![1, 2]);
+    assert_eq!(ids(calm_tasks.get()), vec![2, 3]);
+    assert_eq!(task_runs.get(), 1);
+
+    // Labels changed by an update reach the index
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(3)
+        .remove_label("note".into())
+        .add_label("task".into());
+    blueprint
+        .start_with_update_node(1)
+        .remove_label("urgent".into());
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(ids(tasks.get()), vec![1, 2, 3]);
+    assert_eq!(ids(calm_tasks.get()), vec![1, 2, 3]);
+    assert_eq!(task_runs.get(), 2);
+    assert_eq!(routable.get_node_ids_with_label("note"), Vector::new());
+
+    // Relabelling a node without changing which nodes match doesn't notify
+    let blueprint = BuildBlueprint::new();
+    blueprint
+        .start_with_update_node(2)
+        .add_label("urgent".into());
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(ids(calm_tasks.get()), vec![1, 3]);
+    assert_eq!(task_runs.get(), 2);
+
+    let blueprint = BuildBlueprint::new();
+    blueprint.delete_node(3);
+    routable
+        .process_blueprint(blueprint, "action_data".to_string())
+        .unwrap();
+    assert_eq!(ids(tasks.get()), vec![1, 2]);
+    assert_eq!(task_runs.get(), 3);
+
+    // Undoing restores the node and its labels
+    routable.history.undo("undo".to_string());
+    assert_eq!(ids(tasks.get()), vec![1, 2, 3]);
+    assert_eq!(
+        routable
+            .label_index
+            .query_untracked(&LabelQuery::new().all_of(["task", "urgent"])),
+        Vector::from(vec![2])
+    );
+    assert_eq!(task_runs.get(), 4);
+    assert_eq!(
+        routable.label_index.label_map_untracked().get("task"),
+        Some(&Vector::from(vec![1, 2, 3]))
+    );
+}
@@ -2,29 +2,52 @@ use std::{fmt::Formatter, rc::Rc};
 
 use crate::{
     history::history_interface::HistoryInterface,
-    prelude::{new_node::TempId, reactive_node::read_reactive_node::ReadReactiveNode, *},
-    traversal::traversal_descriptor::TraversalDescriptor,
+    prelude::{
+        label_index::{LabelIndex, LabelQuery},
+        new_node::TempId,
+        reactive_node::read_reactive_node::ReadReactiveNode,
+        *,
+    },
+    traversal::{
+        graph_pattern::GraphPattern, path_search::PathSearch,
+        traversal_descriptor::TraversalDescriptor, traversal_origin::TraversalOrigin,
+    },
 };
 
-use im::HashMap;
+use im::{HashMap, Vector};
 use leptos::*;
 
-pub type GetNodeClosure<T, E, A> =
-    Rc<dyn Fn(&Uid) -> Result<Rc<ReadReactiveNode<T, E, A>>, GraphError>>;
+pub type GetNodeClosure<T, E, A, L = String> =
+    Rc<dyn Fn(&Uid) -> Result<Rc<ReadReactiveNode<T, E, A, L>>, GraphError>>;
+
+// Returns the ids of every node with the given label
+pub type GetLabelClosure<L = String> = Rc<dyn Fn(&L) -> Vector<Uid>>;
+
+// Tracks the nodes matching a label query
+pub type NodesWithLabelMemo<T, E, A, L = String> = Memo<Vector<Rc<ReadReactiveNode<T, E, A, L>>>>;
+
+// Returns the ids of every node in the graph
+pub type GetNodeIdsClosure = Rc<dyn Fn() -> Vector<Uid>>;
 
 pub type ProcessBlueprintReturn = Result<HashMap<TempId, Uid>, GraphError>;
+pub type ProcessBlueprintClosure<T, E, A, L = String> =
+    Rc<dyn Fn(BuildBlueprint<T, E, A, L>, A) -> ProcessBlueprintReturn>;
+pub type InitiateGraphClosure<T, E, A, L = String> =
+    Rc<dyn Fn(BuildBlueprint<T, E, A, L>, A, TempId) -> ProcessBlueprintReturn>;
 #[derive(Clone)]
-pub struct UseRoutableReturn<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub get_node_closure: GetNodeClosure<T, E, A>,
-    pub(super) process_blueprint_closure:
-        Rc<dyn Fn(BuildBlueprint<T, E, A>, A) -> ProcessBlueprintReturn>,
-    pub(super) initiate_graph_closure:
-        Rc<dyn Fn(BuildBlueprint<T, E, A>, A, TempId) -> ProcessBlueprintReturn>,
+pub struct UseRoutableReturn<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String>
+{
+    pub get_node_closure: GetNodeClosure<T, E, A, L>,
+    pub get_label_closure: GetLabelClosure<L>,
+    pub get_node_ids_closure: GetNodeIdsClosure,
+    pub(super) process_blueprint_closure: ProcessBlueprintClosure<T, E, A, L>,
+    pub(super) initiate_graph_closure: InitiateGraphClosure<T, E, A, L>,
     pub graph_lock: Rc<GraphLock>,
-    pub history: Rc<HistoryInterface<T, E, A>>,
+    pub history: Rc<HistoryInterface<T, E, A, L>>,
+    pub label_index: LabelIndex<L>,
 }
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> core::fmt::Debug
-    for UseRoutableReturn<T, E, A>
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> core::fmt::Debug
+    for UseRoutableReturn<T, E, A, L>
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("UseRoutableReturn")
@@ -32,30 +55,76 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> core::fmt::Debug
             .finish()
     }
 }
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> UseRoutableReturn<T, E, A> {
-    pub fn get_node(&self, id: &Uid) -> Result<Rc<ReadReactiveNode<T, E, A>>, GraphError> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> UseRoutableReturn<T, E, A, L> {
+    pub fn get_node(&self, id: &Uid) -> Result<Rc<ReadReactiveNode<T, E, A, L>>, GraphError> {
         (self.get_node_closure)(id)
     }
+    pub fn get_node_ids(&self) -> Vector<Uid> {
+        (self.get_node_ids_closure)()
+    }
+    pub fn get_node_ids_with_label(&self, label: impl Into<L>) -> Vector<Uid> {
+        (self.get_label_closure)(&label.into())
+    }
+    // Tracks the nodes with the label, sorted by id
+    pub fn nodes_with_label(&self, label: impl Into<L>) -> NodesWithLabelMemo<T, E, A, L> {
+        self.nodes_with_labels(LabelQuery::new().all_of([label]))
+    }
+    // Tracks the nodes matching the query, sorted by id
+    pub fn nodes_with_labels(&self, query: LabelQuery<L>) -> NodesWithLabelMemo<T, E, A, L> {
+        let ids = self.label_index.query(query);
+        let get_node = self.get_node_closure.clone();
+        create_memo(move |_| {
+            ids.get()
+                .iter()
+                .filter_map(|id| get_node(id).ok())
+                .collect()
+        })
+    }
     pub fn process_blueprint(
         &self,
-        blueprint: BuildBlueprint<T, E, A>,
+        blueprint: BuildBlueprint<T, E, A, L>,
         action_data: A,
     ) -> ProcessBlueprintReturn {
         (self.process_blueprint_closure)(blueprint, action_data)
     }
     pub fn initiate_graph(
         &self,
-        blueprint: BuildBlueprint<T, E, A>,
+        blueprint: BuildBlueprint<T, E, A, L>,
         action_data: A,
         entry_point_temp_id: TempId,
     ) -> ProcessBlueprintReturn {
         (self.initiate_graph_closure)(blueprint, action_data, entry_point_temp_id)
     }
-    pub fn traverse_search(&self, start_id: Uid) -> TraversalDescriptor<T, E, A> {
-        TraversalDescriptor::new(start_id, self.get_node_closure.clone())
+    pub fn traverse_search(&self, start_id: Uid) -> TraversalDescriptor<T, E, A, L> {
+        self.traverse_search_from(start_id)
+    }
+    pub fn traverse_search_from(
+        &self,
+        origin: impl Into<TraversalOrigin<L>>,
+    ) -> TraversalDescriptor<T, E, A, L> {
+        TraversalDescriptor::new(origin, self.get_node_closure.clone())
+            .with_labels(self.get_label_closure.clone())
+    }
+    pub fn path_search(
+        &self,
+        start_id: Uid,
+        end_id: Uid,
+        edge_finder: EdgeFinder<T, E, A, L>,
+    ) -> PathSearch<T, E, A, L> {
+        PathSearch::new(start_id, end_id, edge_finder, self.get_node_closure.clone())
+    }
+    // A finder which can check the labels and data of the nodes on either end of an edge
+    pub fn edge_finder(&self) -> EdgeFinder<T, E, A, L> {
+        EdgeFinder::new().bind_graph(&self.get_node_closure)
+    }
+    pub fn pattern_search(&self) -> GraphPattern<T, E, A, L> {
+        GraphPattern::new(
+            self.get_node_closure.clone(),
+            self.get_node_ids_closure.clone(),
+        )
     }
 }
-pub fn use_routable<T: GraphTraits, E: GraphTraits, A: GraphTraits>(// id: Uid,
+pub fn use_routable<T: GraphTraits, E: EdgeTraits, A: GraphTraits>(// id: Uid,
 ) -> Rc<UseRoutableReturn<T, E, A>> {
     use_context::<Rc<UseRoutableReturn<T, E, A>>>().expect("Context should exist")
 }
@@ -1,4 +1,4 @@
-use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
 use im::Vector;
 use leptos::*;
@@ -10,8 +10,11 @@ use crate::{
         history_store::{reactive_node::last_action::ActionData, FinalizedBlueprint, HistoryStore},
     },
     prelude::{
-        new_node::TempId, view_graph::ViewGraph, AllowedRenderEdgeSpecifier, BuildBlueprint,
-        GraphError, GraphTraits, Uid,
+        graph_schema::{GraphSchema, NodeKind, SchemaHandle},
+        new_node::TempId,
+        view_graph::ViewGraph,
+        AllowedRenderEdgeSpecifier, BuildBlueprint, EdgeTraits, GraphError, GraphTraits,
+        IdGenerator, PredicateRegistry, Uid,
     },
 };
 
@@ -33,17 +36,115 @@ impl GraphLock {
     }
 }
 
-pub struct GraphSettings<E: GraphTraits, A: GraphTraits> {
-    pub render_edge_types: Option<Vector<AllowedRenderEdgeSpecifier<E>>>,
-    pub action_types: PhantomData<A>,
+#[derive(Clone)]
+pub struct GraphSettings<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub render_edge_types: Option<Vector<AllowedRenderEdgeSpecifier<E, L>>>,
+    // Gives ids to the new nodes and edges of blueprints built without their own generator
+    pub id_generator: IdGenerator,
+    pub schema: Option<SchemaHandle<T, E, A, L>>,
+    // Resolves the predicate keys of render edge finders
+    pub predicate_registry: PredicateRegistry<T, E, A, L>,
 }
 
-pub fn use_routable_store<T: GraphTraits, E: GraphTraits, A: GraphTraits>(
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Default
+    for GraphSettings<T, E, A, L>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> GraphSettings<T, E, A, L> {
+    pub fn new() -> Self {
+        Self {
+            render_edge_types: None,
+            id_generator: IdGenerator::default(),
+            schema: None,
+            predicate_registry: PredicateRegistry::new(),
+        }
+    }
+    pub fn render_edge_types(
+        &self,
+        render_edge_types: impl IntoIterator<Item = AllowedRenderEdgeSpecifier<E, L>>,
+    ) -> Self {
+        Self {
+            render_edge_types: Some(render_edge_types.into_iter().collect()),
+            ..self.clone()
+        }
+    }
+    pub fn id_generator(&self, id_generator: IdGenerator) -> Self {
+        Self {
+            id_generator,
+            ..self.clone()
+        }
+    }
+    pub fn predicate_registry(&self, predicate_registry: PredicateRegistry<T, E, A, L>) -> Self {
+        Self {
+            predicate_registry,
+            ..self.clone()
+        }
+    }
+}
+
+impl<T: NodeKind, E: EdgeTraits, A: GraphTraits, L: GraphTraits> GraphSettings<T, E, A, L> {
+    // Blueprints which would leave the graph breaking the schema are rejected with GraphError::Schema
+    pub fn schema(&self, schema: GraphSchema<T, E>) -> Self {
+        Self {
+            schema: Some(SchemaHandle(Rc::new(schema))),
+            ..self.clone()
+        }
+    }
+}
+
+fn settings_with_render_edge_types<
+    T: GraphTraits,
+    E: EdgeTraits,
+    A: GraphTraits,
+    L: GraphTraits,
+>(
+    render_edge_types: Option<impl IntoIterator<Item = AllowedRenderEdgeSpecifier<E, L>>>,
+) -> GraphSettings<T, E, A, L> {
+    GraphSettings {
+        render_edge_types: render_edge_types.map(|i| i.into_iter().collect()),
+        ..GraphSettings::new()
+    }
+}
+
+pub fn use_routable_store<T: GraphTraits, E: EdgeTraits, A: GraphTraits>(
+    render_edge_types: Option<impl IntoIterator<Item = AllowedRenderEdgeSpecifier<E>>>,
+) {
+    use_routable_store_with_settings::<T, E, A, String>(settings_with_render_edge_types(
+        render_edge_types,
+    ));
+}
+
+pub fn use_routable_store_with_schema<T: NodeKind, E: EdgeTraits, A: GraphTraits>(
     render_edge_types: Option<impl IntoIterator<Item = AllowedRenderEdgeSpecifier<E>>>,
+    schema: GraphSchema<T, E>,
 ) {
-    let view_graph = Rc::new(RefCell::new(ViewGraph::<T, E, A>::new()));
+    use_routable_store_with_settings::<T, E, A, String>(
+        settings_with_render_edge_types(render_edge_types).schema(schema),
+    );
+}
+
+// Returns the routable it provides. With String labels it can also be read back with use_routable.
+pub fn use_routable_store_with_settings<
+    T: GraphTraits,
+    E: EdgeTraits,
+    A: GraphTraits,
+    L: GraphTraits,
+>(
+    settings: GraphSettings<T, E, A, L>,
+) -> Rc<UseRoutableReturn<T, E, A, L>> {
+    let view_graph = Rc::new(RefCell::new(ViewGraph::<T, E, A, L> {
+        schema: settings.schema.clone(),
+        predicate_registry: settings.predicate_registry.clone(),
+        id_generator: settings.id_generator.clone(),
+        ..ViewGraph::new()
+    }));
     let view_graph_clone = view_graph.clone();
     let view_graph_clone2 = view_graph.clone();
+    let view_graph_clone4 = view_graph.clone();
     let (is_locked, set_is_locked) = create_signal(false);
     let graph_lock = Rc::new(GraphLock {
         is_locked,
@@ -51,67 +152,72 @@ pub fn use_routable_store<T: GraphTraits, E: GraphTraits, A: GraphTraits>(
     });
     let graph_lock_clone = graph_lock.clone();
 
-    let graph_settings = Rc::new(GraphSettings {
-        render_edge_types: render_edge_types.map(|i| i.into_iter().collect()),
-        action_types: PhantomData::<A>,
-    });
+    let graph_settings = Rc::new(settings);
 
     let apply_finalized_blueprint = Rc::new(
-        move |finalized_blueprint: FinalizedBlueprint<T, E>,
+        move |finalized_blueprint: FinalizedBlueprint<T, E, L>,
               primary_action_data: A,
               secondary_action_data: Option<Rc<A>>|
               -> Result<(), GraphError> {
             // log_finalize_results(&finalized_blueprint);
-            graph_lock_clone.lock();
-            let mut action_data = ActionData::<A>::new(primary_action_data);
-            if let Some(secondary_action_data) = secondary_action_data {
-                action_data = action_data.set_secondary_action(secondary_action_data);
-            }
-            {
-                let mut graph = view_graph_clone.borrow_mut();
-
-                graph.add_nodes(finalized_blueprint.new_nodes, action_data.clone());
-                graph.delete_nodes(finalized_blueprint.delete_nodes)?;
-            }
-
-            view_graph_clone
-                .borrow()
-                .update_nodes(finalized_blueprint.update_nodes, action_data)?;
-            graph_lock_clone.unlock();
-            Ok(())
+            // Effects wait until the whole blueprint is applied, so they never see a half updated graph
+            // or find it still borrowed
+            batch(|| {
+                graph_lock_clone.lock();
+                let mut action_data = ActionData::<A>::new(primary_action_data);
+                if let Some(secondary_action_data) = secondary_action_data {
+                    action_data = action_data.set_secondary_action(secondary_action_data);
+                }
+                {
+                    let mut graph = view_graph_clone.borrow_mut();
+
+                    graph.add_nodes(finalized_blueprint.new_nodes, action_data.clone());
+                    graph.delete_nodes(finalized_blueprint.delete_nodes)?;
+                }
+
+                view_graph_clone
+                    .borrow()
+                    .update_nodes(finalized_blueprint.update_nodes, action_data)?;
+                graph_lock_clone.unlock();
+                Ok(())
+            })
         },
     );
     let apply_finalized_blueprint_clone = apply_finalized_blueprint.clone();
 
-    let history_interface = Rc::new(HistoryInterface::<T, E, A>::new(
-        HistoryStore::<T, E, A>::new(),
+    let history_interface = Rc::new(HistoryInterface::<T, E, A, L>::new(
+        HistoryStore::<T, E, A, L>::new(),
         apply_finalized_blueprint_clone,
     ));
     let history_interface_clone1 = history_interface.clone();
 
     let underlying_process_blueprint = Rc::new(
-        move |blueprint: BuildBlueprint<T, E, A>,
+        move |blueprint: BuildBlueprint<T, E, A, L>,
               action_data: A,
               entry_point_temp_id: Option<TempId>|
-              -> Result<(), GraphError> {
+              -> ProcessBlueprintReturn {
+            // Ids are assigned before finalizing, so the temp id map holds the graph's ids
+            blueprint.assign_ids(&view_graph_clone2.borrow().id_generator);
+            let temp_id_map = blueprint.temp_id_map.borrow().clone();
             let finalized_blueprint = blueprint
                 .finalize(
                     &view_graph_clone2.clone().borrow(),
                     graph_settings.render_edge_types.clone(),
                     entry_point_temp_id,
                 )
-                .unwrap();
+                .map_err(GraphError::from_errors)?;
             history_interface_clone1.push(history::history_item::HistoryItem {
                 blueprint: finalized_blueprint.clone(),
                 action_data: Rc::new(action_data.clone()),
             });
-            apply_finalized_blueprint(finalized_blueprint, action_data, None)
+            apply_finalized_blueprint(finalized_blueprint, action_data, None)?;
+            Ok(temp_id_map)
         },
     );
     let underlying_process_blueprint_clone = underlying_process_blueprint.clone();
 
     let initiate_graph = Rc::new(
-        move |blueprint: BuildBlueprint<T, E, A>,
+        move |blueprint: BuildBlueprint<T, E, A, L>,
               action_data: A,
               entry_point_temp_id: TempId|
               -> ProcessBlueprintReturn {
@@ -120,8 +226,11 @@ pub fn use_routable_store<T: GraphTraits, E: GraphTraits, A: GraphTraits>(
             //     .borrow()
             //     .get(&entry_point_temp_id)
             //     .copied();
-            let temp_id_map = blueprint.temp_id_map.borrow().clone();
-            underlying_process_blueprint_clone(blueprint, action_data, Some(entry_point_temp_id))?;
+            let temp_id_map = underlying_process_blueprint_clone(
+                blueprint,
+                action_data,
+                Some(entry_point_temp_id),
+            )?;
             // if let Some(final_id) = final_id {
             //     Ok(final_id)
             // } else {
@@ -134,13 +243,12 @@ pub fn use_routable_store<T: GraphTraits, E: GraphTraits, A: GraphTraits>(
         },
     );
     let process_blueprint = Rc::new(
-        move |blueprint: BuildBlueprint<T, E, A>, action_data: A| -> ProcessBlueprintReturn {
-            let temp_id_map = blueprint.temp_id_map.borrow().clone();
-            underlying_process_blueprint(blueprint, action_data, None)?;
-            Ok(temp_id_map)
+        move |blueprint: BuildBlueprint<T, E, A, L>, action_data: A| -> ProcessBlueprintReturn {
+            underlying_process_blueprint(blueprint, action_data, None)
         },
     );
 
+    let label_index = view_graph.borrow().label_index;
     let get_node = Rc::new(move |id: &Uid| {
         let graph = view_graph.borrow();
         let node = graph
@@ -154,11 +262,20 @@ pub fn use_routable_store<T: GraphTraits, E: GraphTraits, A: GraphTraits>(
         Ok(node)
     });
 
-    provide_context(Rc::new(UseRoutableReturn {
+    let get_label = Rc::new(move |label: &L| label_index.ids_with_label_untracked(label));
+
+    let get_node_ids = Rc::new(move || view_graph_clone4.borrow().nodes.keys().copied().collect());
+
+    let routable = Rc::new(UseRoutableReturn {
         get_node_closure: get_node,
+        get_label_closure: get_label,
+        get_node_ids_closure: get_node_ids,
         process_blueprint_closure: process_blueprint,
         initiate_graph_closure: initiate_graph,
         graph_lock,
         history: history_interface,
-    }));
+        label_index,
+    });
+    provide_context(routable.clone());
+    routable
 }
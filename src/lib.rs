@@ -1,4 +1,5 @@
 #![feature(trait_alias)]
+#![feature(associated_type_defaults)]
 
 mod blueprint;
 mod common;
@@ -3,4 +3,19 @@ pub use crate::common::*;
 pub use crate::graph::*;
 pub use crate::hooks::*;
 
-pub use crate::traversal::traversal_step::TraversalCount;
+pub use crate::traversal::graph_pattern::{GraphPattern, PatternEdge, PatternNode};
+pub use crate::traversal::path_search::PathSearch;
+pub use crate::traversal::pattern_match::PatternMatch;
+pub use crate::traversal::traversal_aggregate::AggregateScope;
+pub use crate::traversal::traversal_binding::{TraversalBinding, TraversalBindingRow};
+pub use crate::traversal::traversal_descriptor::TraversalDescriptor;
+pub use crate::traversal::traversal_limits::TraversalLimits;
+pub use crate::traversal::traversal_node::TraversalNode;
+pub use crate::traversal::traversal_node_filter::{DataPredicate, NodeFilter};
+pub use crate::traversal::traversal_origin::{TraversalOrigin, VisitedScope};
+pub use crate::traversal::traversal_page::{EndpointKey, TraversalCursor, TraversalPage};
+pub use crate::traversal::traversal_query::TraversalQuery;
+pub use crate::traversal::traversal_result::TraversalResult;
+pub use crate::traversal::traversal_step::{TraversalCount, TraversalStep};
+pub use crate::traversal::traversal_step_result::TraversalStepResult;
+pub use crate::traversal::traversal_step_spec::{NodeFilterSpec, TraversalStepSpec};
@@ -0,0 +1,315 @@
+use std::rc::Rc;
+
+use im::{HashMap, HashSet, Vector};
+
+use crate::prelude::{reactive_node::read_reactive_node::ReadReactiveNode, *};
+
+use super::pattern_match::PatternMatch;
+
+// The graph node matched to each pattern node so far, indexed like GraphPattern::nodes
+type AssignedNodes<T, E, A, L> = [Option<Rc<ReadReactiveNode<T, E, A, L>>>];
+
+#[derive(Clone, PartialEq, Debug, Eq, Hash)]
+pub struct PatternNode<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub name: String,
+    // Restricts the pattern node to a single graph node
+    pub id: Option<Uid>,
+    pub node_filter: Option<NodeFilter<T, E, A, L>>,
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> PatternNode<T, E, A, L> {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            id: None,
+            node_filter: None,
+        }
+    }
+
+    pub fn accepts_node(&self, node: &ReadReactiveNode<T, E, A, L>) -> bool {
+        self.id.is_none_or(|id| id == node.id)
+            && self
+                .node_filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(node))
+    }
+}
+
+// Matches edges hosted by the source node which point at the target node
+#[derive(Clone, PartialEq, Debug, Eq, Hash)]
+pub struct PatternEdge<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub source: String,
+    pub target: String,
+    pub edge_finder: EdgeFinder<T, E, A, L>,
+}
+
+// Finds every way the pattern can be embedded in the graph.
+// Each pattern node is matched to a different graph node, and every pattern edge must have at least one matching graph edge.
+// Graph edges which aren't part of the pattern are ignored.
+#[derive(Clone)]
+pub struct GraphPattern<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub nodes: Vector<PatternNode<T, E, A, L>>,
+    pub edges: Vector<PatternEdge<T, E, A, L>>,
+    pub max_matches: Option<usize>,
+    pub get_node_closure: GetNodeClosure<T, E, A, L>,
+    pub get_node_ids_closure: GetNodeIdsClosure,
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> GraphPattern<T, E, A, L> {
+    pub fn new(
+        get_node_closure: GetNodeClosure<T, E, A, L>,
+        get_node_ids_closure: GetNodeIdsClosure,
+    ) -> Self {
+        Self {
+            nodes: Vector::new(),
+            edges: Vector::new(),
+            max_matches: None,
+            get_node_closure,
+            get_node_ids_closure,
+        }
+    }
+
+    pub fn add_node(&self, name: impl Into<String>) -> Self {
+        self.add_pattern_node(PatternNode::new(name))
+    }
+
+    pub fn add_filtered_node(
+        &self,
+        name: impl Into<String>,
+        node_filter: NodeFilter<T, E, A, L>,
+    ) -> Self {
+        self.add_pattern_node(PatternNode {
+            node_filter: Some(node_filter),
+            ..PatternNode::new(name)
+        })
+    }
+
+    pub fn add_pinned_node(&self, name: impl Into<String>, id: Uid) -> Self {
+        self.add_pattern_node(PatternNode {
+            id: Some(id),
+            ..PatternNode::new(name)
+        })
+    }
+
+    // Replaces any existing pattern node with the same name
+    pub fn add_pattern_node(&self, node: PatternNode<T, E, A, L>) -> Self {
+        let mut new_nodes = self.nodes.clone();
+        new_nodes.retain(|existing| existing.name != node.name);
+        new_nodes.push_back(PatternNode {
+            node_filter: node
+                .node_filter
+                .map(|filter| filter.bind_graph(&self.get_node_closure)),
+            ..node
+        });
+        Self {
+            nodes: new_nodes,
+            ..self.clone()
+        }
+    }
+
+    // Pattern nodes which haven't been added yet are added without any restrictions
+    pub fn add_edge(
+        &self,
+        source: impl Into<String>,
+        target: impl Into<String>,
+        edge_finder: EdgeFinder<T, E, A, L>,
+    ) -> Self {
+        let source = source.into();
+        let target = target.into();
+        let mut new_pattern = self.clone();
+        for name in [&source, &target] {
+            if !new_pattern.nodes.iter().any(|node| &node.name == name) {
+                new_pattern = new_pattern.add_node(name.clone());
+            }
+        }
+        new_pattern.edges.push_back(PatternEdge {
+            source,
+            target,
+            edge_finder: edge_finder.bind_graph(&self.get_node_closure),
+        });
+        new_pattern
+    }
+
+    pub fn max_matches(&self, max_matches: usize) -> Self {
+        Self {
+            max_matches: Some(max_matches),
+            ..self.clone()
+        }
+    }
+
+    // Fails if a pattern edge refers to a node which isn't in the pattern
+    pub fn find_matches(&self) -> Result<Vector<PatternMatch<T, E, A, L>>, GraphError> {
+        let mut matches = Vector::new();
+        let edge_indices = self.edge_indices()?;
+        if self.nodes.is_empty() {
+            return Ok(matches);
+        }
+        let mut assigned = vec![None; self.nodes.len()];
+        self.extend_match(
+            &self.search_order(&edge_indices),
+            &edge_indices,
+            &mut assigned,
+            &mut matches,
+        );
+        Ok(matches)
+    }
+
+    // The (source, target) node index of each pattern edge
+    fn edge_indices(&self) -> Result<Vec<(usize, usize)>, GraphError> {
+        let node_index = |name: &String| {
+            self.nodes
+                .iter()
+                .position(|node| &node.name == name)
+                .ok_or_else(|| {
+                    GraphError::Traversal(TraversalError::UnknownPatternNode(name.clone()))
+                })
+        };
+        self.edges
+            .iter()
+            .map(|edge| Ok((node_index(&edge.source)?, node_index(&edge.target)?)))
+            .collect()
+    }
+
+    // Pinned nodes go first, then each following node is the one with the most edges to the nodes before it,
+    // so the candidates for most nodes can be found by following edges rather than checking every node
+    fn search_order(&self, edge_indices: &[(usize, usize)]) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while order.len() < self.nodes.len() {
+            let next = (0..self.nodes.len())
+                .filter(|index| !order.contains(index))
+                .max_by_key(|index| {
+                    let connections = edge_indices
+                        .iter()
+                        .filter(|(source, target)| {
+                            (source == index && order.contains(target))
+                                || (target == index && order.contains(source))
+                        })
+                        .count();
+                    (self.nodes[*index].id.is_some(), connections)
+                })
+                .unwrap();
+            order.push(next);
+        }
+        order
+    }
+
+    fn extend_match(
+        &self,
+        order: &[usize],
+        edge_indices: &[(usize, usize)],
+        assigned: &mut AssignedNodes<T, E, A, L>,
+        matches: &mut Vector<PatternMatch<T, E, A, L>>,
+    ) {
+        if self.max_matches.is_some_and(|max| matches.len() >= max) {
+            return;
+        }
+        let Some((&index, remaining_order)) = order.split_first() else {
+            matches.push_back(self.build_match(edge_indices, assigned));
+            return;
+        };
+
+        for candidate_id in self.candidates(index, edge_indices, assigned) {
+            if assigned
+                .iter()
+                .flatten()
+                .any(|node| node.id == candidate_id)
+            {
+                continue;
+            }
+            let Ok(candidate) = (self.get_node_closure)(&candidate_id) else {
+                continue;
+            };
+            if !self.nodes[index].accepts_node(&candidate) {
+                continue;
+            }
+            assigned[index] = Some(candidate);
+            let edges_match = edge_indices
+                .iter()
+                .enumerate()
+                .filter(|(_, (source, target))| *source == index || *target == index)
+                .all(|(edge_index, _)| {
+                    self.matching_edges(edge_index, edge_indices, assigned)
+                        .is_none_or(|edges| !edges.is_empty())
+                });
+            if edges_match {
+                self.extend_match(remaining_order, edge_indices, assigned, matches);
+            }
+            assigned[index] = None;
+        }
+    }
+
+    // Follows an edge from a node which has already been matched when possible, otherwise every node is a candidate
+    fn candidates(
+        &self,
+        index: usize,
+        edge_indices: &[(usize, usize)],
+        assigned: &AssignedNodes<T, E, A, L>,
+    ) -> Vector<Uid> {
+        if let Some(id) = self.nodes[index].id {
+            return Vector::unit(id);
+        }
+        for (edge, (source, target)) in self.edges.iter().zip(edge_indices.iter()) {
+            let (matched_node, edge_finder) = match (&assigned[*source], &assigned[*target]) {
+                (Some(source_node), None) if *target == index => {
+                    (source_node, edge.edge_finder.match_all())
+                }
+                // The gate closure is run against the target node, so it is checked once the source node is matched instead
+                (None, Some(target_node)) if *source == index => (
+                    target_node,
+                    edge.edge_finder.invert_drop_closure().match_all(),
+                ),
+                _ => continue,
+            };
+            return matched_node
+                .search_for_edge(&edge_finder)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|found_edge| found_edge.target)
+                .collect::<HashSet<Uid>>()
+                .into_iter()
+                .collect();
+        }
+        (self.get_node_ids_closure)()
+    }
+
+    // Returns None if either end of the pattern edge hasn't been matched yet
+    fn matching_edges(
+        &self,
+        edge_index: usize,
+        edge_indices: &[(usize, usize)],
+        assigned: &AssignedNodes<T, E, A, L>,
+    ) -> Option<HashSet<EdgeDescriptor<E>>> {
+        let (source, target) = edge_indices[edge_index];
+        let source_node = assigned[source].as_ref()?;
+        let target_id = assigned[target].as_ref()?.id;
+        Some(
+            source_node
+                .search_for_edge(&self.edges[edge_index].edge_finder.match_all())
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|found_edge| found_edge.target == target_id)
+                .collect(),
+        )
+    }
+
+    fn build_match(
+        &self,
+        edge_indices: &[(usize, usize)],
+        assigned: &AssignedNodes<T, E, A, L>,
+    ) -> PatternMatch<T, E, A, L> {
+        PatternMatch {
+            nodes: self
+                .nodes
+                .iter()
+                .zip(assigned.iter())
+                .filter_map(|(pattern_node, node)| Some((pattern_node.name.clone(), node.clone()?)))
+                .collect::<HashMap<_, _>>(),
+            edges: (0..self.edges.len())
+                .map(|edge_index| {
+                    self.matching_edges(edge_index, edge_indices, assigned)
+                        .unwrap_or_default()
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,7 +1,18 @@
+pub mod graph_pattern;
+pub mod path_search;
+pub mod pattern_match;
+pub mod traversal_aggregate;
+pub mod traversal_binding;
 pub mod traversal_descriptor;
 pub mod traversal_edge;
 pub mod traversal_executor;
+pub mod traversal_limits;
 pub mod traversal_node;
+pub mod traversal_node_filter;
+pub mod traversal_origin;
+pub mod traversal_page;
+pub mod traversal_query;
 pub mod traversal_result;
 pub mod traversal_step;
-pub mod traversal_step_result;
\ No newline at end of file
+pub mod traversal_step_result;
+pub mod traversal_step_spec;
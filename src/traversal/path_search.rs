@@ -0,0 +1,244 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    ops::Add,
+    rc::Rc,
+};
+
+use im::{vector, HashMap, Vector};
+
+use crate::prelude::{reactive_node::read_reactive_node::ReadReactiveNode, *};
+
+use super::{
+    traversal_edge::{TraversalEdge, UpstreamEdge},
+    traversal_node::TraversalNode,
+    traversal_step::{TraversalCount, TraversalStep},
+    traversal_step_result::TraversalStepResult,
+};
+
+type Predecessors<E> = HashMap<Uid, Vector<(Uid, EdgeDescriptor<E>)>>;
+
+// Searches for paths between two nodes which only cross edges matching the given EdgeFinder
+// Paths are returned in the same shape as a single TraversalStepResult:
+// the entry is the start node with a single chain of downstream edges, and the only endpoint is the end node
+#[derive(Clone)]
+pub struct PathSearch<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub start_node: Uid,
+    pub end_node: Uid,
+    pub edge_finder: EdgeFinder<T, E, A, L>,
+    pub get_node_closure: GetNodeClosure<T, E, A, L>,
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> PathSearch<T, E, A, L> {
+    pub fn new(
+        start_node: Uid,
+        end_node: Uid,
+        edge_finder: EdgeFinder<T, E, A, L>,
+        get_node_closure: GetNodeClosure<T, E, A, L>,
+    ) -> Self {
+        Self {
+            start_node,
+            end_node,
+            // Every edge leaving a node needs to be considered, not just the first match
+            edge_finder: edge_finder.match_all().bind_graph(&get_node_closure),
+            get_node_closure,
+        }
+    }
+
+    // Unweighted breadth first search
+    pub fn shortest_path(&self) -> Option<TraversalStepResult<T, E, A, L>> {
+        let predecessors = self.breadth_first_predecessors()?;
+        let mut edges = Vector::new();
+        let mut current_id = self.end_node;
+        while current_id != self.start_node {
+            let (_prev_id, edge) = predecessors.get(&current_id)?.head()?.clone();
+            current_id = edge.host;
+            edges.push_front(edge);
+        }
+        self.build_path_result(edges)
+    }
+
+    // Every path which shares the minimum number of hops
+    pub fn all_shortest_paths(&self) -> Vector<TraversalStepResult<T, E, A, L>> {
+        let Some(predecessors) = self.breadth_first_predecessors() else {
+            return Vector::new();
+        };
+        let mut paths = Vector::new();
+        let mut partial_paths = vector![(self.end_node, Vector::<EdgeDescriptor<E>>::new())];
+        while let Some((current_id, edges)) = partial_paths.pop_front() {
+            if current_id == self.start_node {
+                paths.extend(self.build_path_result(edges));
+                continue;
+            }
+            for (prev_id, edge) in predecessors.get(&current_id).cloned().unwrap_or_default() {
+                let mut new_edges = edges.clone();
+                new_edges.push_front(edge);
+                partial_paths.push_back((prev_id, new_edges));
+            }
+        }
+        paths
+    }
+
+    // Dijkstra's algorithm. Costs are expected to be non-negative.
+    // Floats can be used as costs, but a NaN cost is treated as equal to every other cost.
+    pub fn cheapest_path<C>(
+        &self,
+        cost: impl Fn(&EdgeDescriptor<E>) -> C,
+    ) -> Option<(C, TraversalStepResult<T, E, A, L>)>
+    where
+        C: Copy + PartialOrd + Default + Add<Output = C>,
+    {
+        let mut costs = HashMap::<Uid, C>::new();
+        let mut previous_edges = HashMap::<Uid, EdgeDescriptor<E>>::new();
+        let mut queue = BinaryHeap::new();
+        costs.insert(self.start_node, C::default());
+        queue.push(Reverse((QueuedCost(C::default()), self.start_node)));
+
+        while let Some(Reverse((QueuedCost(current_cost), current_id))) = queue.pop() {
+            if current_id == self.end_node {
+                break;
+            }
+            if costs
+                .get(&current_id)
+                .is_some_and(|best| current_cost > *best)
+            {
+                continue;
+            }
+            for edge in self.matching_edges(current_id) {
+                let new_cost = current_cost + cost(&edge);
+                if costs.get(&edge.target).is_none_or(|best| new_cost < *best) {
+                    costs.insert(edge.target, new_cost);
+                    queue.push(Reverse((QueuedCost(new_cost), edge.target)));
+                    previous_edges.insert(edge.target, edge);
+                }
+            }
+        }
+
+        let total_cost = *costs.get(&self.end_node)?;
+        let mut edges = Vector::new();
+        let mut current_id = self.end_node;
+        while current_id != self.start_node {
+            let edge = previous_edges.get(&current_id)?.clone();
+            current_id = edge.host;
+            edges.push_front(edge);
+        }
+        Some((total_cost, self.build_path_result(edges)?))
+    }
+
+    fn matching_edges(&self, node_id: Uid) -> Vector<EdgeDescriptor<E>> {
+        (self.get_node_closure)(&node_id)
+            .ok()
+            .and_then(|node| node.search_for_edge(&self.edge_finder))
+            .map(|edges| edges.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    // Maps each reached node to every (node, edge) pair which reaches it on a shortest path
+    // Returns None if the end node can't be reached
+    fn breadth_first_predecessors(&self) -> Option<Predecessors<E>> {
+        let mut distances = HashMap::<Uid, usize>::new();
+        let mut predecessors = Predecessors::<E>::new();
+        let mut current_layer = vector![self.start_node];
+        distances.insert(self.start_node, 0);
+        let mut distance = 0;
+
+        while !current_layer.is_empty() && !distances.contains_key(&self.end_node) {
+            distance += 1;
+            let mut next_layer = Vector::new();
+            for node_id in current_layer.iter() {
+                for edge in self.matching_edges(*node_id) {
+                    match distances.get(&edge.target) {
+                        Some(existing_distance) if *existing_distance == distance => {
+                            predecessors
+                                .entry(edge.target)
+                                .or_default()
+                                .push_back((*node_id, edge));
+                        }
+                        Some(_) => {}
+                        None => {
+                            distances.insert(edge.target, distance);
+                            next_layer.push_back(edge.target);
+                            predecessors
+                                .entry(edge.target)
+                                .or_default()
+                                .push_back((*node_id, edge));
+                        }
+                    }
+                }
+            }
+            current_layer = next_layer;
+        }
+
+        if !distances.contains_key(&self.end_node) {
+            return None;
+        }
+        Some(predecessors)
+    }
+
+    fn build_path_result(
+        &self,
+        edges: Vector<EdgeDescriptor<E>>,
+    ) -> Option<TraversalStepResult<T, E, A, L>> {
+        let nodes = iter_path_ids(self.start_node, &edges)
+            .map(|id| (self.get_node_closure)(&id).ok())
+            .collect::<Option<Vec<Rc<ReadReactiveNode<T, E, A, L>>>>>()?;
+        let step = Rc::new(TraversalStep::new(
+            self.edge_finder.clone(),
+            TraversalCount::Exactly(edges.len()),
+        ));
+
+        let upstream_edge = |index: usize| {
+            UpstreamEdge::new(edges[index - 1].clone(), step.clone(), index - 1, index - 1)
+        };
+        let path_length = edges.len();
+        let mut current = TraversalNode::new(nodes[path_length].clone(), path_length, path_length);
+        if path_length > 0 {
+            current = current.set_upstream_edge(upstream_edge(path_length));
+        }
+        let endpoint = current.clone();
+
+        for index in (0..path_length).rev() {
+            let mut parent = TraversalNode::new(nodes[index].clone(), index, index);
+            if index > 0 {
+                parent = parent.set_upstream_edge(upstream_edge(index));
+            }
+            current = parent.add_downstream_edge(TraversalEdge::new(
+                edges[index].clone(),
+                step.clone(),
+                current,
+                index,
+                index,
+            ));
+        }
+
+        Some(TraversalStepResult {
+            entry: current,
+            endpoints: vector![endpoint],
+        })
+    }
+}
+
+// Gives partially ordered costs the total order BinaryHeap needs
+#[derive(Clone, Copy, PartialEq)]
+struct QueuedCost<C>(C);
+
+impl<C: PartialOrd> Eq for QueuedCost<C> {}
+
+impl<C: PartialOrd> PartialOrd for QueuedCost<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C: PartialOrd> Ord for QueuedCost<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+fn iter_path_ids<E: EdgeTraits>(
+    start_node: Uid,
+    edges: &Vector<EdgeDescriptor<E>>,
+) -> impl Iterator<Item = Uid> + '_ {
+    std::iter::once(start_node).chain(edges.iter().map(|edge| edge.target))
+}
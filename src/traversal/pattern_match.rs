@@ -0,0 +1,20 @@
+use std::rc::Rc;
+
+use im::{HashMap, HashSet, Vector};
+
+use crate::prelude::{reactive_node::read_reactive_node::ReadReactiveNode, *};
+
+// A single embedding of a GraphPattern in the graph
+#[derive(Clone, PartialEq, Debug, Eq, Hash)]
+pub struct PatternMatch<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    // Maps each pattern node name to the graph node it was matched to
+    pub nodes: HashMap<String, Rc<ReadReactiveNode<T, E, A, L>>>,
+    // Every graph edge matching each pattern edge, in the order the pattern edges were added
+    pub edges: Vector<HashSet<EdgeDescriptor<E>>>,
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> PatternMatch<T, E, A, L> {
+    pub fn node_id(&self, name: &str) -> Option<Uid> {
+        self.nodes.get(name).map(|node| node.id)
+    }
+}
@@ -0,0 +1,138 @@
+use core::hash::Hash;
+
+use im::{HashMap, HashSet, Vector};
+use leptos::*;
+
+use crate::prelude::*;
+
+use super::{
+    traversal_descriptor::TraversalDescriptor, traversal_node::TraversalNode,
+    traversal_page::EndpointKey, traversal_result::TraversalResult,
+};
+
+// Endpoints grouped by a key
+pub type NodeGroups<K, T, E, A, L = String> = HashMap<K, Vector<TraversalNode<T, E, A, L>>>;
+
+// Selects which endpoints of a TraversalResult an aggregation runs over
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash)]
+pub enum AggregateScope {
+    // The endpoints of the step at this index
+    Step(usize),
+    // The endpoints of the final step
+    FinalEndpoints,
+}
+
+// Aggregations see one endpoint per node and traversal index, ordered by EndpointKey.
+// Node data and labels are read with tracking, so they can be used within a memo.
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> TraversalResult<T, E, A, L> {
+    pub fn endpoints_in(&self, scope: AggregateScope) -> Vector<TraversalNode<T, E, A, L>> {
+        let step = match scope {
+            AggregateScope::Step(step) => step,
+            AggregateScope::FinalEndpoints => return self.final_endpoints(),
+        };
+        let mut seen = HashSet::new();
+        let mut endpoints = self
+            .step_results
+            .get(step)
+            .into_iter()
+            .flat_map(|step_results| step_results.iter())
+            .flat_map(|step_result| step_result.endpoints.iter())
+            .filter(|endpoint| seen.insert(EndpointKey::from_endpoint(endpoint)).is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+        endpoints.sort_by_key(EndpointKey::from_endpoint);
+        Vector::from(endpoints)
+    }
+
+    pub fn count(&self, scope: AggregateScope) -> usize {
+        self.endpoints_in(scope).len()
+    }
+
+    pub fn collect_data(&self, scope: AggregateScope) -> Vector<T> {
+        self.endpoints_in(scope)
+            .iter()
+            .map(|endpoint| endpoint.node.data.get())
+            .collect()
+    }
+
+    pub fn group_by<K>(
+        &self,
+        scope: AggregateScope,
+        key_fn: impl Fn(&TraversalNode<T, E, A, L>) -> K,
+    ) -> NodeGroups<K, T, E, A, L>
+    where
+        K: Hash + Eq + Clone,
+    {
+        self.endpoints_in(scope)
+            .into_iter()
+            .fold(HashMap::new(), |mut groups, endpoint| {
+                groups
+                    .entry(key_fn(&endpoint))
+                    .or_default()
+                    .push_back(endpoint);
+                groups
+            })
+    }
+
+    // Groups by the type of the edge used to reach each endpoint. Endpoints reached without a hop are grouped under None.
+    pub fn group_by_edge_type(&self, scope: AggregateScope) -> NodeGroups<Option<E>, T, E, A, L> {
+        self.group_by(scope, |endpoint| {
+            endpoint
+                .upstream_edge
+                .as_ref()
+                .filter(|_| endpoint.step_index > 0)
+                .map(|upstream_edge| upstream_edge.edge.edge_type.clone())
+        })
+    }
+
+    // An endpoint with several labels shows up in each of their groups, and one without labels is in none
+    pub fn group_by_label(&self, scope: AggregateScope) -> NodeGroups<L, T, E, A, L> {
+        self.endpoints_in(scope)
+            .into_iter()
+            .fold(HashMap::new(), |mut groups, endpoint| {
+                for label in endpoint.node.labels.get().iter() {
+                    groups
+                        .entry(label.clone())
+                        .or_default()
+                        .push_back(endpoint.clone());
+                }
+                groups
+            })
+    }
+
+    pub fn fold<B>(
+        &self,
+        scope: AggregateScope,
+        init: B,
+        f: impl Fn(B, &TraversalNode<T, E, A, L>) -> B,
+    ) -> B {
+        self.endpoints_in(scope).iter().fold(init, f)
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>
+    TraversalDescriptor<T, E, A, L>
+{
+    // Reruns the traversal every time a blueprint is applied to the graph, then aggregates the result.
+    // The aggregate is given None when no route satisfies every step and no limit was hit.
+    pub fn create_aggregate_memo<R>(
+        &self,
+        graph_lock: &GraphLock,
+        aggregate: impl Fn(Option<&TraversalResult<T, E, A, L>>) -> R + 'static,
+    ) -> Memo<R>
+    where
+        R: PartialEq + Clone + 'static,
+    {
+        let traversal = self.clone();
+        let is_locked = graph_lock.is_locked;
+        create_memo(move |previous: Option<&R>| {
+            // The graph is only partially updated while it is locked
+            if is_locked.get() {
+                if let Some(previous) = previous {
+                    return previous.clone();
+                }
+            }
+            aggregate(traversal.execute().as_ref())
+        })
+    }
+}
@@ -0,0 +1,94 @@
+use std::rc::Rc;
+
+use im::{HashMap, HashSet, Vector};
+
+use crate::prelude::{reactive_node::read_reactive_node::ReadReactiveNode, *};
+
+use super::{traversal_node::TraversalNode, traversal_result::TraversalResult};
+
+#[derive(Clone, PartialEq, Debug, Eq, Hash)]
+pub struct TraversalBinding<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String>
+{
+    pub node: Rc<ReadReactiveNode<T, E, A, L>>,
+    // The edge which was used to reach the node. None if the step matched without making any hops.
+    pub edge: Option<EdgeDescriptor<E>>,
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> TraversalBinding<T, E, A, L> {
+    pub fn from_endpoint(endpoint: &TraversalNode<T, E, A, L>) -> Self {
+        let edge = if endpoint.step_index == 0 {
+            None
+        } else {
+            endpoint
+                .upstream_edge
+                .as_ref()
+                .map(|upstream_edge| upstream_edge.edge.clone())
+        };
+        Self {
+            node: endpoint.node.clone(),
+            edge,
+        }
+    }
+}
+
+// Maps each step name to the node matched by that step
+pub type TraversalBindingRow<T, E, A, L = String> = HashMap<String, TraversalBinding<T, E, A, L>>;
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> TraversalResult<T, E, A, L> {
+    // Returns one row for every distinct successful match through all of the steps
+    // Unnamed steps are still traversed but do not show up in the rows
+    pub fn bindings(&self) -> Vector<TraversalBindingRow<T, E, A, L>> {
+        let mut rows = Vector::new();
+        let mut seen_rows = HashSet::new();
+        if let Some(first_step_results) = self.step_results.get(0) {
+            for step_result in first_step_results.iter() {
+                self.collect_binding_rows(
+                    0,
+                    &step_result.endpoints,
+                    HashMap::new(),
+                    &mut rows,
+                    &mut seen_rows,
+                );
+            }
+        }
+        rows
+    }
+
+    fn collect_binding_rows(
+        &self,
+        current_step: usize,
+        endpoints: &Vector<TraversalNode<T, E, A, L>>,
+        rolling_row: TraversalBindingRow<T, E, A, L>,
+        rows: &mut Vector<TraversalBindingRow<T, E, A, L>>,
+        seen_rows: &mut HashSet<TraversalBindingRow<T, E, A, L>>,
+    ) {
+        let step_name = self.step_names.get(current_step).cloned().flatten();
+        for endpoint in endpoints.iter() {
+            let mut new_row = rolling_row.clone();
+            if let Some(step_name) = &step_name {
+                new_row.insert(step_name.clone(), TraversalBinding::from_endpoint(endpoint));
+            }
+
+            if current_step + 1 >= self.step_results.len() {
+                if seen_rows.insert(new_row.clone()).is_none() {
+                    rows.push_back(new_row);
+                }
+                continue;
+            }
+
+            // The endpoints of this step are the entries of the next step
+            for next_step_result in self.step_results[current_step + 1].iter().filter(|item| {
+                item.entry.node.id == endpoint.node.id
+                    && item.entry.traversal_index == endpoint.traversal_index
+            }) {
+                self.collect_binding_rows(
+                    current_step + 1,
+                    &next_step_result.endpoints,
+                    new_row.clone(),
+                    rows,
+                    seen_rows,
+                );
+            }
+        }
+    }
+}
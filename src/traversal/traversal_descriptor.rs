@@ -1,103 +1,227 @@
-use std::{fmt::Formatter, iter};
+use std::fmt::Formatter;
 
 use crate::prelude::*;
 
 use super::{
-    traversal_executor::{traverse_step, TraversalStepRecursiveResult},
+    traversal_executor::TraversalExecutor,
+    traversal_limits::{TraversalBudget, TraversalLimits},
+    traversal_origin::{TraversalOrigin, VisitedScope},
     traversal_step::{TraversalCount, TraversalStep},
 };
 
-
-
 use im::{HashSet, Vector};
 
+use super::traversal_result::TraversalResult;
 
-
-use super::{
-    traversal_node::TraversalNode,
-    traversal_result::TraversalResult,
-    traversal_step_result::TraversalStepResult,
-};
+type TraversalGroups<T, E, A, L> = Vector<(Uid, TraversalResult<T, E, A, L>)>;
 
 #[derive(Clone)]
-pub struct TraversalDescriptor<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub start_node: Uid,
-    pub steps: Vector<TraversalStep<T, E, A>>,
-    pub get_node_closure: GetNodeClosure<T, E, A>,
+pub struct TraversalDescriptor<
+    T: GraphTraits,
+    E: EdgeTraits,
+    A: GraphTraits,
+    L: GraphTraits = String,
+> {
+    pub origin: TraversalOrigin<L>,
+    pub visited_scope: VisitedScope,
+    pub steps: Vector<TraversalStep<T, E, A, L>>,
+    pub limits: TraversalLimits,
+    pub get_node_closure: GetNodeClosure<T, E, A, L>,
+    // Needed to resolve a label origin, see with_labels
+    pub get_label_closure: Option<GetLabelClosure<L>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> PartialEq for TraversalDescriptor<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> PartialEq
+    for TraversalDescriptor<T, E, A, L>
+{
     fn eq(&self, other: &Self) -> bool {
-        self.start_node == other.start_node && self.steps == other.steps
+        self.origin == other.origin
+            && self.visited_scope == other.visited_scope
+            && self.steps == other.steps
+            && self.limits == other.limits
     }
 }
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> Eq for TraversalDescriptor<T, E, A> {}
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> std::fmt::Debug
-    for TraversalDescriptor<T, E, A>
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> Eq
+    for TraversalDescriptor<T, E, A, L>
+{
+}
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> std::fmt::Debug
+    for TraversalDescriptor<T, E, A, L>
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TraversalDescriptor")
-            .field("start_node", &self.start_node)
+            .field("origin", &self.origin)
+            .field("visited_scope", &self.visited_scope)
             .field("steps", &self.steps)
+            .field("limits", &self.limits)
             .finish()
     }
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalDescriptor<T, E, A> {
-    pub fn new(start_node: Uid, get_node_closure: GetNodeClosure<T, E, A>) -> Self {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>
+    TraversalDescriptor<T, E, A, L>
+{
+    pub fn new(
+        origin: impl Into<TraversalOrigin<L>>,
+        get_node_closure: GetNodeClosure<T, E, A, L>,
+    ) -> Self {
         Self {
-            start_node,
+            origin: origin.into(),
+            visited_scope: VisitedScope::default(),
             get_node_closure,
+            get_label_closure: None,
             steps: Vector::new(),
+            limits: TraversalLimits::default(),
+        }
+    }
+
+    // Without it, a label origin has no start nodes
+    pub fn with_labels(&self, get_label_closure: GetLabelClosure<L>) -> Self {
+        Self {
+            get_label_closure: Some(get_label_closure),
+            ..self.clone()
+        }
+    }
+
+    pub fn visited_scope(&self, visited_scope: VisitedScope) -> Self {
+        Self {
+            visited_scope,
+            ..self.clone()
         }
     }
 
-    pub fn add_step(&self, edge_finder: EdgeFinder<T, E, A>, count: TraversalCount) -> Self {
+    pub fn with_limits(&self, limits: TraversalLimits) -> Self {
+        Self {
+            limits,
+            ..self.clone()
+        }
+    }
+
+    pub fn add_step(&self, edge_finder: EdgeFinder<T, E, A, L>, count: TraversalCount) -> Self {
+        self.add_traversal_step(TraversalStep::new(edge_finder, count))
+    }
+
+    pub fn add_filtered_step(
+        &self,
+        edge_finder: EdgeFinder<T, E, A, L>,
+        count: TraversalCount,
+        node_filter: NodeFilter<T, E, A, L>,
+    ) -> Self {
+        self.add_traversal_step(TraversalStep::new(edge_finder, count).filter_nodes(node_filter))
+    }
+
+    pub fn add_optional_step(&self, edge_finder: EdgeFinder<T, E, A, L>) -> Self {
+        self.add_traversal_step(TraversalStep::new(edge_finder, TraversalCount::Optional))
+    }
+
+    pub fn add_alternation_step<I>(&self, edge_finders: I, count: TraversalCount) -> Self
+    where
+        I: IntoIterator<Item = EdgeFinder<T, E, A, L>>,
+    {
+        self.add_traversal_step(TraversalStep::new_alternation(edge_finders, count))
+    }
+
+    pub fn add_repeat_until_step(
+        &self,
+        edge_finder: EdgeFinder<T, E, A, L>,
+        until: NodeFilter<T, E, A, L>,
+    ) -> Self {
+        self.add_traversal_step(TraversalStep::new_repeat_until(edge_finder, until))
+    }
+
+    pub fn add_named_step(
+        &self,
+        name: impl Into<String>,
+        edge_finder: EdgeFinder<T, E, A, L>,
+        count: TraversalCount,
+    ) -> Self {
+        self.add_traversal_step(TraversalStep::new(edge_finder, count).name(name))
+    }
+
+    pub fn add_traversal_step(&self, step: TraversalStep<T, E, A, L>) -> Self {
         let mut new_steps = self.steps.clone();
-        new_steps.push_back(TraversalStep::new(edge_finder, count));
+        new_steps.push_back(step.bind_graph(&self.get_node_closure));
         Self {
-            start_node: self.start_node,
-            get_node_closure: self.get_node_closure.clone(),
             steps: new_steps,
+            ..self.clone()
         }
     }
 
-    pub fn execute(&self) -> Option<TraversalResult<T, E, A>> {
-        let get_node = self.get_node_closure.clone();
-        let root_trav_node = TraversalNode::new((get_node)(&self.start_node).unwrap(), 0, 0);
-        let visited_all: HashSet<Uid> = HashSet::new();
-
-        let initial_result_vector =
-            iter::repeat_with(Vector::<TraversalStepResult<T, E, A>>::new)
-                .take(self.steps.len())
-                .collect::<Vector<Vector<TraversalStepResult<T, E, A>>>>();
-
-        // let mut result = TraversalResult::<T, E, A>::new(self.steps.len());
-        let result = traverse_step(
-            Vector::from(vec![root_trav_node]),
-            self,
-            0,
-            visited_all,
-            &get_node,
-            TraversalStepRecursiveResult {
-                result: initial_result_vector,
-                visited_all: HashSet::new(),
-            },
-        )?;
-        let result = TraversalResult {
-            step_results: result.result,
-        };
+    // Combines the results from every start node, so the first step has one entry per successful start node
+    // Returns None if no route satisfies every step.
+    // If a limit was hit before any route was found, the result is empty and marked truncated instead.
+    pub fn execute(&self) -> Option<TraversalResult<T, E, A, L>> {
+        let (groups, truncated) = self.execute_groups();
+        if groups.is_empty() && !truncated {
+            return None;
+        }
+        let mut result = TraversalResult::new(self.steps.len());
+        for (_start_id, group_result) in groups.iter() {
+            for (step, step_results) in group_result.step_results.iter().enumerate() {
+                result.step_results[step].append(step_results.clone());
+            }
+        }
+        // Includes start nodes whose routes were all cut off
+        result.truncated = truncated;
+        result.step_names = self.steps.iter().map(|step| step.name.clone()).collect();
         Some(result)
+    }
+
+    // Returns a result for each start node which has a route satisfying every step, in the order of the origin
+    // The limits are shared by every start node, so later results are the first to be truncated.
+    // Each result is only marked truncated if a limit was hit while traversing from its own start node.
+    pub fn execute_grouped(&self) -> Vector<(Uid, TraversalResult<T, E, A, L>)> {
+        self.execute_groups().0
+    }
+
+    // Also returns whether a limit was hit from any start node
+    fn execute_groups(&self) -> (TraversalGroups<T, E, A, L>, bool) {
+        let budget = TraversalBudget::new(self.limits.clone());
+        let get_node = self.get_node_closure.clone();
+        let mut visited = HashSet::new();
+        let mut groups = Vector::new();
+        let mut truncated = false;
+
+        for start_id in self.origin.resolve(self.get_label_closure.as_ref()) {
+            let is_shared = self.visited_scope == VisitedScope::Shared;
+            if is_shared && visited.contains(&start_id) {
+                continue;
+            }
+            let Ok(start_node) = (get_node)(&start_id) else {
+                continue;
+            };
+            let mut executor = TraversalExecutor::new(self, &get_node, &budget);
+            if is_shared {
+                executor = executor.exclude_nodes(visited.clone());
+            }
+            let step_results = executor.execute(start_node);
+            if is_shared {
+                visited.extend(executor.visited_nodes());
+            }
+            let group_truncated = budget.take_truncated();
+            truncated |= group_truncated;
+            if let Some(step_results) = step_results {
+                groups.push_back((
+                    start_id,
+                    TraversalResult {
+                        step_results,
+                        step_names: self.steps.iter().map(|step| step.name.clone()).collect(),
+                        truncated: group_truncated,
+                    },
+                ));
+            }
+        }
+        (groups, truncated)
 
-        // let mut rolling_result = TraversalResult::<T, E, A>::new(root_trav_node.clone());
-        // let mut next_step_entries: Vector<TraversalNode<T, E, A>> = Vector::new();
-        // let mut new_next_step_entries = Vector::<TraversalNode<T, E, A>>::new();
+        // let mut rolling_result = TraversalResult::<T, E, A, L>::new(root_trav_node.clone());
+        // let mut next_step_entries: Vector<TraversalNode<T, E, A, L>> = Vector::new();
+        // let mut new_next_step_entries = Vector::<TraversalNode<T, E, A, L>>::new();
         // new_next_step_entries.push_back(root_trav_node);
 
         // for (current_step, step) in self.steps.iter().enumerate() {
         //     rolling_result.add_new_step();
         //     next_step_entries = new_next_step_entries.clone();
-        //     new_next_step_entries = Vector::<TraversalNode<T, E, A>>::new();
+        //     new_next_step_entries = Vector::<TraversalNode<T, E, A, L>>::new();
         //     let step = Rc::new(step.clone());
         //     for entry_node in next_step_entries.iter() {
         //         let new_entry_node = entry_node.clone();
@@ -109,7 +233,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalDescriptor<T, E, A
         //             HashSet::<Uid>::new(),
         //             0,
         //             current_step,
-        //             Vector::<TraversalNode<T, E, A>>::new(),
+        //             Vector::<TraversalNode<T, E, A, L>>::new(),
         //             &get_node,
         //         );
 
@@ -1,25 +1,23 @@
 use std::rc::Rc;
 
-
-
 use crate::prelude::*;
 
 use super::{traversal_node::TraversalNode, traversal_step::TraversalStep};
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct UpstreamEdge<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
+pub struct UpstreamEdge<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
     pub edge: EdgeDescriptor<E>,
-    pub step: Rc<TraversalStep<T, E, A>>,
+    pub step: Rc<TraversalStep<T, E, A, L>>,
     pub traversal_index: usize,
     pub step_index: usize,
     pub creates_cycle: bool,
     pub is_reentrant: bool,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> UpstreamEdge<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> UpstreamEdge<T, E, A, L> {
     pub fn new(
         edge: EdgeDescriptor<E>,
-        step: Rc<TraversalStep<T, E, A>>,
+        step: Rc<TraversalStep<T, E, A, L>>,
         step_index: usize,
         traversal_index: usize,
     ) -> Self {
@@ -61,21 +59,21 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> UpstreamEdge<T, E, A> {
     }
 }
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct TraversalEdge<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
+pub struct TraversalEdge<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
     edge: EdgeDescriptor<E>,
     creates_cycle: bool,
     is_reentrant: bool,
-    step: Rc<TraversalStep<T, E, A>>,
+    step: Rc<TraversalStep<T, E, A, L>>,
     step_index: usize,
     traversal_index: usize,
-    target: TraversalNode<T, E, A>,
+    target: TraversalNode<T, E, A, L>,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalEdge<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> TraversalEdge<T, E, A, L> {
     pub fn new(
         edge: EdgeDescriptor<E>,
-        step: Rc<TraversalStep<T, E, A>>,
-        target: TraversalNode<T, E, A>,
+        step: Rc<TraversalStep<T, E, A, L>>,
+        target: TraversalNode<T, E, A, L>,
         step_index: usize,
         traversal_index: usize,
     ) -> Self {
@@ -116,4 +114,12 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalEdge<T, E, A> {
             ..self.clone()
         }
     }
+
+    pub fn edge(&self) -> &EdgeDescriptor<E> {
+        &self.edge
+    }
+
+    pub fn target(&self) -> &TraversalNode<T, E, A, L> {
+        &self.target
+    }
 }
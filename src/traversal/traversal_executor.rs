@@ -1,245 +1,354 @@
-use std::rc::Rc;
+use std::{collections::HashMap, rc::Rc};
 
-use im::{vector, HashSet, Vector};
+use im::{HashSet, Vector};
 
-use crate::prelude::*;
+use crate::prelude::{reactive_node::read_reactive_node::ReadReactiveNode, *};
 
 use super::{
     traversal_descriptor::TraversalDescriptor,
     traversal_edge::{TraversalEdge, UpstreamEdge},
+    traversal_limits::TraversalBudget,
     traversal_node::TraversalNode,
+    traversal_page::EndpointKey,
+    traversal_step::TraversalStep,
     traversal_step_result::TraversalStepResult,
 };
 
-// Note: The current structure is duplicating a lot of the return data
-// I.E. The first step contains the entire successful graph, but the second step contains the entire successful graph minus the first step
-// This probably isn't the best way to do this, but it does have the benefit of being able to continuously traverse the result from the first step,
-// whereas you'd have to manually stitch together the steps to get the entire result graph if each step only contained the relevant nodes
-// There is likely some more efficient way to do this with references instead of full clones, but I'm not sure how to do that yet
-#[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub(crate) struct TraversalStepRecursiveResult<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub result: Vector<Vector<TraversalStepResult<T, E, A>>>,
-    pub visited_all: HashSet<Uid>,
+type StepResults<T, E, A, L> = Vector<Vector<TraversalStepResult<T, E, A, L>>>;
+
+// The executor searches the graph first and only builds the TraversalNode trees once every successful route is known.
+// While searching, every successful node is stored once in an arena and referenced by index.
+// Outcomes are memoized by (step, node, step_index, traversal_index), so a node which is reached along several routes,
+// or which is the entry of a later step more than once, is only explored the first time.
+// Steps without an upper bound skip nodes already on the route. An outcome which never skipped a node can't lead back
+// to the route it was reached by, so it is shared with every other route. Only outcomes inside a cycle are explored per route.
+// The returned TraversalNode trees still contain a full copy of each step, which keeps the result easy to walk.
+
+// A node which is part of at least one successful route through the remaining steps
+struct ArenaNode<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> {
+    node: Rc<ReadReactiveNode<T, E, A, L>>,
+    step_index: usize,
+    traversal_index: usize,
+    // True if the step can end on this node
+    is_exit: bool,
+    // Hops within the same step which lead to successful nodes
+    children: Vec<(EdgeDescriptor<E>, usize)>,
 }
 
-pub(crate) fn traverse_step<T: GraphTraits, E: GraphTraits, A: GraphTraits>(
-    start_nodes: Vector<TraversalNode<T, E, A>>,
-    traversal_descriptor: &TraversalDescriptor<T, E, A>,
+// Identifies a node within a single step
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct ArenaKey {
     current_step: usize,
-    visited_all: HashSet<Uid>,
-    get_node: &GetNodeClosure<T, E, A>,
-    rolling_result: TraversalStepRecursiveResult<T, E, A>,
-) -> Option<TraversalStepRecursiveResult<T, E, A>> {
-    if current_step >= traversal_descriptor.steps.len() {
-        return Some(rolling_result);
-    }
-    let mut is_successful_completion = false;
+    node_id: Uid,
+    step_index: usize,
+    traversal_index: usize,
+}
 
-    let mut new_rolling_result = rolling_result.clone();
-    for start_node in start_nodes.iter() {
-        let _step = traversal_descriptor.steps[current_step].clone();
-        let step_item_results = traverse_step_item(
-            start_node.clone(),
-            current_step,
-            visited_all.clone(),
-            HashSet::<Uid>::new(),
-            0,
-            start_node.traversal_index,
-            Vector::<TraversalNode<T, E, A>>::new(),
+pub(crate) struct TraversalExecutor<
+    'a,
+    T: GraphTraits,
+    E: EdgeTraits,
+    A: GraphTraits,
+    L: GraphTraits = String,
+> {
+    steps: Vec<Rc<TraversalStep<T, E, A, L>>>,
+    get_node: &'a GetNodeClosure<T, E, A, L>,
+    budget: &'a TraversalBudget,
+    arena: Vec<ArenaNode<T, E, A, L>>,
+    // None marks a node which has no successful route
+    outcomes: HashMap<ArenaKey, Option<usize>>,
+    // Nodes which may not be hopped to, used to share visited nodes between start nodes
+    excluded_nodes: HashSet<Uid>,
+    visited_nodes: HashSet<Uid>,
+    // Number of hops skipped because the target was already on the route
+    route_skips: usize,
+}
+
+impl<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>
+    TraversalExecutor<'a, T, E, A, L>
+{
+    pub fn new(
+        traversal_descriptor: &TraversalDescriptor<T, E, A, L>,
+        get_node: &'a GetNodeClosure<T, E, A, L>,
+        budget: &'a TraversalBudget,
+    ) -> Self {
+        let steps = traversal_descriptor
+            .steps
+            .iter()
+            .cloned()
+            .map(Rc::new)
+            .collect::<Vec<_>>();
+        Self {
+            steps,
             get_node,
-            traversal_descriptor,
-            new_rolling_result.clone(),
-        );
+            budget,
+            arena: Vec::new(),
+            outcomes: HashMap::new(),
+            excluded_nodes: HashSet::new(),
+            visited_nodes: HashSet::new(),
+            route_skips: 0,
+        }
+    }
 
-        if step_item_results.is_none() {
-            continue;
+    pub fn exclude_nodes(self, excluded_nodes: HashSet<Uid>) -> Self {
+        Self {
+            excluded_nodes,
+            ..self
         }
-        is_successful_completion = true;
-
-        let successful_item_branch = step_item_results.unwrap();
-        let mut new_step_results_inner = new_rolling_result.result[current_step].clone();
-        new_step_results_inner.push_back(successful_item_branch.into_traversal_step_result());
-        new_rolling_result = successful_item_branch.rolling_result;
-        new_rolling_result
-            .result
-            .set(current_step, new_step_results_inner);
     }
 
-    if !is_successful_completion {
-        return None;
+    // Every node which was explored, whether or not it was part of a successful route
+    pub fn visited_nodes(&self) -> HashSet<Uid> {
+        self.visited_nodes.clone()
     }
-    Some(new_rolling_result)
-}
-#[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub(crate) struct TraversalStepItemRecursiveResult<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub result: TraversalNode<T, E, A>,
-    pub nodes_visited_this_step: HashSet<Uid>,
-    pub exit_nodes: Vector<TraversalNode<T, E, A>>,
-    pub rolling_result: TraversalStepRecursiveResult<T, E, A>,
-}
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalStepItemRecursiveResult<T, E, A> {
-    pub fn into_traversal_step_result(&self) -> TraversalStepResult<T, E, A> {
-        TraversalStepResult {
-            entry: self.result.clone(),
-            endpoints: self.exit_nodes.clone(),
+
+    pub fn execute(
+        &mut self,
+        start_node: Rc<ReadReactiveNode<T, E, A, L>>,
+    ) -> Option<StepResults<T, E, A, L>> {
+        let start_id = start_node.id;
+        self.traverse_step(0, start_node, 0)?;
+        let root = self.outcome(0, start_id, 0)?;
+
+        // The entries of each step are the endpoints of the one before, so they keep the edge they were reached by
+        let mut entries = vec![(root, None)];
+        let mut step_results = Vector::new();
+        for current_step in 0..self.steps.len() {
+            let results = entries
+                .iter()
+                .map(|(entry, upstream_edge)| {
+                    self.build_step_result(current_step, *entry, upstream_edge.clone())
+                })
+                .collect::<Vector<_>>();
+            let mut seen = HashSet::new();
+            entries = results
+                .iter()
+                .flat_map(|result| result.endpoints.iter())
+                .filter_map(|endpoint| {
+                    let entry =
+                        self.outcome(current_step + 1, endpoint.node.id, endpoint.traversal_index)?;
+                    let edge = endpoint
+                        .upstream_edge
+                        .as_ref()
+                        .map(|upstream_edge| upstream_edge.edge.clone());
+                    seen.insert((entry, edge))
+                        .is_none()
+                        .then(|| (entry, endpoint.upstream_edge.clone()))
+                })
+                .collect();
+            step_results.push_back(results);
         }
+        Some(step_results)
     }
-}
-pub(crate) fn traverse_step_item<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits>(
-    node: TraversalNode<T, E, A>,
-    current_step: usize,
-    visited_all: HashSet<Uid>,
-    visited_step: HashSet<Uid>,
-    step_index: usize,
-    traversal_index: usize,
-    exit_nodes: Vector<TraversalNode<T, E, A>>,
-    get_node: &GetNodeClosure<T, E, A>,
-    traversal_descriptor: &TraversalDescriptor<T, E, A>,
-    rolling_result: TraversalStepRecursiveResult<T, E, A>,
-) -> Option<TraversalStepItemRecursiveResult<T, E, A>> {
-    let step = Rc::new(
-        traversal_descriptor
-            .steps
-            .get(current_step)
-            .unwrap()
-            .clone(),
-    );
-    let step_satisfied = step.count.is_satisfied(step_index);
-    let upper_bound_met = step.count.upper_bound_met(step_index);
-
-    let mut new_rolling_result: TraversalStepRecursiveResult<T, E, A> = rolling_result.clone();
-    let mut new_exit_nodes = exit_nodes.clone();
-    let mut new_node = node.clone();
-    let mut is_successful_downstream_result = false;
-    let mut is_self_exit_node = false;
-    let mut new_visited_all = visited_all.clone();
-    let step_clone = step.clone();
-
-    //TODO: Handle cycles
-    // if visited_all.contains(&node.node.id) {
-    //     // Handle cycle, e.g., return an empty Vec.
-    //     todo!();
-    // }
-    // if visited_step.contains(&node.node.id) {
-    //     todo!();
-    // }
-    let mut new_visited_step = visited_step.clone();
-    new_visited_step.insert(node.node.id);
-
-    let matching_edges = node.node.search_for_edge(&step.edge_finder);
-
-    // If the upper bound is met, but the step is not satisfied, then we return none.
-    if upper_bound_met && !step_satisfied {
-        // No matching edges and step is unsatisfied: return a None to trigger backtracking.
-        return None;
+
+    // The arena node a step was entered on, if the remaining steps could be completed from it
+    fn outcome(&self, current_step: usize, node_id: Uid, traversal_index: usize) -> Option<usize> {
+        let key = ArenaKey {
+            current_step,
+            node_id,
+            step_index: 0,
+            traversal_index,
+        };
+        self.outcomes.get(&key).copied().flatten()
     }
 
-    // Checking for the case that this node should be added regardless of further intra-step operations
-    // That would be the case if
-    // - The step is satisfied on this node and
-    // 1. the count is inclusive
-    // 2. this is the upper bound of the count
-    // 3. the count is exclusive and there are no further matching edges to check
-    if step_satisfied
-        && (step.count.is_inclusive()
-            || upper_bound_met
-            || (step.count.is_exclusive() && matching_edges.is_none()))
-    {
-        let mut new_visited_all_inner = visited_all.clone();
-        new_visited_all_inner.extend(new_visited_step.clone());
-
-        let result = traverse_step(
-            vector![new_node.clone()],
-            traversal_descriptor,
-            current_step + 1,
-            new_visited_all_inner,
-            get_node,
-            new_rolling_result.clone(),
-        );
+    // Returns true if every remaining step can be completed when starting from this node
+    fn traverse_step(
+        &mut self,
+        current_step: usize,
+        node: Rc<ReadReactiveNode<T, E, A, L>>,
+        traversal_index: usize,
+    ) -> Option<()> {
+        if current_step >= self.steps.len() {
+            return Some(());
+        }
+        let key = ArenaKey {
+            current_step,
+            node_id: node.id,
+            step_index: 0,
+            traversal_index,
+        };
+        self.traverse_step_item(key, node, HashSet::new())?;
+        Some(())
+    }
 
-        if let Some(successful_completion) = result {
-            is_self_exit_node = true;
-            new_exit_nodes.push_back(new_node.clone());
-            new_rolling_result = successful_completion.clone();
-            new_visited_all.extend(successful_completion.visited_all);
+    fn traverse_step_item(
+        &mut self,
+        key: ArenaKey,
+        node: Rc<ReadReactiveNode<T, E, A, L>>,
+        visited_step: HashSet<Uid>,
+    ) -> Option<usize> {
+        if let Some(outcome) = self.outcomes.get(&key) {
+            return *outcome;
+        }
+        let step = self.steps[key.current_step].clone();
+        let route_skips = self.route_skips;
+        let outcome = self.explore(key, node, visited_step, &step);
+        // Steps which guard their route depend on the route taken if a node was skipped for being on it.
+        // The entry of a step starts a new route, so it can always be shared.
+        let is_memoizable =
+            !step.count.guards_route() || key.step_index == 0 || self.route_skips == route_skips;
+        if is_memoizable {
+            self.outcomes.insert(key, outcome);
         }
+        outcome
     }
 
-    if let Some(matching_edges) = matching_edges {
-        if !upper_bound_met {
-            // If there are successful results, we need to combine the visited_step and exit_nodes from each result into a single result
-            // In addition, we need to add the successful nodes to the current TraversalNode's downstream_edges
-            // We are building a result to return to the upstream node
+    fn explore(
+        &mut self,
+        key: ArenaKey,
+        node: Rc<ReadReactiveNode<T, E, A, L>>,
+        mut visited_step: HashSet<Uid>,
+        step: &Rc<TraversalStep<T, E, A, L>>,
+    ) -> Option<usize> {
+        if !self.budget.visit(node.id) {
+            return None;
+        }
+        self.visited_nodes.insert(node.id);
+        let trav_node = TraversalNode::new(node.clone(), key.step_index, key.traversal_index);
+        let step_satisfied = step.is_satisfied(&trav_node, key.step_index);
+        let upper_bound_met = step.upper_bound_met(&trav_node, key.step_index);
+
+        // If the upper bound is met, but the step is not satisfied, then this route can't succeed
+        if upper_bound_met && !step_satisfied {
+            return None;
+        }
+
+        let mut matching_edges = step.search_for_edges(&trav_node);
+        // Hops past the maximum depth are treated as if there were no further matching edges
+        if matching_edges.is_some()
+            && !upper_bound_met
+            && self.budget.depth_reached(key.traversal_index)
+        {
+            self.budget.mark_truncated();
+            matching_edges = None;
+        }
+
+        // The step can end on this node if it is satisfied and
+        // 1. the count is inclusive
+        // 2. this is the upper bound of the count
+        // 3. the count is exclusive and there are no further matching edges to check
+        // and the remaining steps can be completed from this node.
+        // Endpoints of the final step also count towards the maximum number of results
+        let is_final_step = key.current_step + 1 >= self.steps.len();
+        let is_exit = step_satisfied
+            && (step.count.is_inclusive()
+                || upper_bound_met
+                || (step.count.is_exclusive() && matching_edges.is_none()))
+            && (!is_final_step
+                || self.budget.record_result(EndpointKey {
+                    node_id: node.id,
+                    traversal_index: key.traversal_index,
+                }))
+            && self
+                .traverse_step(key.current_step + 1, node.clone(), key.traversal_index)
+                .is_some();
 
+        let mut children = Vec::new();
+        if let (Some(matching_edges), false) = (matching_edges, upper_bound_met) {
+            visited_step.insert(node.id);
             for edge in matching_edges.iter() {
-                let new_trav_node = TraversalNode::new(
-                    (get_node)(&edge.target).unwrap(),
-                    step_index + 1,
-                    traversal_index + 1,
-                )
-                .set_upstream_edge(UpstreamEdge::new(
-                    edge.clone(),
-                    step_clone.clone(),
-                    step_index,
-                    traversal_index,
-                ));
-                let edge_result = traverse_step_item(
-                    new_trav_node,
-                    current_step,
-                    visited_all.clone(),
-                    new_visited_step.clone(),
-                    step_index + 1,
-                    traversal_index + 1,
-                    new_exit_nodes.clone(),
-                    get_node,
-                    traversal_descriptor,
-                    new_rolling_result.clone(),
+                // Steps without an upper bound would never terminate on a cycle
+                if step.count.guards_route() && visited_step.contains(&edge.target) {
+                    self.route_skips += 1;
+                    continue;
+                }
+                if self.excluded_nodes.contains(&edge.target) {
+                    continue;
+                }
+                let Ok(target) = (self.get_node)(&edge.target) else {
+                    continue;
+                };
+                let child_key = ArenaKey {
+                    node_id: target.id,
+                    step_index: key.step_index + 1,
+                    traversal_index: key.traversal_index + 1,
+                    ..key
+                };
+                let target_trav_node = TraversalNode::new(
+                    target.clone(),
+                    child_key.step_index,
+                    child_key.traversal_index,
                 );
-                if let Some(successful_branch) = edge_result {
-                    let mut new_visited_all_inner = visited_all.clone();
-                    new_visited_all_inner.extend(new_visited_step.clone());
-
-                    let result = traverse_step(
-                        successful_branch.exit_nodes.clone(),
-                        traversal_descriptor,
-                        current_step + 1,
-                        new_visited_all_inner,
-                        get_node,
-                        new_rolling_result.clone(),
-                    );
-                    if let Some(successful_completion) = result {
-                        is_successful_downstream_result = true;
-
-                        new_visited_step.extend(successful_branch.nodes_visited_this_step);
-                        // new_exit_nodes.extend(successful_branch.exit_nodes);
-                        new_exit_nodes = successful_branch.exit_nodes;
-                        new_node = new_node.add_downstream_edge(TraversalEdge::new(
-                            edge.clone(),
-                            step.clone(),
-                            successful_branch.result,
-                            step_index,
-                            traversal_index,
-                        ));
-
-                        new_rolling_result = successful_completion.clone();
-                        new_visited_all.extend(successful_completion.visited_all);
-                    }
+                if !step.accepts_node(&target_trav_node) {
+                    continue;
+                }
+                if let Some(child) =
+                    self.traverse_step_item(child_key, target, visited_step.clone())
+                {
+                    children.push((edge.clone(), child));
                 }
             }
         }
+
+        if !is_exit && children.is_empty() {
+            return None;
+        }
+        self.arena.push(ArenaNode {
+            node,
+            step_index: key.step_index,
+            traversal_index: key.traversal_index,
+            is_exit,
+            children,
+        });
+        Some(self.arena.len() - 1)
     }
 
-    // If there were no successful results after searching the matching edges, then we need to stop the recursion and return a None to trigger backtracking.
-    // This means that the recursive function has run down into the branch and has ultimately found no routes to an exit
-    // We propagate that negative finding upward
-    if !is_successful_downstream_result && !is_self_exit_node {
-        return None;
+    fn build_step_result(
+        &self,
+        current_step: usize,
+        entry: usize,
+        upstream_edge: Option<UpstreamEdge<T, E, A, L>>,
+    ) -> TraversalStepResult<T, E, A, L> {
+        let step = &self.steps[current_step];
+        let mut endpoints = Vector::new();
+        let entry = self.build_traversal_node(entry, step, upstream_edge, &mut endpoints);
+        // Routes which merge reach the same endpoints, which are only listed once
+        let mut seen = HashSet::new();
+        endpoints.retain(|endpoint| seen.insert(EndpointKey::from_endpoint(endpoint)).is_none());
+        TraversalStepResult { entry, endpoints }
     }
 
-    Some(TraversalStepItemRecursiveResult {
-        result: new_node,
-        nodes_visited_this_step: new_visited_step,
-        exit_nodes: new_exit_nodes,
-        rolling_result: new_rolling_result,
-    })
+    // Routes which merge within a step share an arena node, so they are expanded into separate branches here
+    fn build_traversal_node(
+        &self,
+        index: usize,
+        step: &Rc<TraversalStep<T, E, A, L>>,
+        upstream_edge: Option<UpstreamEdge<T, E, A, L>>,
+        endpoints: &mut Vector<TraversalNode<T, E, A, L>>,
+    ) -> TraversalNode<T, E, A, L> {
+        let arena_node = &self.arena[index];
+        let mut trav_node = TraversalNode::new(
+            arena_node.node.clone(),
+            arena_node.step_index,
+            arena_node.traversal_index,
+        );
+        if let Some(upstream_edge) = upstream_edge {
+            trav_node = trav_node.set_upstream_edge(upstream_edge);
+        }
+        if arena_node.is_exit {
+            endpoints.push_back(trav_node.clone());
+        }
+        for (edge, child) in arena_node.children.iter() {
+            let child_node = self.build_traversal_node(
+                *child,
+                step,
+                Some(UpstreamEdge::new(
+                    edge.clone(),
+                    step.clone(),
+                    arena_node.step_index,
+                    arena_node.traversal_index,
+                )),
+                endpoints,
+            );
+            trav_node = trav_node.add_downstream_edge(TraversalEdge::new(
+                edge.clone(),
+                step.clone(),
+                child_node,
+                arena_node.step_index,
+                arena_node.traversal_index,
+            ));
+        }
+        trav_node
+    }
 }
@@ -0,0 +1,154 @@
+use std::cell::{Cell, RefCell};
+
+use im::HashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+use super::traversal_page::EndpointKey;
+
+// Caps on how much work a single traversal is allowed to do
+// Any limit which is hit marks the TraversalResult as truncated
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct TraversalLimits {
+    // Maximum number of hops from the start node, across all steps
+    pub max_depth: Option<usize>,
+    // Maximum number of distinct nodes the traversal may visit
+    pub max_visited: Option<usize>,
+    // Maximum number of endpoints found by the final step
+    pub max_results: Option<usize>,
+    // Maximum number of nodes which may be expanded, including repeat visits of the same node
+    // Used in place of a wall clock budget, since timers are not available on every target
+    pub max_operations: Option<usize>,
+}
+
+impl TraversalLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_depth(&self, max_depth: usize) -> Self {
+        Self {
+            max_depth: Some(max_depth),
+            ..self.clone()
+        }
+    }
+
+    pub fn max_visited(&self, max_visited: usize) -> Self {
+        Self {
+            max_visited: Some(max_visited),
+            ..self.clone()
+        }
+    }
+
+    pub fn max_results(&self, max_results: usize) -> Self {
+        Self {
+            max_results: Some(max_results),
+            ..self.clone()
+        }
+    }
+
+    pub fn max_operations(&self, max_operations: usize) -> Self {
+        Self {
+            max_operations: Some(max_operations),
+            ..self.clone()
+        }
+    }
+}
+
+// Tracks the work done against the TraversalLimits during a single execution
+// Shared by reference through the recursive executor, so the counters use interior mutability
+pub(crate) struct TraversalBudget {
+    limits: TraversalLimits,
+    visited: RefCell<HashSet<Uid>>,
+    operations: Cell<usize>,
+    results: RefCell<HashSet<EndpointKey>>,
+    truncated: Cell<bool>,
+}
+
+impl TraversalBudget {
+    pub fn new(limits: TraversalLimits) -> Self {
+        Self {
+            limits,
+            visited: RefCell::new(HashSet::new()),
+            operations: Cell::new(0),
+            results: RefCell::new(HashSet::new()),
+            truncated: Cell::new(false),
+        }
+    }
+
+    // Records a visit to the node, returning false if the node may not be expanded
+    pub fn visit(&self, node_id: Uid) -> bool {
+        if self.results_exhausted() {
+            self.truncated.set(true);
+            return false;
+        }
+        let operations = self.operations.get() + 1;
+        if self
+            .limits
+            .max_operations
+            .is_some_and(|max| operations > max)
+        {
+            self.truncated.set(true);
+            return false;
+        }
+        self.operations.set(operations);
+
+        let mut visited = self.visited.borrow_mut();
+        if !visited.contains(&node_id)
+            && self
+                .limits
+                .max_visited
+                .is_some_and(|max| visited.len() >= max)
+        {
+            self.truncated.set(true);
+            return false;
+        }
+        visited.insert(node_id);
+        true
+    }
+
+    // Returns true if hops beyond this depth are not allowed
+    pub fn depth_reached(&self, traversal_index: usize) -> bool {
+        self.limits
+            .max_depth
+            .is_some_and(|max| traversal_index >= max)
+    }
+
+    // Returns false if the maximum number of results has already been recorded
+    // An endpoint reached along several routes only counts once
+    pub fn record_result(&self, endpoint: EndpointKey) -> bool {
+        if self.results.borrow().contains(&endpoint) {
+            return true;
+        }
+        if self.results_exhausted() {
+            self.truncated.set(true);
+            return false;
+        }
+        self.results.borrow_mut().insert(endpoint);
+        true
+    }
+
+    pub fn mark_truncated(&self) {
+        self.truncated.set(true);
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated.get()
+    }
+
+    // Whether a limit was hit since the last call, so the flag can be kept per start node
+    pub fn take_truncated(&self) -> bool {
+        self.truncated.replace(false)
+    }
+
+    pub fn can_record_result(&self) -> bool {
+        !self.results_exhausted()
+    }
+
+    fn results_exhausted(&self) -> bool {
+        self.limits
+            .max_results
+            .is_some_and(|max| self.results.borrow().len() >= max)
+    }
+}
@@ -7,17 +7,17 @@ use crate::prelude::{reactive_node::read_reactive_node::ReadReactiveNode, *};
 use super::traversal_edge::{TraversalEdge, UpstreamEdge};
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct TraversalNode<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub node: Rc<ReadReactiveNode<T, E, A>>,
+pub struct TraversalNode<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub node: Rc<ReadReactiveNode<T, E, A, L>>,
     pub step_index: usize,
     pub traversal_index: usize,
-    pub downstream_edges: Vector<TraversalEdge<T, E, A>>,
-    pub upstream_edge: Option<UpstreamEdge<T, E, A>>,
+    pub downstream_edges: Vector<TraversalEdge<T, E, A, L>>,
+    pub upstream_edge: Option<UpstreamEdge<T, E, A, L>>,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalNode<T, E, A> {
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> TraversalNode<T, E, A, L> {
     pub fn new(
-        node: Rc<ReadReactiveNode<T, E, A>>,
+        node: Rc<ReadReactiveNode<T, E, A, L>>,
         step_index: usize,
         traversal_index: usize,
     ) -> Self {
@@ -30,7 +30,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalNode<T, E, A> {
         }
     }
 
-    pub fn add_downstream_edge(&self, edge: TraversalEdge<T, E, A>) -> Self {
+    pub fn add_downstream_edge(&self, edge: TraversalEdge<T, E, A, L>) -> Self {
         let mut new_downstream = self.downstream_edges.clone();
         new_downstream.push_back(edge);
         Self {
@@ -38,7 +38,7 @@ impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalNode<T, E, A> {
             ..self.clone()
         }
     }
-    pub fn set_upstream_edge(&self, edge: UpstreamEdge<T, E, A>) -> Self {
+    pub fn set_upstream_edge(&self, edge: UpstreamEdge<T, E, A, L>) -> Self {
         Self {
             upstream_edge: Some(edge),
             ..self.clone()
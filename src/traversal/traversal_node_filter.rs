@@ -0,0 +1,169 @@
+use core::fmt::Debug;
+use core::hash::Hash;
+use std::rc::Rc;
+
+use im::Vector;
+use leptos::*;
+
+use crate::prelude::{reactive_node::read_reactive_node::ReadReactiveNode, *};
+
+use super::traversal_node::TraversalNode;
+
+// A predicate over node data. The key is what makes the predicate comparable and hashable,
+// so two predicates registered under the same key are considered the same predicate.
+#[derive(Clone)]
+pub struct DataPredicate<T: GraphTraits> {
+    pub key: String,
+    pub predicate: Rc<dyn Fn(&T) -> bool>,
+    // Only predicates taken from a PredicateRegistry can be saved, since binding looks the key up there
+    registered: bool,
+}
+
+impl<T: GraphTraits> DataPredicate<T> {
+    pub fn new(key: impl Into<String>, predicate: impl Fn(&T) -> bool + 'static) -> Self {
+        Self {
+            key: key.into(),
+            predicate: Rc::new(predicate),
+            registered: false,
+        }
+    }
+
+    pub(crate) fn registered(
+        key: impl Into<String>,
+        predicate: impl Fn(&T) -> bool + 'static,
+    ) -> Self {
+        Self {
+            registered: true,
+            ..Self::new(key, predicate)
+        }
+    }
+
+    pub fn is_registered(&self) -> bool {
+        self.registered
+    }
+
+    // Fails if the predicate wasn't taken from a PredicateRegistry
+    pub fn to_spec(&self) -> Result<String, GraphError> {
+        match self.registered {
+            true => Ok(self.key.clone()),
+            false => Err(GraphError::Traversal(
+                TraversalError::UnserializablePredicate,
+            )),
+        }
+    }
+
+    pub fn matches(&self, data: &T) -> bool {
+        (self.predicate)(data)
+    }
+}
+
+impl<T: GraphTraits> PartialEq for DataPredicate<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+impl<T: GraphTraits> Eq for DataPredicate<T> {}
+impl<T: GraphTraits> Hash for DataPredicate<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+impl<T: GraphTraits> Debug for DataPredicate<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataPredicate")
+            .field("key", &self.key)
+            .field("registered", &self.registered)
+            .finish()
+    }
+}
+
+// Filters which are run against the node at the end of each hop within a traversal step
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum NodeFilter<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    HasLabel(L),
+    Data(DataPredicate<T>),
+    // Note that the edge finder is run from the perspective of the filtered node
+    HasEdge(Box<EdgeFinder<T, E, A, L>>),
+    All(Vector<NodeFilter<T, E, A, L>>),
+    Any(Vector<NodeFilter<T, E, A, L>>),
+    Not(Box<NodeFilter<T, E, A, L>>),
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> NodeFilter<T, E, A, L> {
+    pub fn has_label(label: impl Into<L>) -> Self {
+        Self::HasLabel(label.into())
+    }
+
+    pub fn lacks_label(label: impl Into<L>) -> Self {
+        Self::has_label(label).not()
+    }
+
+    // Can't be saved with to_spec, use PredicateRegistry::data_filter for filters which need to be
+    pub fn data(key: impl Into<String>, predicate: impl Fn(&T) -> bool + 'static) -> Self {
+        Self::Data(DataPredicate::new(key, predicate))
+    }
+
+    pub fn has_edge(edge_finder: EdgeFinder<T, E, A, L>) -> Self {
+        Self::HasEdge(Box::new(edge_finder))
+    }
+
+    pub fn and(&self, other: Self) -> Self {
+        match self {
+            Self::All(filters) => {
+                let mut new_filters = filters.clone();
+                new_filters.push_back(other);
+                Self::All(new_filters)
+            }
+            _ => Self::All(Vector::from(vec![self.clone(), other])),
+        }
+    }
+
+    pub fn or(&self, other: Self) -> Self {
+        match self {
+            Self::Any(filters) => {
+                let mut new_filters = filters.clone();
+                new_filters.push_back(other);
+                Self::Any(new_filters)
+            }
+            _ => Self::Any(Vector::from(vec![self.clone(), other])),
+        }
+    }
+
+    pub fn not(&self) -> Self {
+        Self::Not(Box::new(self.clone()))
+    }
+
+    // Binds the edge finders of any HasEdge filters, see EdgeFinder::bind_graph
+    pub fn bind_graph(&self, get_node_closure: &GetNodeClosure<T, E, A, L>) -> Self {
+        let bind_all = |filters: &Vector<NodeFilter<T, E, A, L>>| {
+            filters
+                .iter()
+                .map(|filter| filter.bind_graph(get_node_closure))
+                .collect()
+        };
+        match self {
+            Self::HasEdge(edge_finder) => {
+                Self::HasEdge(Box::new(edge_finder.bind_graph(get_node_closure)))
+            }
+            Self::All(filters) => Self::All(bind_all(filters)),
+            Self::Any(filters) => Self::Any(bind_all(filters)),
+            Self::Not(filter) => Self::Not(Box::new(filter.bind_graph(get_node_closure))),
+            Self::HasLabel(_) | Self::Data(_) => self.clone(),
+        }
+    }
+
+    pub fn matches(&self, node: &ReadReactiveNode<T, E, A, L>) -> bool {
+        match self {
+            Self::HasLabel(label) => node.labels.with_untracked(|labels| labels.contains(label)),
+            Self::Data(predicate) => node.data.with_untracked(|data| predicate.matches(data)),
+            Self::HasEdge(edge_finder) => node.search_for_edge(edge_finder).is_some(),
+            Self::All(filters) => filters.iter().all(|filter| filter.matches(node)),
+            Self::Any(filters) => filters.iter().any(|filter| filter.matches(node)),
+            Self::Not(filter) => !filter.matches(node),
+        }
+    }
+
+    pub fn matches_traversal_node(&self, node: &TraversalNode<T, E, A, L>) -> bool {
+        self.matches(&node.node)
+    }
+}
@@ -0,0 +1,55 @@
+use im::Vector;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+// Where a traversal starts. Labels are resolved each time the traversal is executed.
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Serialize, Deserialize)]
+pub enum TraversalOrigin<L: GraphTraits = String> {
+    Node(Uid),
+    Nodes(Vector<Uid>),
+    Label(L),
+}
+
+impl<L: GraphTraits> TraversalOrigin<L> {
+    pub fn nodes(ids: impl IntoIterator<Item = Uid>) -> Self {
+        Self::Nodes(ids.into_iter().collect())
+    }
+
+    pub fn label(label: impl Into<L>) -> Self {
+        Self::Label(label.into())
+    }
+
+    // Duplicate ids are only started from once, in the order they were first given
+    // A label can't be resolved without a closure to look it up, so it resolves to no nodes
+    pub fn resolve(&self, get_label_closure: Option<&GetLabelClosure<L>>) -> Vector<Uid> {
+        let ids = match self {
+            Self::Node(id) => Vector::unit(*id),
+            Self::Nodes(ids) => ids.clone(),
+            Self::Label(label) => get_label_closure
+                .map(|get_label_closure| get_label_closure(label))
+                .unwrap_or_default(),
+        };
+        let mut seen = im::HashSet::new();
+        ids.into_iter()
+            .filter(|id| seen.insert(*id).is_none())
+            .collect()
+    }
+}
+
+impl<L: GraphTraits> From<Uid> for TraversalOrigin<L> {
+    fn from(id: Uid) -> Self {
+        Self::Node(id)
+    }
+}
+
+// Controls whether the traversals from each start node can see each other's nodes
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum VisitedScope {
+    // Every start node is traversed as if it were the only one
+    #[default]
+    PerStart,
+    // Nodes visited from an earlier start node are skipped by every later one,
+    // and start nodes which have already been visited are not traversed at all
+    Shared,
+}
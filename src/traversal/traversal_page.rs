@@ -0,0 +1,289 @@
+use std::rc::Rc;
+
+use im::{HashMap, HashSet, Vector};
+
+use crate::prelude::*;
+
+use super::{
+    traversal_aggregate::AggregateScope, traversal_descriptor::TraversalDescriptor,
+    traversal_edge::UpstreamEdge, traversal_limits::TraversalBudget, traversal_node::TraversalNode,
+    traversal_origin::VisitedScope, traversal_result::TraversalResult,
+    traversal_step::TraversalStep,
+};
+
+// Identifies an endpoint by its node and traversal index. Aggregations list endpoints in this order.
+#[derive(Clone, Copy, PartialEq, Debug, Eq, Hash, PartialOrd, Ord)]
+pub struct EndpointKey {
+    pub node_id: Uid,
+    pub traversal_index: usize,
+}
+
+impl EndpointKey {
+    pub fn from_endpoint<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>(
+        endpoint: &TraversalNode<T, E, A, L>,
+    ) -> Self {
+        Self {
+            node_id: endpoint.node.id,
+            traversal_index: endpoint.traversal_index,
+        }
+    }
+}
+
+// The edge a frontier node was reached by, which belongs to the previous step if the node is a step entry
+#[derive(Clone, PartialEq, Debug, Eq)]
+struct FrontierEdge<E: EdgeTraits> {
+    edge: EdgeDescriptor<E>,
+    current_step: usize,
+    step_index: usize,
+}
+
+// A node waiting to be explored within a step
+#[derive(Clone, PartialEq, Debug, Eq)]
+struct FrontierItem<E: EdgeTraits> {
+    // Position of the start node in the resolved origin
+    start_index: usize,
+    current_step: usize,
+    node_id: Uid,
+    step_index: usize,
+    traversal_index: usize,
+    upstream_edge: Option<FrontierEdge<E>>,
+    // Nodes already on this route within the step, only tracked for steps which could otherwise cycle forever
+    route: HashSet<Uid>,
+}
+
+// (step, node, step index, traversal index)
+type ExpandedKey = (usize, Uid, usize, usize);
+
+// Where the next page of a traversal continues from.
+// Holds the nodes still to be explored, in the order they will be explored, so each page only does the work needed to fill it.
+// Edges are followed in order of target id and then edge id, so the same graph always gives the same pages.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct TraversalCursor<E: EdgeTraits> {
+    // Used as a stack, the next node to explore is at the back
+    frontier: Vector<FrontierItem<E>>,
+    // Nodes whose route doesn't affect what they lead to are only explored once
+    expanded: HashSet<ExpandedKey>,
+    emitted: HashSet<EndpointKey>,
+    // With VisitedScope::Shared, the first start node which reached each node
+    claimed: HashMap<Uid, usize>,
+}
+
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub struct TraversalPage<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub endpoints: Vector<TraversalNode<T, E, A, L>>,
+    // None once there is nothing left to explore. The last page can be empty if the remaining routes had no endpoints.
+    pub next_cursor: Option<TraversalCursor<E>>,
+    // True if a TraversalLimit cut this page short
+    pub truncated: bool,
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> TraversalResult<T, E, A, L> {
+    // The endpoints of the final step, with one entry per node and traversal index, ordered by EndpointKey
+    pub fn final_endpoints(&self) -> Vector<TraversalNode<T, E, A, L>> {
+        match self.step_results.len() {
+            0 => Vector::new(),
+            num_steps => self.endpoints_in(AggregateScope::Step(num_steps - 1)),
+        }
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>
+    TraversalDescriptor<T, E, A, L>
+{
+    // Fetches final endpoints a page at a time, starting with a cursor of None.
+    // Pages are listed in the order the routes are explored, with one entry per node and traversal index.
+    // The TraversalLimits apply to the work done for each page. A page cut short by a limit is marked truncated,
+    // and the next page picks up where it stopped. Depth limits apply to every page.
+    pub fn execute_page(
+        &self,
+        after: Option<TraversalCursor<E>>,
+        page_size: usize,
+    ) -> TraversalPage<T, E, A, L> {
+        let mut cursor = after.unwrap_or_else(|| self.start_cursor());
+        let steps = self.steps.iter().cloned().map(Rc::new).collect::<Vec<_>>();
+        let budget = TraversalBudget::new(self.limits.clone());
+        let mut endpoints = Vector::new();
+
+        while endpoints.len() < page_size && !steps.is_empty() {
+            let Some(item) = cursor.frontier.pop_back() else {
+                break;
+            };
+            if !budget.can_record_result() {
+                cursor.frontier.push_back(item);
+                budget.mark_truncated();
+                break;
+            }
+            if !self.explore_frontier_item(&mut cursor, &item, &steps, &budget, &mut endpoints) {
+                // A limit was hit before the node could be explored, so the next page starts with it
+                cursor.frontier.push_back(item);
+                break;
+            }
+        }
+
+        TraversalPage {
+            endpoints,
+            next_cursor: (!cursor.frontier.is_empty() && !steps.is_empty()).then_some(cursor),
+            truncated: budget.is_truncated(),
+        }
+    }
+
+    fn start_cursor(&self) -> TraversalCursor<E> {
+        let start_ids = self.origin.resolve(self.get_label_closure.as_ref());
+        TraversalCursor {
+            frontier: start_ids
+                .into_iter()
+                .enumerate()
+                .rev()
+                .map(|(start_index, node_id)| FrontierItem {
+                    start_index,
+                    current_step: 0,
+                    node_id,
+                    step_index: 0,
+                    traversal_index: 0,
+                    upstream_edge: None,
+                    route: HashSet::new(),
+                })
+                .collect(),
+            expanded: HashSet::new(),
+            emitted: HashSet::new(),
+            claimed: HashMap::new(),
+        }
+    }
+
+    // Follows the same rules as the TraversalExecutor, one node at a time.
+    // Returns false if the node may not be explored within this page's budget.
+    fn explore_frontier_item(
+        &self,
+        cursor: &mut TraversalCursor<E>,
+        item: &FrontierItem<E>,
+        steps: &[Rc<TraversalStep<T, E, A, L>>],
+        budget: &TraversalBudget,
+        endpoints: &mut Vector<TraversalNode<T, E, A, L>>,
+    ) -> bool {
+        let step = &steps[item.current_step];
+        let guards_route = step.count.guards_route();
+        let key = (
+            item.current_step,
+            item.node_id,
+            item.step_index,
+            item.traversal_index,
+        );
+        let is_shareable = !guards_route || item.step_index == 0;
+        if is_shareable && cursor.expanded.contains(&key) {
+            return true;
+        }
+        let is_shared_scope = self.visited_scope == VisitedScope::Shared;
+        let claimed_earlier = |cursor: &TraversalCursor<E>, node_id: &Uid| {
+            is_shared_scope
+                && cursor
+                    .claimed
+                    .get(node_id)
+                    .is_some_and(|start_index| *start_index < item.start_index)
+        };
+        let is_start = item.current_step == 0 && item.traversal_index == 0;
+        if is_start && claimed_earlier(cursor, &item.node_id) {
+            return true;
+        }
+        let Ok(node) = (self.get_node_closure)(&item.node_id) else {
+            return true;
+        };
+        if !budget.visit(node.id) {
+            return false;
+        }
+        if is_shareable {
+            cursor.expanded.insert(key);
+        }
+        if is_shared_scope {
+            cursor.claimed.entry(node.id).or_insert(item.start_index);
+        }
+
+        let trav_node = TraversalNode::new(node.clone(), item.step_index, item.traversal_index);
+        let step_satisfied = step.is_satisfied(&trav_node, item.step_index);
+        let upper_bound_met = step.upper_bound_met(&trav_node, item.step_index);
+        if upper_bound_met && !step_satisfied {
+            return true;
+        }
+        let mut matching_edges = step.search_for_edges(&trav_node);
+        if matching_edges.is_some()
+            && !upper_bound_met
+            && budget.depth_reached(item.traversal_index)
+        {
+            budget.mark_truncated();
+            matching_edges = None;
+        }
+        let is_exit = step_satisfied
+            && (step.count.is_inclusive()
+                || upper_bound_met
+                || (step.count.is_exclusive() && matching_edges.is_none()));
+        let is_final_step = item.current_step + 1 >= steps.len();
+
+        // Collected in the order they should be explored
+        let mut next_items = Vec::new();
+        if is_exit && is_final_step {
+            let endpoint_key = EndpointKey::from_endpoint(&trav_node);
+            if !cursor.emitted.contains(&endpoint_key) && budget.record_result(endpoint_key) {
+                cursor.emitted.insert(endpoint_key);
+                endpoints.push_back(page_endpoint(trav_node, item, steps));
+            }
+        } else if is_exit {
+            next_items.push(FrontierItem {
+                current_step: item.current_step + 1,
+                step_index: 0,
+                route: HashSet::new(),
+                ..item.clone()
+            });
+        }
+
+        if let (Some(matching_edges), false) = (matching_edges, upper_bound_met) {
+            let mut matching_edges = matching_edges.into_iter().collect::<Vec<_>>();
+            matching_edges.sort_by_key(|edge| (edge.target, edge.id));
+            let mut route = item.route.clone();
+            if guards_route {
+                route.insert(node.id);
+            }
+            for edge in matching_edges {
+                if route.contains(&edge.target) || claimed_earlier(cursor, &edge.target) {
+                    continue;
+                }
+                let Ok(target) = (self.get_node_closure)(&edge.target) else {
+                    continue;
+                };
+                let target_trav_node =
+                    TraversalNode::new(target, item.step_index + 1, item.traversal_index + 1);
+                if !step.accepts_node(&target_trav_node) {
+                    continue;
+                }
+                next_items.push(FrontierItem {
+                    node_id: edge.target,
+                    step_index: item.step_index + 1,
+                    traversal_index: item.traversal_index + 1,
+                    upstream_edge: Some(FrontierEdge {
+                        edge,
+                        current_step: item.current_step,
+                        step_index: item.step_index,
+                    }),
+                    route: route.clone(),
+                    ..item.clone()
+                });
+            }
+        }
+        cursor.frontier.extend(next_items.into_iter().rev());
+        true
+    }
+}
+
+fn page_endpoint<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>(
+    trav_node: TraversalNode<T, E, A, L>,
+    item: &FrontierItem<E>,
+    steps: &[Rc<TraversalStep<T, E, A, L>>],
+) -> TraversalNode<T, E, A, L> {
+    match &item.upstream_edge {
+        Some(upstream_edge) => trav_node.set_upstream_edge(UpstreamEdge::new(
+            upstream_edge.edge.clone(),
+            steps[upstream_edge.current_step].clone(),
+            upstream_edge.step_index,
+            item.traversal_index - 1,
+        )),
+        None => trav_node,
+    }
+}
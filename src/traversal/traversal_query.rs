@@ -0,0 +1,60 @@
+use im::Vector;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+use super::{
+    traversal_descriptor::TraversalDescriptor,
+    traversal_limits::TraversalLimits,
+    traversal_origin::{TraversalOrigin, VisitedScope},
+    traversal_step_spec::TraversalStepSpec,
+};
+
+// A saved traversal which holds no closures, so it can be serialized and stored anywhere
+// Binding it to a graph resolves every predicate key through a PredicateRegistry
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Serialize, Deserialize)]
+pub struct TraversalQuery<E: EdgeTraits, L: GraphTraits = String> {
+    pub origin: TraversalOrigin<L>,
+    pub visited_scope: VisitedScope,
+    pub steps: Vector<TraversalStepSpec<E, L>>,
+    pub limits: TraversalLimits,
+}
+
+impl<E: EdgeTraits, L: GraphTraits> TraversalQuery<E, L> {
+    pub fn bind<T: GraphTraits, A: GraphTraits>(
+        &self,
+        routable: &UseRoutableReturn<T, E, A, L>,
+        registry: &PredicateRegistry<T, E, A, L>,
+    ) -> Result<TraversalDescriptor<T, E, A, L>, GraphError> {
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| step.bind(registry, &routable.get_node_closure))
+            .collect::<Result<Vector<_>, _>>()?;
+        Ok(TraversalDescriptor {
+            steps,
+            ..routable
+                .traverse_search_from(self.origin.clone())
+                .visited_scope(self.visited_scope)
+                .with_limits(self.limits.clone())
+        })
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>
+    TraversalDescriptor<T, E, A, L>
+{
+    // Fails if any step uses a gate closure, since there is no key to save it under
+    pub fn to_query(&self) -> Result<TraversalQuery<E, L>, GraphError> {
+        Ok(TraversalQuery {
+            origin: self.origin.clone(),
+            visited_scope: self.visited_scope,
+            steps: self
+                .steps
+                .iter()
+                .map(|step| step.to_spec())
+                .collect::<Result<_, _>>()?,
+            limits: self.limits.clone(),
+        })
+    }
+}
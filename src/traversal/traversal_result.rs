@@ -1,24 +1,33 @@
+use std::iter;
+
 use crate::prelude::*;
 use im::Vector;
 
-use super::{traversal_step_result::TraversalStepResult};
+use super::traversal_step_result::TraversalStepResult;
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct TraversalResult<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    // pub full_result: TraversalNode<T, E, A>,
-    pub step_results: Vector<Vector<TraversalStepResult<T, E, A>>>,
+pub struct TraversalResult<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    // pub full_result: TraversalNode<T, E, A, L>,
+    pub step_results: Vector<Vector<TraversalStepResult<T, E, A, L>>>,
+    pub step_names: Vector<Option<String>>,
+    // True if a TraversalLimit was hit, so there may be matches which are missing from the result
+    pub truncated: bool,
 }
 
-impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalResult<T, E, A> {
+impl<'a, T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>
+    TraversalResult<T, E, A, L>
+{
     pub fn new(num_steps: usize) -> Self {
         let mut vec = Vec::with_capacity(num_steps);
         for _ in 0..num_steps {
-            vec.push(Vector::<TraversalStepResult<T, E, A>>::new());
+            vec.push(Vector::<TraversalStepResult<T, E, A, L>>::new());
         }
         let step_results = Vector::from(vec);
         Self {
             // full_result: start_node,
             step_results,
+            step_names: iter::repeat_n(None, num_steps).collect(),
+            truncated: false,
         }
     }
 
@@ -34,7 +43,7 @@ impl<'a, T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalResult<T, E, A
     // pub fn add_step_result(
     //     &self,
     //     step_index: usize,
-    //     step_result: TraversalStepResult<T, E, A>,
+    //     step_result: TraversalStepResult<T, E, A, L>,
     // ) -> Self {
     //     let mut new_step_results = self.step_results.clone();
     //     let mut new_step_items = new_step_results[step_index].clone();
@@ -1,16 +1,28 @@
-use crate::prelude::{*};
+use std::iter;
 
+use im::{HashSet, Vector};
+use serde::{Deserialize, Serialize};
 
+use crate::prelude::*;
 
-#[derive(Clone, PartialEq, Debug, Eq, Hash)]
+use super::{traversal_node::TraversalNode, traversal_node_filter::NodeFilter};
+
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Serialize, Deserialize)]
 pub enum TraversalCount {
     /// Only returns the final node in a chain which is at least this long
+    /// Will not revisit nodes within the step
     AtLeastExclusive(usize),
     /// Only returns every node in a chain which is at least this long
+    /// Will not revisit nodes within the step
     AtLeastInclusive(usize),
     BetweenExclusive(usize, usize),
     BetweenInclusive(usize, usize),
     Exactly(usize),
+    /// Returns the entry node as well as the node one hop away, if there is a matching edge
+    Optional,
+    /// Repeats until a node matching the step's `until` filter is reached, and only returns that node
+    /// Requires at least one hop, and will not revisit nodes within the step
+    RepeatUntil,
 }
 
 impl TraversalCount {
@@ -22,6 +34,9 @@ impl TraversalCount {
             TraversalCount::BetweenExclusive(min, max)
             | TraversalCount::BetweenInclusive(min, max) => count >= *min && count <= *max,
             TraversalCount::Exactly(exact) => count == *exact,
+            TraversalCount::Optional => count <= 1,
+            // Depends on the node, so it is handled by the TraversalStep
+            TraversalCount::RepeatUntil => false,
         }
     }
     pub fn upper_bound_met(&self, count: usize) -> bool {
@@ -31,14 +46,28 @@ impl TraversalCount {
                 count == *max
             }
             TraversalCount::Exactly(exact) => count == *exact,
+            TraversalCount::Optional => count == 1,
+            TraversalCount::RepeatUntil => false,
         }
     }
     pub fn is_inclusive(&self) -> bool {
         match self {
-            TraversalCount::AtLeastInclusive(_) | TraversalCount::BetweenInclusive(_, _) => true,
+            TraversalCount::AtLeastInclusive(_)
+            | TraversalCount::BetweenInclusive(_, _)
+            | TraversalCount::Optional => true,
             _ => false,
         }
     }
+    // Counts without an upper bound don't revisit a node already on the route within the step,
+    // so they can't cycle forever
+    pub fn guards_route(&self) -> bool {
+        matches!(
+            self,
+            TraversalCount::AtLeastExclusive(_)
+                | TraversalCount::AtLeastInclusive(_)
+                | TraversalCount::RepeatUntil
+        )
+    }
     pub fn is_exclusive(&self) -> bool {
         match self {
             TraversalCount::AtLeastExclusive(_) | TraversalCount::BetweenExclusive(_, _) => true,
@@ -48,22 +77,148 @@ impl TraversalCount {
 }
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct TraversalStep<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub edge_finder: EdgeFinder<T, E, A>,
+pub struct TraversalStep<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits = String> {
+    pub edge_finder: EdgeFinder<T, E, A, L>,
+    // Edges matching any of these finders are followed in addition to those matching the edge_finder
+    pub alternative_edge_finders: Vector<EdgeFinder<T, E, A, L>>,
     pub count: TraversalCount,
+    // Run against every node reached by this step. Branches which reach a node failing the filter are pruned.
+    pub node_filter: Option<NodeFilter<T, E, A, L>>,
+    // Only used with TraversalCount::RepeatUntil
+    pub until: Option<NodeFilter<T, E, A, L>>,
+    // Used as the key for this step's nodes in TraversalResult::bindings
+    pub name: Option<String>,
 }
 
-impl<T: GraphTraits, E: GraphTraits, A: GraphTraits> TraversalStep<T, E, A> {
-    pub fn new(edge_finder: EdgeFinder<T, E, A>, count: TraversalCount) -> Self {
-        Self { edge_finder, count }
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> TraversalStep<T, E, A, L> {
+    // Binds every edge finder used by the step, see EdgeFinder::bind_graph
+    pub fn bind_graph(&self, get_node_closure: &GetNodeClosure<T, E, A, L>) -> Self {
+        Self {
+            edge_finder: self.edge_finder.bind_graph(get_node_closure),
+            alternative_edge_finders: self
+                .alternative_edge_finders
+                .iter()
+                .map(|edge_finder| edge_finder.bind_graph(get_node_closure))
+                .collect(),
+            node_filter: self
+                .node_filter
+                .as_ref()
+                .map(|filter| filter.bind_graph(get_node_closure)),
+            until: self
+                .until
+                .as_ref()
+                .map(|filter| filter.bind_graph(get_node_closure)),
+            ..self.clone()
+        }
+    }
+
+    pub fn new(edge_finder: EdgeFinder<T, E, A, L>, count: TraversalCount) -> Self {
+        Self {
+            edge_finder,
+            alternative_edge_finders: Vector::new(),
+            count,
+            node_filter: None,
+            until: None,
+            name: None,
+        }
+    }
+
+    pub fn new_alternation<I>(edge_finders: I, count: TraversalCount) -> Self
+    where
+        I: IntoIterator<Item = EdgeFinder<T, E, A, L>>,
+    {
+        let mut edge_finders = edge_finders.into_iter();
+        // An empty alternation has nothing to follow, so it matches no edge ids rather than every edge
+        let first_edge_finder = edge_finders
+            .next()
+            .unwrap_or_else(|| EdgeFinder::new().edge_ids([]));
+        Self {
+            alternative_edge_finders: edge_finders.collect(),
+            ..Self::new(first_edge_finder, count)
+        }
+    }
+
+    pub fn new_repeat_until(
+        edge_finder: EdgeFinder<T, E, A, L>,
+        until: NodeFilter<T, E, A, L>,
+    ) -> Self {
+        Self {
+            until: Some(until),
+            ..Self::new(edge_finder, TraversalCount::RepeatUntil)
+        }
+    }
+
+    pub fn name(&self, name: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self.clone()
+        }
+    }
+
+    pub fn or_edge_finder(&self, edge_finder: EdgeFinder<T, E, A, L>) -> Self {
+        let mut new_alternatives = self.alternative_edge_finders.clone();
+        new_alternatives.push_back(edge_finder);
+        Self {
+            alternative_edge_finders: new_alternatives,
+            ..self.clone()
+        }
+    }
+
+    // Multiple calls are combined, so every given filter must match
+    pub fn filter_nodes(&self, node_filter: NodeFilter<T, E, A, L>) -> Self {
+        let node_filter = match &self.node_filter {
+            Some(existing_filter) => existing_filter.and(node_filter),
+            None => node_filter,
+        };
+        Self {
+            node_filter: Some(node_filter),
+            ..self.clone()
+        }
+    }
+
+    pub fn accepts_node(&self, node: &TraversalNode<T, E, A, L>) -> bool {
+        self.node_filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches_traversal_node(node))
+    }
+
+    pub fn is_satisfied(&self, node: &TraversalNode<T, E, A, L>, step_index: usize) -> bool {
+        match (&self.count, &self.until) {
+            (TraversalCount::RepeatUntil, Some(until)) => {
+                step_index > 0 && until.matches_traversal_node(node)
+            }
+            (count, _) => count.is_satisfied(step_index),
+        }
+    }
+
+    pub fn upper_bound_met(&self, node: &TraversalNode<T, E, A, L>, step_index: usize) -> bool {
+        match &self.count {
+            // The first matching node ends the repetition
+            TraversalCount::RepeatUntil => self.is_satisfied(node, step_index),
+            count => count.upper_bound_met(step_index),
+        }
+    }
+
+    pub fn search_for_edges(
+        &self,
+        node: &TraversalNode<T, E, A, L>,
+    ) -> Option<HashSet<EdgeDescriptor<E>>> {
+        let found_edges = iter::once(&self.edge_finder)
+            .chain(self.alternative_edge_finders.iter())
+            .filter_map(|edge_finder| node.node.search_for_edge(edge_finder))
+            .fold(HashSet::new(), |acc, edges| acc.union(edges));
+        if found_edges.is_empty() {
+            return None;
+        }
+        Some(found_edges)
     }
 }
 
-// fn get_target_node_if_matches<T: GraphTraits, E: GraphTraits, A: GraphTraits>(
+// fn get_target_node_if_matches<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits>(
 //     edge: &EdgeDescriptor<E>,
-//     edge_finder: &EdgeFinder<T, E, A>,
-//     get_node_closure: GetNodeClosure<T, E, A>,
-// ) -> Option<ReadReactiveNode<T, E, A>> {
+//     edge_finder: &EdgeFinder<T, E, A, L>,
+//     get_node_closure: GetNodeClosure<T, E, A, L>,
+// ) -> Option<ReadReactiveNode<T, E, A, L>> {
 //     if edge_finder.matches(edge) {
 //         Some(edge.target)
 //     } else {
@@ -1,11 +1,16 @@
 use im::Vector;
 
-use crate::prelude::GraphTraits;
+use crate::prelude::{EdgeTraits, GraphTraits};
 
 use super::traversal_node::TraversalNode;
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
-pub struct TraversalStepResult<T: GraphTraits, E: GraphTraits, A: GraphTraits> {
-    pub entry: TraversalNode<T, E, A>,
-    pub endpoints: Vector<TraversalNode<T, E, A>>,
+pub struct TraversalStepResult<
+    T: GraphTraits,
+    E: EdgeTraits,
+    A: GraphTraits,
+    L: GraphTraits = String,
+> {
+    pub entry: TraversalNode<T, E, A, L>,
+    pub endpoints: Vector<TraversalNode<T, E, A, L>>,
 }
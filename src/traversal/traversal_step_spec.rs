@@ -0,0 +1,119 @@
+use im::Vector;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+// A closure-free description of a NodeFilter. Data predicates are referred to by their key.
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Serialize, Deserialize)]
+pub enum NodeFilterSpec<E: EdgeTraits, L: GraphTraits = String> {
+    HasLabel(L),
+    Data(String),
+    HasEdge(Box<EdgeFinderSpec<E, L>>),
+    All(Vector<NodeFilterSpec<E, L>>),
+    Any(Vector<NodeFilterSpec<E, L>>),
+    Not(Box<NodeFilterSpec<E, L>>),
+}
+
+impl<E: EdgeTraits, L: GraphTraits> NodeFilterSpec<E, L> {
+    pub fn bind<T: GraphTraits, A: GraphTraits>(
+        &self,
+        registry: &PredicateRegistry<T, E, A, L>,
+        get_node_closure: &GetNodeClosure<T, E, A, L>,
+    ) -> Result<NodeFilter<T, E, A, L>, GraphError> {
+        let bind_all = |filters: &Vector<NodeFilterSpec<E, L>>| {
+            filters
+                .iter()
+                .map(|filter| filter.bind(registry, get_node_closure))
+                .collect::<Result<Vector<_>, _>>()
+        };
+        Ok(match self {
+            Self::HasLabel(label) => NodeFilter::HasLabel(label.clone()),
+            Self::Data(key) => NodeFilter::Data(registry.get_data_predicate(key)?),
+            Self::HasEdge(edge_finder) => NodeFilter::HasEdge(Box::new(
+                edge_finder.bind(registry, get_node_closure.clone())?,
+            )),
+            Self::All(filters) => NodeFilter::All(bind_all(filters)?),
+            Self::Any(filters) => NodeFilter::Any(bind_all(filters)?),
+            Self::Not(filter) => {
+                NodeFilter::Not(Box::new(filter.bind(registry, get_node_closure)?))
+            }
+        })
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> NodeFilter<T, E, A, L> {
+    pub fn to_spec(&self) -> Result<NodeFilterSpec<E, L>, GraphError> {
+        let all_to_spec = |filters: &Vector<NodeFilter<T, E, A, L>>| {
+            filters
+                .iter()
+                .map(|filter| filter.to_spec())
+                .collect::<Result<Vector<_>, _>>()
+        };
+        Ok(match self {
+            Self::HasLabel(label) => NodeFilterSpec::HasLabel(label.clone()),
+            Self::Data(predicate) => NodeFilterSpec::Data(predicate.to_spec()?),
+            Self::HasEdge(edge_finder) => NodeFilterSpec::HasEdge(Box::new(edge_finder.to_spec()?)),
+            Self::All(filters) => NodeFilterSpec::All(all_to_spec(filters)?),
+            Self::Any(filters) => NodeFilterSpec::Any(all_to_spec(filters)?),
+            Self::Not(filter) => NodeFilterSpec::Not(Box::new(filter.to_spec()?)),
+        })
+    }
+}
+
+// A closure-free description of a TraversalStep
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Serialize, Deserialize)]
+pub struct TraversalStepSpec<E: EdgeTraits, L: GraphTraits = String> {
+    pub edge_finder: EdgeFinderSpec<E, L>,
+    pub alternative_edge_finders: Vector<EdgeFinderSpec<E, L>>,
+    pub count: TraversalCount,
+    pub node_filter: Option<NodeFilterSpec<E, L>>,
+    pub until: Option<NodeFilterSpec<E, L>>,
+    pub name: Option<String>,
+}
+
+impl<E: EdgeTraits, L: GraphTraits> TraversalStepSpec<E, L> {
+    pub fn bind<T: GraphTraits, A: GraphTraits>(
+        &self,
+        registry: &PredicateRegistry<T, E, A, L>,
+        get_node_closure: &GetNodeClosure<T, E, A, L>,
+    ) -> Result<TraversalStep<T, E, A, L>, GraphError> {
+        let bind_filter = |filter: &Option<NodeFilterSpec<E, L>>| {
+            filter
+                .as_ref()
+                .map(|filter| filter.bind(registry, get_node_closure))
+                .transpose()
+        };
+        Ok(TraversalStep {
+            edge_finder: self.edge_finder.bind(registry, get_node_closure.clone())?,
+            alternative_edge_finders: self
+                .alternative_edge_finders
+                .iter()
+                .map(|edge_finder| edge_finder.bind(registry, get_node_closure.clone()))
+                .collect::<Result<_, _>>()?,
+            count: self.count.clone(),
+            node_filter: bind_filter(&self.node_filter)?,
+            until: bind_filter(&self.until)?,
+            name: self.name.clone(),
+        })
+    }
+}
+
+impl<T: GraphTraits, E: EdgeTraits, A: GraphTraits, L: GraphTraits> TraversalStep<T, E, A, L> {
+    pub fn to_spec(&self) -> Result<TraversalStepSpec<E, L>, GraphError> {
+        let filter_to_spec = |filter: &Option<NodeFilter<T, E, A, L>>| {
+            filter.as_ref().map(|filter| filter.to_spec()).transpose()
+        };
+        Ok(TraversalStepSpec {
+            edge_finder: self.edge_finder.to_spec()?,
+            alternative_edge_finders: self
+                .alternative_edge_finders
+                .iter()
+                .map(|edge_finder| edge_finder.to_spec())
+                .collect::<Result<_, _>>()?,
+            count: self.count.clone(),
+            node_filter: filter_to_spec(&self.node_filter)?,
+            until: filter_to_spec(&self.until)?,
+            name: self.name.clone(),
+        })
+    }
+}